@@ -6,12 +6,18 @@ use ethers::{
     prelude::*,
     utils::{Anvil, AnvilInstance},
 };
-use ethers_solc::artifacts::CompactContractBytecode;
 use eyre::Result;
 use serde::{Deserialize, Serialize};
-use std::{fs::File, io::BufReader, path::PathBuf, sync::Arc, time::Duration};
+use solidity_deploy_rs::cli_config::TxStoreConfig;
+use solidity_deploy_rs::deployer::Deployer;
+use solidity_deploy_rs::error::DeployError;
+use solidity_deploy_rs::tx_store::{self, TxFilter, TxStatus, DEFAULT_TX_DB};
+use std::{sync::Arc, time::Duration};
 use tokio::sync::Mutex;
 
+// Contract the server stores its messages in, compiled from `contracts/`
+const CONTRACT_NAME: &str = "MessageStorage";
+
 // Define a struct for the request body of /api/store-message
 #[derive(Deserialize, Debug)]
 struct StoreMessageRequest {
@@ -24,6 +30,14 @@ struct RetrieveMessagesResponse {
     messages: Vec<String>,
 }
 
+// Query of GET /api/transactions, every field optional
+#[derive(Deserialize, Debug)]
+struct TransactionsQuery {
+    chain_id: Option<u64>,
+    status: Option<String>,
+    limit: Option<usize>,
+}
+
 // Define the contract binding - expects ABI at compile time
 abigen!(
     MessageStorage,
@@ -88,8 +102,11 @@ async fn store_message_handler(
 
     let message_clone = message_to_store.clone();
 
-    match contract.write_message(message_to_store).send().await {
+    let call = contract.write_message(message_to_store);
+    match call.send().await {
         Ok(pending_tx) => {
+            let tx_hash = pending_tx.tx_hash();
+            tx_store::record_sent(&*contract.client(), "writeMessage", &call.tx, tx_hash).await;
             log::info!(
                 "Transaction sent for message '{}', waiting for confirmation...",
                 message_clone
@@ -97,6 +114,7 @@ async fn store_message_handler(
             // Wait for confirmation with a timeout
             match pending_tx.interval(Duration::from_millis(100)).await {
                 Ok(Some(receipt)) => {
+                    tx_store::record_receipt(&receipt);
                     log::info!(
                         "Message '{}' stored successfully! Transaction hash: {:?}",
                         message_clone,
@@ -105,6 +123,7 @@ async fn store_message_handler(
                     Ok(HttpResponse::Ok().json(serde_json::json!({ "status": "success", "tx_hash": receipt.transaction_hash })))
                 }
                 Ok(None) => {
+                    tx_store::record_dropped(tx_hash);
                     log::error!(
                         "Transaction for message '{}' dropped from mempool",
                         message_clone
@@ -129,12 +148,18 @@ async fn store_message_handler(
                 message_clone,
                 e
             );
-            // Check for common contract errors (like revert)
-            if let Some(contract_error) = e.as_revert() {
-                log::error!("Contract reverted: {:?}", contract_error);
-                Ok(HttpResponse::BadRequest().json(serde_json::json!({ "status": "error", "message": format!("Contract execution failed: {:?}", contract_error) })))
-            } else {
-                Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "status": "error", "message": format!("Failed to send transaction: {:?}", e) })))
+            // A revert is the caller's fault, anything else the server's
+            match DeployError::from_middleware(&e, Some(contract.abi())) {
+                DeployError::Reverted { reason } => {
+                    log::error!("Contract reverted: {}", reason);
+                    Ok(HttpResponse::BadRequest().json(serde_json::json!({ "status": "error", "message": format!("Contract execution failed: {}", reason), "reason": reason })))
+                }
+                DeployError::Timeout { message } => {
+                    Ok(HttpResponse::GatewayTimeout().json(serde_json::json!({ "status": "error", "message": format!("Node did not answer: {}", message) })))
+                }
+                error => {
+                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "status": "error", "message": format!("Failed to send transaction: {}", error) })))
+                }
             }
         }
     }
@@ -156,19 +181,71 @@ async fn retrieve_messages_handler(
         }
         Err(e) => {
             log::error!("Failed to call get_messages: {}", e);
-            if let Some(contract_error) = e.as_revert() {
-                log::error!(
-                    "Contract reverted during get_messages: {:?}",
-                    contract_error
-                );
-                Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "status": "error", "message": format!("Contract execution failed during retrieval: {:?}", contract_error) })))
-            } else {
-                Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "status": "error", "message": format!("Failed to retrieve messages: {}", e) })))
+            match DeployError::from_middleware(&e, Some(contract.abi())) {
+                DeployError::Reverted { reason } => {
+                    log::error!("Contract reverted during get_messages: {}", reason);
+                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "status": "error", "message": format!("Contract execution failed during retrieval: {}", reason), "reason": reason })))
+                }
+                DeployError::Timeout { message } => {
+                    Ok(HttpResponse::GatewayTimeout().json(serde_json::json!({ "status": "error", "message": format!("Node did not answer: {}", message) })))
+                }
+                error => {
+                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "status": "error", "message": format!("Failed to retrieve messages: {}", error) })))
+                }
             }
         }
     }
 }
 
+// Handler for GET /api/transactions, reading the transaction database the
+// CLI records its transactions in as well
+async fn transactions_handler(query: web::Query<TransactionsQuery>) -> ActixResult<impl Responder> {
+    let status = match query
+        .status
+        .as_deref()
+        .map(str::parse::<TxStatus>)
+        .transpose()
+    {
+        Ok(status) => status,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest()
+                .json(serde_json::json!({ "status": "error", "message": e.to_string() })))
+        }
+    };
+    let filter = TxFilter {
+        chain_id: query.chain_id,
+        status,
+        limit: Some(query.limit.unwrap_or(100)),
+    };
+    match tx_store::open_configured().and_then(|store| store.list(&filter)) {
+        Ok(transactions) => Ok(HttpResponse::Ok().json(transactions)),
+        Err(e) => {
+            log::error!("Failed to read the transaction database: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "status": "error", "message": format!("Failed to read transactions: {}", e) })))
+        }
+    }
+}
+
+// Handler for GET /api/transactions/{hash}
+async fn transaction_handler(path: web::Path<String>) -> ActixResult<impl Responder> {
+    let hash: H256 = match path.into_inner().parse() {
+        Ok(hash) => hash,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({ "status": "error", "message": format!("Invalid transaction hash: {}", e) })))
+        }
+    };
+    match tx_store::open_configured().and_then(|store| store.get(hash)) {
+        Ok(Some(transaction)) => Ok(HttpResponse::Ok().json(transaction)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(
+            serde_json::json!({ "status": "error", "message": format!("Unknown transaction {:?}", hash) }),
+        )),
+        Err(e) => {
+            log::error!("Failed to read the transaction database: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "status": "error", "message": format!("Failed to read transaction: {}", e) })))
+        }
+    }
+}
+
 // Function to compile and deploy the contract
 async fn setup_contract() -> Result<(
     MessageStorage<SignerMiddleware<Provider<Http>, Wallet<k256::ecdsa::SigningKey>>>,
@@ -183,50 +260,19 @@ async fn setup_contract() -> Result<(
     let provider =
         Provider::<Http>::try_from(anvil.endpoint())?.interval(Duration::from_millis(10u64));
 
-    let balance = provider.clone().get_balance(wallet.address(), None).await?;
+    let balance = provider.get_balance(wallet.address(), None).await?;
     log::info!("Address: {}, balance: {}", wallet.address(), balance);
 
-    log::info!("Loading contract ABI and bytecode from file...");
-    let abi_path = PathBuf::from("./contracts/artifacts/MessageStorage.sol/MessageStorage.json");
-    let file = File::open(&abi_path)
-        .map_err(|e| eyre::eyre!("Failed to open ABI file {:?}: {}", abi_path, e))?;
-    if abi_path.exists() {
-        println!("{}", abi_path.display())
-    }
-    let reader = BufReader::new(file);
-    let contract_artifact: CompactContractBytecode = serde_json::from_reader(reader)
-        .map_err(|e| eyre::eyre!("Failed to parse ABI file {:?}: {}", abi_path, e))?;
-
-    let abi = contract_artifact
-        .abi
-        .clone()
-        .ok_or_else(|| eyre::eyre!("ABI not found in artifact file"))?;
-
-    let bytecode: Bytes = contract_artifact
-        .bytecode
-        .clone()
-        .ok_or_else(|| eyre::eyre!("Bytecode not found in artifact file"))?
-        .object
-        .into_bytes()
-        .ok_or_else(|| eyre::eyre!("Bytecode object is not valid bytes"))?;
-
-    let client = Arc::new(SignerMiddleware::new(
-        provider,
-        wallet.with_chain_id(anvil.chain_id()),
-    ));
-
     log::info!("Deploying contract...");
-    let factory = ContractFactory::new(abi.clone(), bytecode, client.clone());
-    let deployer = factory
-        .deploy(())? // constructor arguments hire
-        .legacy();
-
-    let contract_instance = deployer.send().await?;
-
-    let contract_address = contract_instance.address();
-    log::info!("Contract deployed at address: {:?}", contract_address);
-
-    let contract = MessageStorage::new(contract_address, client.clone());
+    let (contract, receipt): (MessageStorage<_>, _) = Deployer::new(provider)
+        .with_signer(wallet)
+        .deploy(CONTRACT_NAME, ()) // no constructor arguments
+        .await?;
+    log::info!(
+        "Contract deployed at address: {:?} in block {:?}",
+        contract.address(),
+        receipt.block_number
+    );
 
     // Subscribe to events
     contract.clone().subscribe_to_events().await?;
@@ -238,6 +284,13 @@ async fn setup_contract() -> Result<(
 async fn main() -> Result<()> {
     // Use RUST_LOG=info cargo run --bin server
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    // Shared with the CLI, which records its transactions in the same database
+    tx_store::init(TxStoreConfig {
+        tx_db: std::env::var("TX_DB")
+            .unwrap_or_else(|_| DEFAULT_TX_DB.to_owned())
+            .into(),
+        no_tx_db: false,
+    });
 
     let (contract_instance, _anvil_instance) =
         setup_contract().await.expect("Failed to setup contract");
@@ -266,6 +319,11 @@ async fn main() -> Result<()> {
                 "/api/retrieve-messages",
                 web::get().to(retrieve_messages_handler),
             )
+            .route("/api/transactions", web::get().to(transactions_handler))
+            .route(
+                "/api/transactions/{hash}",
+                web::get().to(transaction_handler),
+            )
     })
     .bind((server_address, server_port))?
     .run()
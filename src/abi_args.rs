@@ -0,0 +1,93 @@
+use ethers::abi::token::{LenientTokenizer, Tokenizer};
+use ethers::abi::{Function, Param, Token};
+use ethers::types::{Bytes, I256};
+use eyre::{eyre, Result};
+
+// Parse raw command line values into ABI tokens according to the given inputs.
+// Values use the usual solidity literal forms: `42`, `0xabc...`, `hello`,
+// `[1,2,3]` for arrays and `(1,0xabc...)` for tuples.
+pub fn parse_tokens(inputs: &[Param], values: &[String]) -> Result<Vec<Token>> {
+    if inputs.len() != values.len() {
+        return Err(eyre!(
+            "Expected {} argument(s) {}, got {}",
+            inputs.len(),
+            describe_params(inputs),
+            values.len()
+        ));
+    }
+
+    inputs
+        .iter()
+        .zip(values)
+        .map(|(param, value)| {
+            LenientTokenizer::tokenize(&param.kind, value).map_err(|e| {
+                eyre!(
+                    "Invalid value {value:?} for argument `{}` of type {}: {e}",
+                    param.name,
+                    param.kind
+                )
+            })
+        })
+        .collect()
+}
+
+// Calldata of a call to `function`, its selector followed by the encoded
+// arguments, as `send` sends it and `abi-encode` prints it
+pub fn encode_call(function: &Function, tokens: &[Token]) -> Result<Bytes> {
+    Ok(function.encode_input(tokens)?.into())
+}
+
+// Render parameters as a solidity-like signature, e.g. `(uint256 amount, address to)`
+pub fn describe_params(inputs: &[Param]) -> String {
+    let params = inputs
+        .iter()
+        .map(|param| {
+            if param.name.is_empty() {
+                param.kind.to_string()
+            } else {
+                format!("{} {}", param.kind, param.name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("({params})")
+}
+
+// Render a decoded token in the same literal form `parse_tokens` accepts
+pub fn format_token(token: &Token) -> String {
+    match token {
+        Token::Address(address) => format!("{address:?}"),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => {
+            format!("0x{}", ethers::utils::hex::encode(bytes))
+        }
+        Token::Int(value) => I256::from_raw(*value).to_string(),
+        Token::Uint(value) => value.to_string(),
+        Token::Bool(value) => value.to_string(),
+        Token::String(value) => value.clone(),
+        Token::FixedArray(tokens) | Token::Array(tokens) => {
+            format!("[{}]", format_tokens(tokens))
+        }
+        Token::Tuple(tokens) => format!("({})", format_tokens(tokens)),
+    }
+}
+
+fn format_tokens(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .map(format_token)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Print named values one per line, e.g. decoded return values
+pub fn print_named_tokens(params: &[Param], tokens: &[Token]) {
+    for (index, token) in tokens.iter().enumerate() {
+        match params.get(index) {
+            Some(param) if !param.name.is_empty() => {
+                println!("  {} {} = {}", param.kind, param.name, format_token(token))
+            }
+            Some(param) => println!("  {} = {}", param.kind, format_token(token)),
+            None => println!("  {}", format_token(token)),
+        }
+    }
+}
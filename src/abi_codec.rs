@@ -0,0 +1,89 @@
+use crate::abi_args::{encode_call, parse_tokens, print_named_tokens};
+use crate::cli_config::{AbiDecodeConfig, AbiEncodeConfig};
+use crate::contract_abi::{find_function, function_signature, load_abi, parse_signature};
+use ethers::abi::{Abi, Function};
+use ethers::utils::hex;
+use eyre::{eyre, ContextCompat, Result};
+use std::path::Path;
+
+// Entry point of `abi-encode`: print the calldata `send` would send for the
+// method and arguments
+pub async fn abi_encode(config: AbiEncodeConfig) -> Result<()> {
+    let function = resolve_function(
+        &config.signature,
+        config.abi.as_deref(),
+        config.contract_name.as_deref(),
+        |abi| find_function(abi, &config.signature, config.args.len()).cloned(),
+    )
+    .await?;
+    let tokens = parse_tokens(&function.inputs, &config.args)?;
+    let calldata = encode_call(&function, &tokens)?;
+    let encoded = match config.no_selector {
+        true => &calldata[4..],
+        false => &calldata[..],
+    };
+    println!("0x{}", hex::encode(encoded));
+    Ok(())
+}
+
+// Entry point of `abi-decode`: print the arguments of calldata, or the
+// values of return data with `--output`
+pub async fn abi_decode(config: AbiDecodeConfig) -> Result<()> {
+    // With the method looked up by name, overloads are told apart by selector
+    let selector = config.data.get(..4);
+    let function = resolve_function(
+        &config.signature,
+        config.abi.as_deref(),
+        config.contract_name.as_deref(),
+        |abi| {
+            abi.functions()
+                .filter(|function| {
+                    function.name == config.signature
+                        || function_signature(function) == config.signature.replace(' ', "")
+                })
+                .find(|function| config.output || Some(&function.short_signature()[..]) == selector)
+                .cloned()
+                .with_context(|| format!("No function {} decodes the data", config.signature))
+        },
+    )
+    .await?;
+
+    println!("{}", function_signature(&function));
+    if config.output {
+        if function.outputs.is_empty() {
+            return Err(eyre!(
+                "{} declares no outputs, add them as `returns (...)`",
+                config.signature
+            ));
+        }
+        let tokens = function.decode_output(&config.data)?;
+        print_named_tokens(&function.outputs, &tokens);
+    } else {
+        let selector = selector.context("Calldata is shorter than a 4-byte function selector")?;
+        if selector != function.short_signature() {
+            return Err(eyre!(
+                "Selector 0x{} is not that of {}, 0x{}",
+                hex::encode(selector),
+                function_signature(&function),
+                hex::encode(function.short_signature())
+            ));
+        }
+        let tokens = function.decode_input(&config.data[4..])?;
+        print_named_tokens(&function.inputs, &tokens);
+    }
+    Ok(())
+}
+
+// Function parsed from the signature alone, or looked up in the ABI file or
+// project contract when one is given
+async fn resolve_function(
+    signature: &str,
+    abi_path: Option<&Path>,
+    contract_name: Option<&str>,
+    find: impl FnOnce(&Abi) -> Result<Function>,
+) -> Result<Function> {
+    match (abi_path, contract_name) {
+        (None, None) => parse_signature(signature),
+        _ => find(&load_abi(abi_path, contract_name).await?),
+    }
+}
@@ -0,0 +1,133 @@
+use crate::cli_config::AbiDiffConfig;
+use crate::contract_abi::{event_signature, function_signature, load_abi, load_abi_file};
+use crate::manifest::Deployment;
+use ethers::abi::{Abi, Event, Function};
+use eyre::{eyre, Result};
+use std::collections::BTreeMap;
+
+// Entry point of the `abi-diff` subcommand: compare the compiled ABI of a
+// contract with a previous one, from an artifact or the deployment manifest.
+// Removed or changed functions and events break the callers and indexers of
+// the deployed contract, additions do not.
+pub async fn abi_diff(config: AbiDiffConfig) -> Result<()> {
+    let (previous, source) = match (&config.abi, &config.chain) {
+        (Some(path), _) => (load_abi_file(path)?, path.display().to_string()),
+        (None, Some(chain)) => {
+            let name = config.name.as_deref().unwrap_or(&config.contract_name);
+            let deployment = Deployment::load(chain.chain_id, name)?;
+            // A proxy is called with the ABI of its implementation
+            let abi = match &deployment.proxy {
+                Some(proxy) => Deployment::load(chain.chain_id, &proxy.implementation_name)?.abi,
+                None => deployment.abi,
+            };
+            (abi, format!("{name} on {}", chain.name))
+        }
+        (None, None) => return Err(eyre!("Either --abi or --chain is required")),
+    };
+    let current = load_abi(None, Some(&config.contract_name)).await?;
+
+    println!("{}", "=".repeat(80));
+    println!("ABI DIFF {source} -> {}", config.contract_name);
+    let mut breaking = 0;
+    let functions = |abi: &Abi| -> BTreeMap<String, Function> {
+        abi.functions()
+            .map(|function| (function_signature(function), function.clone()))
+            .collect()
+    };
+    let (old, new) = (functions(&previous), functions(&current));
+    for (signature, function) in &old {
+        match new.get(signature) {
+            None => {
+                breaking += 1;
+                println!(
+                    "REMOVED function {signature}{}",
+                    overloads(&new, &function.name)
+                );
+            }
+            Some(changed) if output_kinds(changed) != output_kinds(function) => {
+                breaking += 1;
+                println!(
+                    "CHANGED function {signature} returns ({}) instead of ({})",
+                    output_kinds(changed),
+                    output_kinds(function)
+                );
+            }
+            Some(changed) if changed.state_mutability != function.state_mutability => {
+                breaking += 1;
+                println!(
+                    "CHANGED function {signature} is {:?} instead of {:?}",
+                    changed.state_mutability, function.state_mutability
+                );
+            }
+            Some(_) => {}
+        }
+    }
+    for signature in new.keys().filter(|signature| !old.contains_key(*signature)) {
+        println!("ADDED function {signature}");
+    }
+
+    let events = |abi: &Abi| -> BTreeMap<String, Event> {
+        abi.events()
+            .map(|event| (event_signature(event), event.clone()))
+            .collect()
+    };
+    let (old, new) = (events(&previous), events(&current));
+    for (signature, event) in &old {
+        match new.get(signature) {
+            None => {
+                breaking += 1;
+                println!("REMOVED event {signature}");
+            }
+            Some(changed) if topics(changed) != topics(event) => {
+                // Indexed parameters move between topics and data
+                breaking += 1;
+                println!("CHANGED event {signature} has different indexed parameters");
+            }
+            Some(_) => {}
+        }
+    }
+    for signature in new.keys().filter(|signature| !old.contains_key(*signature)) {
+        println!("ADDED event {signature}");
+    }
+
+    if breaking > 0 {
+        return Err(eyre!(
+            "{breaking} incompatible ABI change(s) in {} since {source}",
+            config.contract_name
+        ));
+    }
+    println!("ABI is compatible with {source}");
+    Ok(())
+}
+
+// e.g. ` (now transfer(address,uint256,bytes))` for a function whose
+// parameters changed
+fn overloads(functions: &BTreeMap<String, Function>, name: &str) -> String {
+    let overloads = functions
+        .iter()
+        .filter(|(_, function)| function.name == name)
+        .map(|(signature, _)| signature.as_str())
+        .collect::<Vec<_>>();
+    match overloads.is_empty() {
+        true => String::new(),
+        false => format!(" (now {})", overloads.join(", ")),
+    }
+}
+
+// e.g. `string,address`, parameter names are not part of the ABI encoding
+fn output_kinds(function: &Function) -> String {
+    function
+        .outputs
+        .iter()
+        .map(|output| output.kind.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Which parameters are indexed, and whether topic0 is the signature
+fn topics(event: &Event) -> (Vec<bool>, bool) {
+    (
+        event.inputs.iter().map(|input| input.indexed).collect(),
+        event.anonymous,
+    )
+}
@@ -0,0 +1,163 @@
+use crate::cli_config::{AbiConfig, AbiFormat, CompilerConfig, ExportTsConfig};
+use crate::compiler::{compile, contracts_path};
+use crate::contract_abi::load_abi;
+use crate::manifest::Deployment;
+use ethers::abi::{Abi, EventParam, Param, StateMutability};
+use eyre::Result;
+use std::collections::BTreeMap;
+
+// Entry point of the `abi` subcommand
+pub async fn export_abi(config: AbiConfig) -> Result<()> {
+    let abi = load_abi(None, Some(&config.contract_name)).await?;
+    let exported = match config.format {
+        AbiFormat::Json => serde_json::to_string(&abi)?,
+        AbiFormat::Pretty => serde_json::to_string_pretty(&abi)?,
+        AbiFormat::Human => human_readable(&abi).join("\n"),
+    };
+    match config.out {
+        Some(out) => {
+            if let Some(dir) = out.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&out, format!("{exported}\n"))?;
+            println!(
+                "ABI of {} written to {}",
+                config.contract_name,
+                out.display()
+            );
+        }
+        None => println!("{exported}"),
+    }
+    Ok(())
+}
+
+// Entry point of the `export-ts` subcommand: write one TypeScript module per
+// contract with its ABI as a `const` and the addresses it is deployed at
+pub async fn export_typescript(config: ExportTsConfig) -> Result<()> {
+    // Compiler settings do not affect the ABI
+    let output = compile(contracts_path(), None, &CompilerConfig::default()).await?;
+    let deployments = Deployment::list_all()?;
+    std::fs::create_dir_all(&config.out)?;
+
+    for (id, artifact) in output.into_artifacts() {
+        if !config.contract_names.is_empty() && !config.contract_names.contains(&id.name) {
+            continue;
+        }
+        let Some(abi) = artifact.abi else {
+            continue;
+        };
+
+        // Addresses per deployment name, then per chain id. Plans may deploy
+        // the same contract under several names.
+        let mut addresses: BTreeMap<&str, BTreeMap<u64, String>> = BTreeMap::new();
+        for deployment in deployments
+            .iter()
+            .filter(|deployment| deployment.contract_name == id.name)
+        {
+            addresses
+                .entry(deployment.name.as_str())
+                .or_default()
+                .insert(deployment.chain_id, format!("{:?}", deployment.address));
+        }
+
+        let mut module = String::from("// Generated by solidity-deploy-rs, do not edit\n\n");
+        module.push_str(&format!(
+            "export const {}Abi = {} as const;\n",
+            ts_identifier(&id.name),
+            serde_json::to_string_pretty(&abi.abi)?
+        ));
+        for (name, chains) in addresses {
+            module.push_str(&format!(
+                "\nexport const {}Addresses = {{\n",
+                ts_identifier(name)
+            ));
+            for (chain_id, address) in chains {
+                module.push_str(&format!("  {chain_id}: \"{address}\",\n"));
+            }
+            module.push_str("} as const;\n");
+        }
+
+        let path = config.out.join(format!("{}.ts", id.name));
+        std::fs::write(&path, module)?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+// `MessageStorage` -> `messageStorage`
+fn ts_identifier(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+// Human-readable ABI, one signature per line, e.g.
+// `function writeMessage(string message)` or
+// `function getMessage() view returns (string)`
+pub fn human_readable(abi: &Abi) -> Vec<String> {
+    let mut lines = vec![];
+    if let Some(constructor) = &abi.constructor {
+        lines.push(format!("constructor({})", params(&constructor.inputs)));
+    }
+    for function in abi.functions() {
+        let mut line = format!("function {}({})", function.name, params(&function.inputs));
+        match function.state_mutability {
+            StateMutability::Pure => line.push_str(" pure"),
+            StateMutability::View => line.push_str(" view"),
+            StateMutability::Payable => line.push_str(" payable"),
+            StateMutability::NonPayable => {}
+        }
+        if !function.outputs.is_empty() {
+            line.push_str(&format!(" returns ({})", params(&function.outputs)));
+        }
+        lines.push(line);
+    }
+    for event in abi.events() {
+        let mut line = format!("event {}({})", event.name, event_params(&event.inputs));
+        if event.anonymous {
+            line.push_str(" anonymous");
+        }
+        lines.push(line);
+    }
+    for error in abi.errors() {
+        lines.push(format!("error {}({})", error.name, params(&error.inputs)));
+    }
+    if abi.fallback {
+        lines.push("fallback()".to_owned());
+    }
+    if abi.receive {
+        lines.push("receive() payable".to_owned());
+    }
+    lines
+}
+
+fn params(params: &[Param]) -> String {
+    params
+        .iter()
+        .map(|param| named(param.kind.to_string(), &param.name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn event_params(params: &[EventParam]) -> String {
+    params
+        .iter()
+        .map(|param| {
+            let kind = match param.indexed {
+                true => format!("{} indexed", param.kind),
+                false => param.kind.to_string(),
+            };
+            named(kind, &param.name)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn named(kind: String, name: &str) -> String {
+    match name.is_empty() {
+        true => kind,
+        false => format!("{kind} {name}"),
+    }
+}
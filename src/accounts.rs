@@ -0,0 +1,66 @@
+use crate::cli_config::AccountsConfig;
+use crate::rpc;
+use crate::signer::{wallet_from_config, wallet_from_mnemonic, ANVIL_MNEMONIC};
+use crate::units::format_eth;
+use ethers::signers::Signer;
+use ethers::types::Address;
+use ethers_providers::Middleware;
+use eyre::{ContextCompat, Result};
+
+// Entry point of the `accounts` subcommand: list the accounts the signer
+// options stand for, with their balance and nonce when an RPC endpoint is
+// given. A mnemonic lists `--count` accounts, and no credentials at all the
+// default accounts of Anvil.
+pub async fn accounts(config: AccountsConfig) -> Result<()> {
+    let signer = &config.signer;
+    let single_account = signer.private_key.is_some()
+        || signer.keystore.is_some()
+        || signer.derivation_path.is_some();
+    let accounts: Vec<(String, Address)> = match (&signer.mnemonic, single_account) {
+        (_, true) => {
+            let wallet = wallet_from_config(signer)?.context("No credentials given")?;
+            vec![("-".to_owned(), wallet.address())]
+        }
+        (mnemonic, false) => {
+            let mnemonic = match mnemonic {
+                Some(mnemonic) => mnemonic.as_str(),
+                None => {
+                    println!("No credentials given, listing the default accounts of Anvil");
+                    ANVIL_MNEMONIC
+                }
+            };
+            (signer.account_index..signer.account_index + config.count)
+                .map(|index| {
+                    let wallet = wallet_from_mnemonic(mnemonic, None, index)?;
+                    Ok((index.to_string(), wallet.address()))
+                })
+                .collect::<Result<_>>()?
+        }
+    };
+
+    println!("{}", "=".repeat(80));
+    let Some(rpc_url) = &config.rpc_url else {
+        println!("{:<6} ADDRESS", "INDEX");
+        for (index, address) in &accounts {
+            println!("{index:<6} {address:?}");
+        }
+        return Ok(());
+    };
+    let provider = rpc::connect(rpc_url)?;
+    let chain_id = provider.get_chainid().await?;
+    println!("Chain id {chain_id}");
+    println!(
+        "{:<6} {:<42} {:>28} {:>8}",
+        "INDEX", "ADDRESS", "BALANCE", "NONCE"
+    );
+    for (index, address) in &accounts {
+        let balance = provider.get_balance(*address, None).await?;
+        let nonce = provider.get_transaction_count(*address, None).await?;
+        println!(
+            "{index:<6} {:<42} {:>28} {nonce:>8}",
+            format!("{address:?}"),
+            format_eth(balance)
+        );
+    }
+    Ok(())
+}
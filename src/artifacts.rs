@@ -0,0 +1,242 @@
+use crate::compiler::{compiler_version, contracts_path};
+use crate::deployer::find_unlinked_contract;
+use crate::manifest::CompilerInfo;
+use crate::storage_layout::find_storage_layout;
+use ethers::abi::Abi;
+use ethers_solc::artifacts::{BytecodeObject, CompactBytecode, Offsets, Settings, StorageLayout};
+use ethers_solc::{ConfigurableArtifacts, ProjectCompileOutput};
+use eyre::{eyre, ContextCompat, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// Where deployed bytecode comes from: the project compiled from source, or
+// artifacts an existing Hardhat build produced
+pub enum ContractSource {
+    Compiled(ProjectCompileOutput<ConfigurableArtifacts>),
+    Hardhat(Vec<HardhatArtifact>),
+}
+
+impl ContractSource {
+    // ABI and creation bytecode, which may still hold library placeholders
+    pub fn find(&self, contract_name: &str) -> Result<(Abi, CompactBytecode)> {
+        match self {
+            ContractSource::Compiled(output) => find_unlinked_contract(output, contract_name),
+            ContractSource::Hardhat(artifacts) => {
+                let artifact = find_hardhat_artifact(artifacts, contract_name)?;
+                Ok((
+                    artifact.abi.clone(),
+                    CompactBytecode {
+                        object: artifact.bytecode.clone(),
+                        source_map: None,
+                        link_references: artifact.link_references.clone(),
+                    },
+                ))
+            }
+        }
+    }
+
+    // Compiler version and settings a contract was built with. Hardhat builds
+    // record them in their build info.
+    pub fn compiler(&self, contract_name: &str, settings: &Settings) -> Result<CompilerInfo> {
+        match self {
+            ContractSource::Compiled(output) => Ok(CompilerInfo {
+                version: compiler_version(output, contract_name)?,
+                settings: settings.clone(),
+            }),
+            ContractSource::Hardhat(artifacts) => {
+                let artifact = find_hardhat_artifact(artifacts, contract_name)?;
+                let build_info = artifact.build_info()?;
+                let mut settings_with_libraries = build_info.input.settings;
+                settings_with_libraries.libraries = settings.libraries.clone();
+                Ok(CompilerInfo {
+                    version: build_info.solc_long_version,
+                    settings: settings_with_libraries,
+                })
+            }
+        }
+    }
+
+    pub fn storage_layout(&self, contract_name: &str) -> Option<StorageLayout> {
+        match self {
+            ContractSource::Compiled(output) => find_storage_layout(output, contract_name),
+            ContractSource::Hardhat(_) => None,
+        }
+    }
+
+    // Names of the project's own contracts that can be deployed, leaving out
+    // interfaces, abstract contracts and those of dependencies. Libraries
+    // come before the contracts linking them.
+    pub fn deployable(&self) -> Result<Vec<String>> {
+        // Contract name and the libraries it links
+        let contracts: BTreeMap<String, Vec<String>> = match self {
+            ContractSource::Compiled(output) => {
+                let root = std::fs::canonicalize(contracts_path())?;
+                output
+                    .clone()
+                    .into_artifacts()
+                    .filter(|(id, _)| {
+                        std::fs::canonicalize(&id.source)
+                            .is_ok_and(|source| source.starts_with(&root))
+                    })
+                    .filter_map(|(id, artifact)| {
+                        let bytecode = artifact.bytecode?;
+                        has_code(&bytecode.object)
+                            .then(|| (id.name, linked_libraries(&bytecode.link_references)))
+                    })
+                    .collect()
+            }
+            // Only the project's sources are loaded from a Hardhat build
+            ContractSource::Hardhat(artifacts) => artifacts
+                .iter()
+                .filter(|artifact| has_code(&artifact.bytecode))
+                .map(|artifact| {
+                    (
+                        artifact.contract_name.clone(),
+                        linked_libraries(&artifact.link_references),
+                    )
+                })
+                .collect(),
+        };
+        let mut ordered = vec![];
+        for name in contracts.keys() {
+            push_libraries_first(name, &contracts, &mut ordered);
+        }
+        Ok(ordered)
+    }
+}
+
+// Interfaces and abstract contracts compile to empty bytecode
+fn has_code(object: &BytecodeObject) -> bool {
+    match object {
+        BytecodeObject::Bytecode(bytes) => !bytes.is_empty(),
+        BytecodeObject::Unlinked(code) => !code.is_empty(),
+    }
+}
+
+fn linked_libraries(
+    link_references: &BTreeMap<String, BTreeMap<String, Vec<Offsets>>>,
+) -> Vec<String> {
+    link_references
+        .values()
+        .flat_map(|libraries| libraries.keys().cloned())
+        .collect()
+}
+
+// Add a contract after the libraries it links. Libraries of dependencies are
+// left out, they are deployed when linking.
+fn push_libraries_first(
+    name: &str,
+    contracts: &BTreeMap<String, Vec<String>>,
+    ordered: &mut Vec<String>,
+) {
+    if ordered.iter().any(|added| added == name) {
+        return;
+    }
+    if let Some(libraries) = contracts.get(name) {
+        for library in libraries {
+            push_libraries_first(library, contracts, ordered);
+        }
+        ordered.push(name.to_owned());
+    }
+}
+
+// A Hardhat artifact, `artifacts/contracts/<source>/<Contract>.json`
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HardhatArtifact {
+    pub contract_name: String,
+    pub source_name: String,
+    pub abi: Abi,
+    pub bytecode: BytecodeObject,
+    #[serde(default)]
+    pub link_references: BTreeMap<String, BTreeMap<String, Vec<Offsets>>>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HardhatDebugFile {
+    build_info: PathBuf,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HardhatBuildInfo {
+    solc_long_version: String,
+    input: HardhatBuildInput,
+}
+
+#[derive(Deserialize)]
+struct HardhatBuildInput {
+    settings: Settings,
+}
+
+impl HardhatArtifact {
+    // Build info referenced by the `<Contract>.dbg.json` next to the artifact
+    fn build_info(&self) -> Result<HardhatBuildInfo> {
+        let debug_path = self.path.with_extension("dbg.json");
+        let debug: HardhatDebugFile = serde_json::from_str(
+            &std::fs::read_to_string(&debug_path)
+                .map_err(|e| eyre!("No Hardhat debug file {debug_path:?}: {e}"))?,
+        )?;
+        let dir = debug_path.parent().unwrap_or(Path::new("."));
+        let build_info_path = dir.join(debug.build_info);
+        let content = std::fs::read_to_string(&build_info_path)
+            .map_err(|e| eyre!("Failed to read Hardhat build info {build_info_path:?}: {e}"))?;
+        serde_json::from_str(&content)
+            .map_err(|e| eyre!("Failed to parse Hardhat build info {build_info_path:?}: {e}"))
+    }
+}
+
+// Load every contract artifact of a Hardhat build. `dir` is the artifacts
+// folder, whose `contracts/` holds one folder per source file.
+pub fn load_hardhat_artifacts(dir: &Path) -> Result<Vec<HardhatArtifact>> {
+    let contracts_dir = dir.join("contracts");
+    if !contracts_dir.is_dir() {
+        return Err(eyre!("No Hardhat artifacts in {contracts_dir:?}"));
+    }
+    let mut artifacts = vec![];
+    let mut pending = vec![contracts_dir];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            if !file_name.ends_with(".json") || file_name.ends_with(".dbg.json") {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path)?;
+            let mut artifact: HardhatArtifact = serde_json::from_str(&content)
+                .map_err(|e| eyre!("Failed to parse Hardhat artifact {path:?}: {e}"))?;
+            artifact.path = path;
+            artifacts.push(artifact);
+        }
+    }
+    artifacts.sort_by(|a, b| a.contract_name.cmp(&b.contract_name));
+    Ok(artifacts)
+}
+
+fn find_hardhat_artifact<'a>(
+    artifacts: &'a [HardhatArtifact],
+    contract_name: &str,
+) -> Result<&'a HardhatArtifact> {
+    let mut matches = artifacts
+        .iter()
+        .filter(|artifact| artifact.contract_name == contract_name);
+    let artifact = matches
+        .next()
+        .with_context(|| format!("Contract {contract_name} not found in the Hardhat artifacts"))?;
+    if let Some(other) = matches.next() {
+        return Err(eyre!(
+            "Contract {contract_name} is defined in both {} and {}",
+            artifact.source_name,
+            other.source_name
+        ));
+    }
+    Ok(artifact)
+}
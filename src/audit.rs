@@ -0,0 +1,130 @@
+use crate::cli_config::AuditConfig;
+use crate::tx_store;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, H256, U256};
+use ethers::utils::keccak256;
+use ethers_providers::Middleware;
+use eyre::{eyre, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+// Audit settings of the run, set once the command line is parsed
+static AUDIT_CONFIG: OnceLock<AuditConfig> = OnceLock::new();
+
+pub fn init(config: AuditConfig) {
+    let _ = AUDIT_CONFIG.set(config);
+}
+
+// A broadcast transaction, one line of the audit log. Every entry holds the
+// hash of the line before it, so edited or removed lines break the chain.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditEntry<'a> {
+    timestamp: u64,
+    chain_id: u64,
+    from: Option<Address>,
+    action: &'a str,
+    transaction_hash: H256,
+    // None for a contract creation
+    to: Option<Address>,
+    value: U256,
+    nonce: Option<U256>,
+    calldata: Bytes,
+    previous: H256,
+    version: &'static str,
+}
+
+// Line of the audit log. The signature is an EIP-191 signature by the sender
+// of the same line without its `signature` field.
+#[derive(Serialize)]
+struct SignedEntry<'a> {
+    #[serde(flatten)]
+    entry: AuditEntry<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+// Append the broadcast transaction `tx` to the audit log, described by
+// `action`, e.g. the journal step, and record it in the transaction store.
+// The transaction is already sent, so a failure to record it is reported
+// without failing the run.
+pub async fn record<M: Middleware>(client: &M, action: &str, tx: &TypedTransaction, hash: H256)
+where
+    M::Error: 'static,
+{
+    tx_store::record_sent(client, action, tx, hash).await;
+    let Some(config) = AUDIT_CONFIG.get().filter(|config| !config.no_audit_log) else {
+        return;
+    };
+    if let Err(e) = append(client, config, action, tx, hash).await {
+        warn!("Failed to record transaction {hash:?} in the audit log: {e}");
+    }
+}
+
+async fn append<M: Middleware>(
+    client: &M,
+    config: &AuditConfig,
+    action: &str,
+    tx: &TypedTransaction,
+    hash: H256,
+) -> Result<()>
+where
+    M::Error: 'static,
+{
+    let path = &config.audit_log;
+    let previous = match std::fs::read_to_string(path) {
+        Ok(content) => content
+            .lines()
+            .last()
+            .map(|line| H256::from(keccak256(line.as_bytes())))
+            .unwrap_or_default(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => H256::zero(),
+        Err(e) => return Err(eyre!("Failed to read {}: {e}", path.display())),
+    };
+    let chain_id = match tx.chain_id() {
+        Some(chain_id) => chain_id.as_u64(),
+        None => client.get_chainid().await?.as_u64(),
+    };
+    let from = tx.from().copied().or_else(|| client.default_sender());
+    let entry = AuditEntry {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        chain_id,
+        from,
+        action,
+        transaction_hash: hash,
+        to: tx.to().and_then(|to| to.as_address()).copied(),
+        value: tx.value().copied().unwrap_or_default(),
+        nonce: tx.nonce().copied(),
+        calldata: tx.data().cloned().unwrap_or_default(),
+        previous,
+        version: env!("CARGO_PKG_VERSION"),
+    };
+
+    let signature = match (config.audit_sign, from) {
+        (true, Some(from)) => {
+            let unsigned = serde_json::to_string(&entry)?;
+            let signature = client
+                .sign(unsigned.into_bytes(), &from)
+                .await
+                .map_err(|e| eyre!("Failed to sign the audit entry: {e}"))?;
+            Some(format!("0x{signature}"))
+        }
+        (true, None) => return Err(eyre!("No sender to sign the audit entry with")),
+        (false, _) => None,
+    };
+    let line = serde_json::to_string(&SignedEntry { entry, signature })?;
+
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| eyre!("Failed to open {}: {e}", path.display()))?;
+    writeln!(file, "{line}")?;
+    Ok(())
+}
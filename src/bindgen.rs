@@ -0,0 +1,53 @@
+use crate::cli_config::{BindgenConfig, CompilerConfig};
+use crate::compiler::{compile, contracts_path};
+use ethers::contract::{Abigen, MultiAbigen};
+use eyre::{eyre, Result};
+use std::collections::BTreeMap;
+
+// Entry point of the `bindgen` subcommand: generate typed Rust bindings for
+// the compiled contracts, the same code `abigen!` expands to
+pub async fn bindgen(config: BindgenConfig) -> Result<()> {
+    // Compiler settings do not affect the ABI
+    let output = compile(contracts_path(), None, &CompilerConfig::default()).await?;
+
+    let mut abis = BTreeMap::new();
+    for (id, artifact) in output.into_artifacts() {
+        if !config.contract_names.is_empty() && !config.contract_names.contains(&id.name) {
+            continue;
+        }
+        let Some(abi) = artifact.abi else {
+            continue;
+        };
+        if abis.insert(id.name.clone(), abi.abi).is_some() {
+            return Err(eyre!(
+                "Contract {} is defined more than once, bindings need unique names",
+                id.name
+            ));
+        }
+    }
+    if let Some(missing) = config
+        .contract_names
+        .iter()
+        .find(|name| !abis.contains_key(*name))
+    {
+        return Err(eyre!(
+            "Contract {missing} not found in {}",
+            contracts_path().display()
+        ));
+    }
+
+    let abigens = abis
+        .iter()
+        .map(|(name, abi)| Abigen::new(name, serde_json::to_string(abi)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    MultiAbigen::from_abigens(abigens)
+        .build()?
+        .write_to_module(&config.out, config.single_file)?;
+
+    println!(
+        "Bindings for {} written to {}",
+        abis.keys().cloned().collect::<Vec<_>>().join(", "),
+        config.out.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,358 @@
+use crate::abi_args::{format_token, parse_tokens};
+use crate::audit;
+use crate::cli_config::{parse_amount, FeeBumpConfig};
+use crate::contract_abi::find_function;
+use crate::env::interpolate;
+use crate::error::DeployError;
+use crate::fees::Fees;
+use crate::interact::print_receipt;
+use crate::journal::Journal;
+use crate::multicall::BatchReader;
+use crate::revert::{decode_revert, revert_error};
+use crate::rpc::RpcTransport;
+use crate::timelock::{timelock_abi, OperationState, TimelockOperation};
+use crate::tx::wait_for_receipt;
+use ethers::abi::{Abi, StateMutability};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionReceipt, TransactionRequest, U256};
+use ethers_providers::Middleware;
+use eyre::{eyre, Result, WrapErr};
+use serde::Deserialize;
+use std::path::Path;
+
+// A contract method call planned to run after deployment, e.g.
+// `{ "method": "writeMessage", "args": ["Hello Solidity!"] }` or
+// `{ "method": "getMessages", "expect": ["[Hello Solidity!]"] }`
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlannedCall {
+    pub method: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    // Amount sent along with the call, e.g. `1ether`
+    #[serde(default)]
+    pub value: Option<String>,
+    // Values the method must return, checked with `eth_call` before anything
+    // is sent
+    #[serde(default)]
+    pub expect: Option<Vec<String>>,
+    // TimelockController the call is scheduled on and later executed
+    // through, waiting out its delay, e.g. `"@Timelock"`
+    #[serde(default)]
+    pub timelock: Option<String>,
+}
+
+// Post-deploy script in TOML, which has no top-level lists:
+//
+// [[calls]]
+// method = "writeMessage"
+// args = ["Hello Solidity!"]
+#[derive(Deserialize)]
+struct CallScript {
+    calls: Vec<PlannedCall>,
+}
+
+// Read the planned calls of a post-deploy script, a TOML file or a JSON list
+pub fn load_calls(path: &Path) -> Result<Vec<PlannedCall>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| eyre!("Failed to read post-deploy script {path:?}: {e}"))?;
+    let content = interpolate(&content, path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::from_str::<CallScript>(&content)
+            .map(|script| script.calls)
+            .map_err(|e| eyre!("Failed to parse post-deploy script {path:?}: {e}")),
+        _ => serde_json::from_str(&content)
+            .map_err(|e| eyre!("Failed to parse post-deploy script {path:?}: {e}")),
+    }
+}
+
+impl PlannedCall {
+    // Build the transaction calling this method on the contract at `address`
+    pub fn to_transaction(&self, abi: &Abi, address: Address) -> Result<TransactionRequest> {
+        let mut tx = TransactionRequest::new()
+            .to(address)
+            .data(self.calldata(abi)?);
+        if let Some(value) = self.value()? {
+            tx = tx.value(value);
+        }
+        Ok(tx)
+    }
+
+    fn calldata(&self, abi: &Abi) -> Result<Bytes> {
+        let function = find_function(abi, &self.method, self.args.len())?;
+        let tokens = parse_tokens(&function.inputs, &self.args)?;
+        Ok(function.encode_input(&tokens)?.into())
+    }
+
+    // Whether the method is view or pure, only called and never sent
+    fn is_read(&self, abi: &Abi) -> Result<bool> {
+        let function = find_function(abi, &self.method, self.args.len())?;
+        Ok(matches!(
+            function.state_mutability,
+            StateMutability::View | StateMutability::Pure
+        ))
+    }
+
+    // Decode the output of an `eth_call` of this method and compare it with
+    // the expected values
+    fn check_output(&self, abi: &Abi, output: &Bytes) -> Result<String> {
+        let function = find_function(abi, &self.method, self.args.len())?;
+        let tokens = function.decode_output(output)?;
+        let formatted = tokens
+            .iter()
+            .map(format_token)
+            .collect::<Vec<_>>()
+            .join(", ");
+        if let Some(expect) = &self.expect {
+            let expected = parse_tokens(&function.outputs, expect)
+                .map_err(|e| eyre!("Call {} expects invalid values: {e}", self.method))?;
+            if expected != tokens {
+                return Err(eyre!(
+                    "Call {} returned ({formatted}), expected ({})",
+                    self.method,
+                    expect.join(", ")
+                ));
+            }
+        }
+        Ok(formatted)
+    }
+
+    fn value(&self) -> Result<Option<U256>> {
+        self.value
+            .as_deref()
+            .map(parse_amount)
+            .transpose()
+            .map_err(|e| eyre!("Call {}: {e}", self.method))
+    }
+}
+
+// Send the planned calls one after another, waiting for each receipt. Every
+// call is checked with `eth_call` first so reverts and unexpected results
+// surface before broadcasting. View and pure methods are only called, each
+// run of consecutive reads in one Multicall3 batch. Timelocked calls are
+// scheduled and executed once their delay has passed. With a journal, calls
+// confirmed by a previous run are skipped. Returns the receipt of every call,
+// `None` for those only called.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_calls<M: Middleware<Provider = RpcTransport>>(
+    client: &M,
+    abi: &Abi,
+    address: Address,
+    calls: &[PlannedCall],
+    from: Option<Address>,
+    fees: Option<&Fees>,
+    mut journal: Option<&mut Journal>,
+    confirmations: usize,
+    mut nonce: Option<&mut U256>,
+    fee_bump: Option<&FeeBumpConfig>,
+) -> Result<Vec<Option<TransactionReceipt>>>
+where
+    M::Error: 'static,
+{
+    let mut receipts = Vec::with_capacity(calls.len());
+    let mut index = 0;
+    while index < calls.len() {
+        let mut reads = 0;
+        while calls
+            .get(index + reads)
+            .map(|call| call.is_read(abi))
+            .transpose()?
+            .unwrap_or(false)
+        {
+            reads += 1;
+        }
+        if reads > 0 {
+            read_calls(client, abi, address, &calls[index..index + reads]).await?;
+            receipts.extend((0..reads).map(|_| None));
+            index += reads;
+            continue;
+        }
+
+        let call = &calls[index];
+        let step = format!("call #{index} {} on {address:?}", call.method);
+        index += 1;
+        let receipt = match &call.timelock {
+            Some(timelock) => {
+                let timelock: Address = timelock.parse().map_err(|e| {
+                    eyre!(
+                        "Call {} has an invalid timelock {timelock:?}: {e}",
+                        call.method
+                    )
+                })?;
+                let operation =
+                    TimelockOperation::new(timelock, &call.to_transaction(abi, address)?, &step)?;
+                let id = operation.id(client).await?;
+                let timelock_abi = timelock_abi()?;
+                match operation.state(client, id).await? {
+                    OperationState::Done => {
+                        println!(
+                            "CALL {} already executed by timelock {timelock:?}",
+                            call.method
+                        );
+                        receipts.push(None);
+                        continue;
+                    }
+                    OperationState::Unset => {
+                        let delay = operation.min_delay(client).await?;
+                        println!(
+                            "SCHEDULE {} through timelock {timelock:?}, delay {delay}s",
+                            call.method
+                        );
+                        send_call(
+                            client,
+                            &timelock_abi,
+                            timelock,
+                            &operation.schedule(delay),
+                            &format!("{step} schedule"),
+                            from,
+                            fees,
+                            journal.as_deref_mut(),
+                            confirmations,
+                            nonce.as_deref_mut(),
+                            fee_bump,
+                        )
+                        .await?;
+                    }
+                    OperationState::Waiting(_) | OperationState::Ready => {}
+                }
+                operation.wait_until_ready(client, id).await?;
+                send_call(
+                    client,
+                    &timelock_abi,
+                    timelock,
+                    &operation.execute(),
+                    &format!("{step} execute"),
+                    from,
+                    fees,
+                    journal.as_deref_mut(),
+                    confirmations,
+                    nonce.as_deref_mut(),
+                    fee_bump,
+                )
+                .await?
+            }
+            None => {
+                send_call(
+                    client,
+                    abi,
+                    address,
+                    call,
+                    &step,
+                    from,
+                    fees,
+                    journal.as_deref_mut(),
+                    confirmations,
+                    nonce.as_deref_mut(),
+                    fee_bump,
+                )
+                .await?
+            }
+        };
+        receipts.push(Some(receipt));
+    }
+    Ok(receipts)
+}
+
+// Send a call as the journal step `step` after checking it with `eth_call`,
+// or return the receipt of a previous run that confirmed it
+#[allow(clippy::too_many_arguments)]
+pub async fn send_call<M: Middleware<Provider = RpcTransport>>(
+    client: &M,
+    abi: &Abi,
+    address: Address,
+    call: &PlannedCall,
+    step: &str,
+    from: Option<Address>,
+    fees: Option<&Fees>,
+    mut journal: Option<&mut Journal>,
+    confirmations: usize,
+    nonce: Option<&mut U256>,
+    fee_bump: Option<&FeeBumpConfig>,
+) -> Result<TransactionReceipt>
+where
+    M::Error: 'static,
+{
+    if let Some(journal) = journal.as_deref() {
+        if let Some(receipt) = journal.completed(client, step).await? {
+            return Ok(receipt);
+        }
+    }
+
+    let mut tx: TypedTransaction = call.to_transaction(abi, address)?.into();
+    if let Some(from) = from {
+        tx.set_from(from);
+    }
+    if let Some(fees) = fees {
+        fees.apply(&mut tx);
+    }
+
+    let output = client
+        .call(&tx, None)
+        .await
+        .map_err(|e| revert_error(e, Some(abi), &format!("Call {}", call.method)))?;
+    call.check_output(abi, &output)?;
+    // An explicit nonce counts up with every transaction sent
+    if let Some(nonce) = nonce {
+        tx.set_nonce(*nonce);
+        *nonce += U256::one();
+    }
+    let pending_tx = client.send_transaction(tx.clone(), None).await?;
+    audit::record(client, step, &tx, pending_tx.tx_hash()).await;
+    if let Some(journal) = journal.as_deref_mut() {
+        journal.record_sent(step, pending_tx.tx_hash())?;
+    }
+    let transaction = pending_tx.tx_hash();
+    let receipt = wait_for_receipt(
+        client,
+        pending_tx,
+        tx,
+        fees,
+        fee_bump,
+        confirmations,
+        |hash| match journal.as_deref_mut() {
+            Some(journal) => journal.record_sent(step, hash),
+            None => Ok(()),
+        },
+    )
+    .await?
+    .ok_or(DeployError::Dropped { transaction })
+    .wrap_err_with(|| format!("Call {} failed", call.method))?;
+    if receipt.status.is_some_and(|status| status.is_zero()) {
+        return Err(eyre!(
+            "Call {} reverted in transaction {:?}",
+            call.method,
+            receipt.transaction_hash
+        ));
+    }
+    if let Some(journal) = journal.as_deref_mut() {
+        journal.record_confirmed(step)?;
+    }
+    println!("CALL {}", call.method);
+    print_receipt(&receipt);
+    Ok(receipt)
+}
+
+// Call a run of view and pure methods in one batch and check what they return
+async fn read_calls<M: Middleware>(
+    client: &M,
+    abi: &Abi,
+    address: Address,
+    calls: &[PlannedCall],
+) -> Result<()>
+where
+    M::Error: 'static,
+{
+    let mut reader = BatchReader::new(client);
+    for call in calls {
+        reader.add(address, call.calldata(abi)?);
+    }
+    for (call, result) in calls.iter().zip(reader.read().await?) {
+        let output = result
+            .map_err(|data| DeployError::Reverted {
+                reason: decode_revert(&data, Some(abi)),
+            })
+            .wrap_err_with(|| format!("Call {} failed", call.method))?;
+        let returned = call.check_output(abi, &output)?;
+        println!("READ {} returned ({returned})", call.method);
+    }
+    Ok(())
+}
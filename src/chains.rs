@@ -0,0 +1,146 @@
+use crate::cli_config::FeeConfig;
+use crate::network::load_project_config;
+use ethers::etherscan::Client;
+use ethers::types::Chain;
+use eyre::{eyre, ContextCompat, Result};
+use serde::Deserialize;
+use std::str::FromStr;
+
+// A chain known by name: one of ethers' built-in chains or a `[chains.<name>]`
+// section of `solidity-deploy.toml`, which take precedence
+#[derive(Debug, Clone)]
+pub struct ChainInfo {
+    pub name: String,
+    pub chain_id: u64,
+    // Etherscan compatible API and browser of the chain's block explorer
+    pub explorer_api_url: Option<String>,
+    pub explorer_url: Option<String>,
+    pub eip1559: bool,
+}
+
+// A chain added in `solidity-deploy.toml`, e.g.
+//
+// [chains.devnet]
+// chain_id = 1337
+// explorer_api_url = "https://explorer.devnet.example/api"
+// explorer_url = "https://explorer.devnet.example"
+// eip1559 = false
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CustomChain {
+    chain_id: u64,
+    explorer_api_url: Option<String>,
+    explorer_url: Option<String>,
+    #[serde(default = "eip1559_default")]
+    eip1559: bool,
+}
+
+fn eip1559_default() -> bool {
+    true
+}
+
+// Resolve a chain by name or id, e.g. `sepolia` or `11155111`
+pub fn resolve_chain(value: &str) -> Result<ChainInfo> {
+    let value = value.trim();
+    let custom = load_project_config()?.chains;
+    if let Some(chain) = custom.get(value) {
+        return Ok(ChainInfo::custom(value, chain));
+    }
+    if let Ok(chain_id) = value.parse::<u64>() {
+        return chain_info(chain_id);
+    }
+    match Chain::from_str(value) {
+        Ok(chain) => Ok(ChainInfo::from(chain)),
+        Err(_) => Err(eyre!(
+            "Unknown chain {value}, use a chain id or add it under [chains.{value}] in solidity-deploy.toml"
+        )),
+    }
+}
+
+// Registry entry of a chain id. Chains missing from the registry are assumed
+// to support EIP-1559 and to have no known block explorer.
+pub fn chain_info(chain_id: u64) -> Result<ChainInfo> {
+    let custom = load_project_config()?.chains;
+    if let Some((name, chain)) = custom.iter().find(|(_, chain)| chain.chain_id == chain_id) {
+        return Ok(ChainInfo::custom(name, chain));
+    }
+    Ok(match Chain::try_from(chain_id) {
+        Ok(chain) => ChainInfo::from(chain),
+        Err(_) => ChainInfo {
+            name: chain_id.to_string(),
+            chain_id,
+            explorer_api_url: None,
+            explorer_url: None,
+            eip1559: true,
+        },
+    })
+}
+
+impl ChainInfo {
+    fn custom(name: &str, chain: &CustomChain) -> Self {
+        ChainInfo {
+            name: name.to_owned(),
+            chain_id: chain.chain_id,
+            explorer_api_url: chain.explorer_api_url.clone(),
+            explorer_url: chain.explorer_url.clone(),
+            eip1559: chain.eip1559,
+        }
+    }
+
+    // Fail when the connected node serves another chain, before anything is sent
+    pub fn check(&self, connected_chain_id: u64) -> Result<()> {
+        if connected_chain_id != self.chain_id {
+            return Err(eyre!(
+                "RPC endpoint serves chain id {connected_chain_id}, but {} has chain id {}",
+                self.name,
+                self.chain_id
+            ));
+        }
+        Ok(())
+    }
+
+    // Fee settings for the chain: chains without EIP-1559 always get legacy
+    // transactions
+    pub fn fee_config(&self, config: &FeeConfig) -> FeeConfig {
+        let mut config = config.clone();
+        if !self.eip1559 && !config.legacy {
+            println!(
+                "{} does not support EIP-1559, sending legacy transactions",
+                self.name
+            );
+            config.legacy = true;
+            config.max_fee_per_gas = None;
+            config.max_priority_fee_per_gas = None;
+        }
+        config
+    }
+
+    // Client of the chain's block explorer, for verification
+    pub fn explorer_client(&self, api_key: &str) -> Result<Client> {
+        let api_url = self.explorer_api_url.as_deref().with_context(|| {
+            format!(
+                "No block explorer known for {}, add explorer_api_url under [chains.{}] in solidity-deploy.toml",
+                self.name, self.name
+            )
+        })?;
+        let url = self.explorer_url.as_deref().unwrap_or(api_url);
+        Ok(Client::builder()
+            .with_api_key(api_key)
+            .with_api_url(api_url)?
+            .with_url(url)?
+            .build()?)
+    }
+}
+
+impl From<Chain> for ChainInfo {
+    fn from(chain: Chain) -> Self {
+        let urls = chain.etherscan_urls();
+        ChainInfo {
+            name: chain.to_string(),
+            chain_id: u64::from(chain),
+            explorer_api_url: urls.map(|(api_url, _)| api_url.to_owned()),
+            explorer_url: urls.map(|(_, url)| url.to_owned()),
+            eip1559: !chain.is_legacy(),
+        }
+    }
+}
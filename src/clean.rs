@@ -0,0 +1,56 @@
+use crate::cli_config::{CleanConfig, CompilerConfig};
+use crate::compiler::{build_project, contracts_path};
+use crate::deployer::LOCAL_CHAIN_ID;
+use crate::manifest::{manifest_path, Deployment, DEPLOYMENTS_FOLDER};
+use crate::rpc;
+use ethers_providers::Middleware;
+use eyre::Result;
+use std::path::PathBuf;
+
+// Entry point of the `clean` subcommand, forcing the next build to start
+// from scratch and dropping manifests of contracts that no longer exist
+pub async fn clean(config: CleanConfig) -> Result<()> {
+    let project = build_project(contracts_path(), None, &CompilerConfig::default())?;
+    let mut stale = vec![
+        config.output_dir.clone(),
+        project.paths.cache.clone(),
+        project.paths.artifacts.clone(),
+    ];
+
+    let checked_chain = match &config.rpc_url {
+        Some(rpc_url) => {
+            let provider = rpc::connect(rpc_url)?;
+            let chain_id = provider.get_chainid().await?.as_u64();
+            for deployment in Deployment::list_all()? {
+                if deployment.chain_id == chain_id
+                    && provider
+                        .get_code(deployment.address, None)
+                        .await?
+                        .is_empty()
+                {
+                    stale.push(manifest_path(chain_id, &deployment.name));
+                }
+            }
+            Some(chain_id)
+        }
+        None => None,
+    };
+    // Contracts of a local chain are gone once it stops
+    if checked_chain != Some(LOCAL_CHAIN_ID) {
+        stale.push(PathBuf::from(DEPLOYMENTS_FOLDER).join(LOCAL_CHAIN_ID.to_string()));
+    }
+
+    for path in stale.into_iter().filter(|path| path.exists()) {
+        if config.dry_run {
+            println!("Would remove {}", path.display());
+            continue;
+        }
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path)?;
+        } else {
+            std::fs::remove_file(&path)?;
+        }
+        println!("Removed {}", path.display());
+    }
+    Ok(())
+}
@@ -1,28 +1,2013 @@
-use clap::Parser;
+use crate::chains::{resolve_chain, ChainInfo};
+use crate::proxy::ProxyKind;
+use crate::secrets::parse_secret;
+use crate::tx_store::{TxStatus, DEFAULT_TX_DB};
+use crate::{env, network};
+use clap::{Args, Parser};
+use ethers::types::{Address, BlockNumber, Bytes, H256, U256};
+use ethers::utils::{hex, parse_units};
+use ethers_solc::artifacts::BytecodeHash;
+use ethers_solc::EvmVersion;
+use eyre::{eyre, Result};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug, Clone)]
 #[clap(about, author, version)]
 pub struct Config {
+    /// Network profile of `solidity-deploy.toml` providing defaults for the RPC URL, chain id, signer, ...
+    #[clap(long, value_parser, global = true, env = "NETWORK")]
+    pub network: Option<String>,
+
+    /// Environment profile of `solidity-deploy.toml`, e.g. `staging` or `prod`, selecting the network, signer, confirmations and verification
+    #[clap(long, value_parser, global = true, env = "DEPLOY_PROFILE")]
+    pub profile: Option<String>,
+
+    /// File of environment variables the flags fall back to. Defaults to `.env` when present
+    #[clap(long, value_parser, global = true)]
+    pub env_file: Option<PathBuf>,
+
+    /// Print JSON events (compiled, deploying, deployed, tx-confirmed) on stdout instead of the human-oriented output. Supported by the commands that deploy, compile or send
+    #[clap(long, value_parser, global = true)]
+    pub json: bool,
+
+    /// Log more details: `-v` for debug output and phase timings, `-vv` for everything
+    #[clap(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Only log warnings and errors, without the progress display
+    #[clap(short, long, value_parser, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Folder with the Solidity sources, e.g. `src/`, or a single `.sol` file
+    #[clap(
+        long,
+        value_parser,
+        global = true,
+        env = "CONTRACTS_DIR",
+        default_value = "contracts/"
+    )]
+    pub contracts_dir: PathBuf,
+
+    #[clap(flatten)]
+    pub retry: RetryConfig,
+
+    #[clap(flatten)]
+    pub audit: AuditConfig,
+
+    #[clap(flatten)]
+    pub tx_store: TxStoreConfig,
+
     #[clap(subcommand)]
     pub command: Command,
 }
 
+// Retrying of RPC requests that fail with a timeout, rate limit or gateway
+// error, e.g. HTTP 429 or 502 from a public endpoint
+#[derive(Args, Debug, Clone, Default)]
+pub struct RetryConfig {
+    /// Times a failing RPC request is retried
+    #[clap(
+        long,
+        value_parser,
+        global = true,
+        env = "RPC_RETRIES",
+        default_value_t = 5
+    )]
+    pub rpc_retries: u32,
+
+    /// Wait before the first retry in milliseconds, doubled on every further one
+    #[clap(
+        long,
+        value_parser,
+        global = true,
+        env = "RPC_RETRY_BACKOFF_MS",
+        default_value_t = 500
+    )]
+    pub rpc_retry_backoff_ms: u64,
+
+    /// Retries allowed over the whole run, so a dead endpoint fails instead of stalling
+    #[clap(
+        long,
+        value_parser,
+        global = true,
+        env = "RPC_RETRY_BUDGET",
+        default_value_t = 100
+    )]
+    pub rpc_retry_budget: u32,
+}
+
+// Audit log of every transaction the tool broadcasts, kept so a team can tell
+// what was deployed and called, by whom and when
+#[derive(Args, Debug, Clone, Default)]
+pub struct AuditConfig {
+    /// Append-only log of the broadcast transactions, one JSON object per line
+    #[clap(
+        long,
+        value_parser,
+        global = true,
+        env = "AUDIT_LOG",
+        default_value = "deployments/audit.jsonl"
+    )]
+    pub audit_log: PathBuf,
+
+    /// Sign every audit log entry with the key that sent the transaction
+    #[clap(long, value_parser, global = true, env = "AUDIT_SIGN")]
+    pub audit_sign: bool,
+
+    /// Do not record the broadcast transactions in the audit log
+    #[clap(long, value_parser, global = true, conflicts_with = "audit_sign")]
+    pub no_audit_log: bool,
+}
+
+// Database of every transaction sent, with its status, read back by `tx list`,
+// `tx show` and the server
+#[derive(Args, Debug, Clone, Default)]
+pub struct TxStoreConfig {
+    /// SQLite database the sent transactions and their status are recorded in
+    #[clap(
+        long,
+        value_parser,
+        global = true,
+        env = "TX_DB",
+        default_value = DEFAULT_TX_DB
+    )]
+    pub tx_db: PathBuf,
+
+    /// Do not record the sent transactions in the transaction database
+    #[clap(long, value_parser, global = true)]
+    pub no_tx_db: bool,
+}
+
 #[derive(Parser, Clone, Debug)]
 pub enum Command {
     #[clap()]
     Deploy(DeployConfig),
+    /// Compile the contracts and report artifacts without touching any chain
+    #[clap()]
+    Compile(CompileConfig),
+    /// Call a read-only method of a deployed contract
+    #[clap()]
+    Call(CallConfig),
+    /// Sign and send a transaction to a method of a deployed contract
+    #[clap()]
+    Send(SendConfig),
+    /// List the accounts of the configured signer, or Anvil's, with their balances and nonces
+    #[clap()]
+    Accounts(AccountsConfig),
+    /// Report the chain id, latest block, fees and supported features of a node
+    #[clap()]
+    ChainInfo(ChainInfoConfig),
+    /// Fund an account, setting its balance on a local node or sending a transfer from the signer elsewhere
+    #[clap()]
+    Fund(FundConfig),
+    /// Verify a deployed contract's source on Etherscan
+    #[clap()]
+    Verify(VerifyConfig),
+    /// Compare a deployed contract's bytecode with the locally compiled one
+    #[clap()]
+    VerifyBytecode(VerifyBytecodeConfig),
+    /// Estimate the gas and cost of a deployment without broadcasting it
+    #[clap()]
+    Estimate(EstimateConfig),
+    /// Compare the bytecode size and gas of a contract across optimizer settings on a scratch Anvil chain
+    #[clap()]
+    OptimizeReport(OptimizeReportConfig),
+    /// Predict the address of a contract deployed with CREATE or CREATE2
+    #[clap()]
+    PredictAddress(PredictAddressConfig),
+    /// Search for a CREATE2 salt giving a contract an address with a vanity prefix or suffix
+    #[clap()]
+    MineSalt(MineSaltConfig),
+    /// Upgrade a proxy deployed with `deploy --proxy` to a new implementation
+    #[clap()]
+    Upgrade(UpgradeConfig),
+    /// Print a contract's source with all of its imports inlined
+    #[clap()]
+    Flatten(FlattenConfig),
+    /// Export a contract's ABI as JSON or human-readable signatures
+    #[clap()]
+    Abi(AbiConfig),
+    /// Print a contract's function selectors and event topics, or check the project for selector collisions
+    #[clap()]
+    Selectors(SelectorsConfig),
+    /// Convert an amount between wei, gwei, ether and token units
+    #[clap()]
+    Convert(ConvertConfig),
+    /// Hash data with keccak256, or compute a function selector, event topic or ERC-1967 storage slot
+    #[clap()]
+    Hash(HashConfig),
+    /// Compare a contract's compiled ABI with a previous artifact or its deployment
+    #[clap()]
+    AbiDiff(AbiDiffConfig),
+    /// Generate typed Rust bindings for the compiled contracts
+    #[clap()]
+    Bindgen(BindgenConfig),
+    /// Write TypeScript modules with each contract's ABI and deployed addresses
+    #[clap()]
+    ExportTs(ExportTsConfig),
+    /// Interactive prompt to call and send methods of a deployed contract
+    #[clap()]
+    Console(ConsoleConfig),
+    /// Query and decode past events of a deployed contract
+    #[clap()]
+    Logs(LogsConfig),
+    /// Print all events of a deployed contract, optionally streaming new ones
+    #[clap()]
+    Events(EventsConfig),
+    /// Read and decode the storage of a deployed contract
+    #[clap()]
+    Storage(StorageConfig),
+    /// Print the storage layout of a contract, with the contract declaring each variable
+    #[clap()]
+    Layout(LayoutConfig),
+    /// Decode transaction calldata with the ABIs of the project
+    #[clap()]
+    DecodeCalldata(DecodeCalldataConfig),
+    /// ABI encode a call, e.g. `abi-encode "transfer(address,uint256)" 0x.. 1000`
+    #[clap()]
+    AbiEncode(AbiEncodeConfig),
+    /// Decode calldata or return data against a function signature
+    #[clap()]
+    AbiDecode(AbiDecodeConfig),
+    /// Print the call tree of a transaction, decoded with the ABIs of the project
+    #[clap()]
+    Trace(TraceConfig),
+    /// Speed up or cancel a pending transaction, or look up the sent ones
+    #[clap()]
+    Tx(TxConfig),
+    /// Snapshot the state of a local chain or roll it back to a snapshot
+    #[clap()]
+    Snapshot(SnapshotConfig),
+    /// Scaffold a new project with a sample contract, network profiles and a `.env.example`
+    #[clap()]
+    Init(InitConfig),
+    /// Remove compiled artifacts, the compiler cache and stale deployment manifests
+    #[clap()]
+    Clean(CleanConfig),
+    /// Store mnemonics, private keys and API keys encrypted, for network profiles to refer to as `secret:<name>`
+    #[clap()]
+    Secrets(SecretsConfig),
+    /// List, show and remove the recorded deployments of every network
+    #[clap()]
+    Registry(RegistryConfig),
+    /// Sign a deployment transaction offline, e.g. on an air-gapped machine, for `broadcast` to send later
+    #[clap()]
+    Sign(SignConfig),
+    /// Send a deployment transaction signed with `sign` and record its deployment
+    #[clap()]
+    Broadcast(BroadcastConfig),
+    /// Sign an EIP-712 typed data payload, e.g. a permit, with the configured signer
+    #[clap()]
+    SignTypedData(SignTypedDataConfig),
+}
+
+impl Command {
+    // Whether the command reports as the JSON events of `--json`. The others
+    // only have human-oriented output, which `--json` suppresses.
+    pub fn reports_json(&self) -> bool {
+        match self {
+            Command::Deploy(_)
+            | Command::Compile(_)
+            | Command::Send(_)
+            | Command::Upgrade(_)
+            | Command::Broadcast(_) => true,
+            Command::Tx(tx) => matches!(tx.command, TxCommand::Bump(_) | TxCommand::Cancel(_)),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Parser, Debug, Clone)]
 #[clap(author, version, about, long_about = None)]
 pub struct DeployConfig {
-    #[clap(long, value_parser, env = "MNEMONIC")]
-    pub mnemonic: String,
+    /// RPC endpoint to deploy to. A local Anvil chain is spawned when omitted
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: Option<String>,
+
+    /// Websocket endpoint of the same node, subscribed to for new blocks instead of polling the RPC endpoint
+    #[clap(long, value_parser = parse_secret, env = "WS_URL")]
+    pub ws_url: Option<String>,
+
+    /// Chain the RPC endpoint must serve, by name or id, e.g. `sepolia`. Checked before anything is sent
+    #[clap(long, value_parser = parse_chain, env = "CHAIN")]
+    pub chain: Option<ChainInfo>,
 
+    /// Network profiles to deploy to in one run, e.g. `--networks sepolia,base-sepolia`. Each uses the RPC URL, signer and fees of its profile
+    #[clap(
+        long,
+        value_parser,
+        value_delimiter = ',',
+        conflicts_with_all = ["rpc_url", "ws_url", "chain", "nonce", "watch", "attach", "fork_url", "keep_alive"]
+    )]
+    pub networks: Vec<String>,
+
+    /// Deploy to the networks of --networks at the same time instead of one after the other. Their output interleaves
+    #[clap(long, value_parser, requires = "networks")]
+    pub parallel: bool,
+
+    /// Number of confirmations to wait for on every transaction
+    #[clap(long, value_parser, env = "CONFIRMATIONS", default_value_t = 1)]
+    pub confirmations: usize,
+
+    /// Nonce of the first transaction, the following ones count up from it. Taken from the node when omitted
+    #[clap(long, value_parser = parse_u256, conflicts_with_all = ["dry_run", "watch"])]
+    pub nonce: Option<U256>,
+
+    #[clap(flatten)]
+    pub anvil: AnvilConfig,
+
+    #[clap(flatten)]
+    pub signer: SignerConfig,
+
+    #[clap(flatten)]
+    pub safe: SafeConfig,
+
+    #[clap(flatten)]
+    pub smart_account: SmartAccountConfig,
+
+    #[clap(
+        long,
+        value_parser,
+        env = "CONTRACT_NAME",
+        required_unless_present_any = ["plan", "all"]
+    )]
+    pub contract_name: Option<String>,
+
+    /// Deploy every contract of the project without constructor arguments, libraries first
+    #[clap(
+        long,
+        value_parser,
+        conflicts_with_all = ["contract_name", "constructor_args", "plan", "calls", "proxy", "dry_run", "safe", "smart_account"]
+    )]
+    pub all: bool,
+
+    /// Constructor arguments, e.g. `--constructor-args 42 0xabc.. "[1,2]" "(1,hello)"`. Asked for when omitted at a terminal, unless --json or CI is set
+    #[clap(long, value_parser, num_args = 1.., allow_hyphen_values = true)]
+    pub constructor_args: Vec<String>,
+
+    /// Deployment plan (TOML, YAML or JSON) listing several contracts to deploy in order
+    #[clap(
+        long,
+        value_parser,
+        conflicts_with_all = ["contract_name", "constructor_args", "calls", "dry_run", "safe", "smart_account"]
+    )]
+    pub plan: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub fees: FeeConfig,
+
+    #[clap(flatten)]
+    pub fee_bump: FeeBumpConfig,
+
+    #[clap(flatten)]
+    pub price_feed: PriceFeedConfig,
+
+    /// Post-deploy script (TOML or JSON) of calls with args and expected results, e.g. `[{"method": "writeMessage", "args": ["hi"]}]`
+    #[clap(
+        long = "post-deploy",
+        visible_alias = "calls",
+        value_parser,
+        conflicts_with_all = ["safe", "smart_account"]
+    )]
+    pub calls: Option<PathBuf>,
+
+    /// Simulate the deployment and calls on a fork of the target chain without broadcasting
+    #[clap(long, value_parser, conflicts_with_all = ["safe", "smart_account"])]
+    pub dry_run: bool,
+
+    /// Resume an interrupted deployment from its journal, skipping confirmed steps
+    #[clap(long, value_parser, conflicts_with = "dry_run")]
+    pub resume: bool,
+
+    /// Take over the deployment lock of the network from another run, e.g. one that died on another machine
+    #[clap(long, value_parser, conflicts_with = "dry_run")]
+    pub force_unlock: bool,
+
+    /// Report on the node as `chain-info` does before deploying, failing when it cannot serve the deployment
+    #[clap(long, value_parser, env = "PREFLIGHT")]
+    pub preflight: bool,
+
+    /// Deploy through the deterministic deployment proxy so the address only depends on the salt and init code
+    #[clap(long, value_parser, requires = "salt", conflicts_with = "dry_run")]
+    pub create2: bool,
+
+    /// CREATE2 salt as hex, left-padded to 32 bytes
+    #[clap(long, value_parser = parse_salt, requires = "create2")]
+    pub salt: Option<H256>,
+
+    /// Deploy the contract as the implementation behind a new ERC-1967 proxy
+    #[clap(long, value_enum, conflicts_with_all = ["plan", "dry_run", "safe", "smart_account"])]
+    pub proxy: Option<ProxyKind>,
+
+    /// Initializer called through the proxy on deployment, e.g. `initialize` or `initialize(address)`
+    #[clap(long, value_parser, requires = "proxy")]
+    pub initializer: Option<String>,
+
+    #[clap(
+        long,
+        value_parser,
+        num_args = 1..,
+        allow_hyphen_values = true,
+        requires = "initializer"
+    )]
+    pub initializer_args: Vec<String>,
+
+    /// Admin of a transparent proxy. Defaults to the deployer
+    #[clap(long, value_parser, requires = "proxy")]
+    pub proxy_admin: Option<Address>,
+
+    /// Already deployed libraries to link, e.g. `--libraries MathLib:0xabc..`. Missing ones are deployed first
+    #[clap(long, value_parser = parse_library, num_args = 1..)]
+    pub libraries: Vec<(String, Address)>,
+
+    /// Deploy precompiled artifacts instead of compiling, e.g. `hardhat:./artifacts`
+    #[clap(long, value_parser = parse_artifacts_source)]
+    pub artifacts: Option<ArtifactsSource>,
+
+    /// Recompile and redeploy to the local Anvil chain whenever a contract source changes
+    #[clap(
+        long,
+        value_parser,
+        conflicts_with_all = ["rpc_url", "dry_run", "resume", "verify", "artifacts", "safe", "smart_account"]
+    )]
+    pub watch: bool,
+
+    /// Print the gas used by each deployment and post-deploy call, as a `text` table or `json`
+    #[clap(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        default_missing_value = "text",
+        conflicts_with_all = ["dry_run", "safe", "smart_account"]
+    )]
+    pub gas_report: Option<GasReportFormat>,
+
+    /// ENS name to point at the deployed contract, e.g. `mycontract.mydao.eth`. Its resolver must accept the deployer
+    #[clap(long, value_parser, conflicts_with_all = ["plan", "all", "dry_run", "safe", "smart_account"])]
+    pub ens_name: Option<String>,
+
+    /// Also set the contract's reverse record to --ens-name, which needs the deployer to own the contract
+    #[clap(long, value_parser, requires = "ens_name")]
+    pub ens_reverse: bool,
+
+    /// Verify the contract on Etherscan once it is deployed
+    #[clap(
+        long,
+        value_parser,
+        env = "VERIFY",
+        conflicts_with_all = ["safe", "smart_account"]
+    )]
+    pub verify: bool,
+
+    #[clap(
+        long,
+        value_parser = parse_secret,
+        env = "ETHERSCAN_API_KEY",
+        required_if_eq("verify", "true")
+    )]
+    pub etherscan_api_key: Option<String>,
+
+    /// Chain family to build and deploy for. `zksync` compiles with zksolc and deploys through the ContractDeployer of zkSync Era
+    #[clap(long, value_enum, default_value = "evm")]
+    pub target: DeployTarget,
+
+    /// zksolc binary compiling the contracts for `--target zksync`
+    #[clap(long, value_parser, env = "ZKSOLC", default_value = "zksolc")]
+    pub zksolc: PathBuf,
+
+    #[clap(flatten)]
+    pub compiler: CompilerConfig,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct EstimateConfig {
+    /// RPC endpoint to estimate against. A local Anvil chain is spawned when omitted
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: Option<String>,
+
+    /// Credentials of the deploying account, used as the sender of the estimates
+    #[clap(flatten)]
+    pub signer: SignerConfig,
+
+    #[clap(long, value_parser, env = "CONTRACT_NAME")]
+    pub contract_name: String,
+
+    #[clap(long, value_parser, num_args = 1.., allow_hyphen_values = true)]
+    pub constructor_args: Vec<String>,
+
+    #[clap(flatten)]
+    pub fees: FeeConfig,
+
+    #[clap(flatten)]
+    pub price_feed: PriceFeedConfig,
+
+    /// Post-deploy script (TOML or JSON) of the calls to estimate, e.g. `[{"method": "writeMessage", "args": ["hi"]}]`
+    #[clap(long = "post-deploy", visible_alias = "calls", value_parser)]
+    pub calls: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub compiler: CompilerConfig,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct OptimizeReportConfig {
     #[clap(long, value_parser, env = "CONTRACT_NAME")]
     pub contract_name: String,
+
+    #[clap(long, value_parser, num_args = 1.., allow_hyphen_values = true)]
+    pub constructor_args: Vec<String>,
+
+    /// Optimizer runs values to compare, besides a build without the optimizer
+    #[clap(
+        long,
+        value_parser,
+        value_delimiter = ',',
+        default_value = "1,200,1000,10000"
+    )]
+    pub runs: Vec<usize>,
+
+    /// Leave out the builds through the Yul IR pipeline, which compile much slower
+    #[clap(long, value_parser)]
+    pub no_via_ir: bool,
+
+    /// Post-deploy script (TOML or JSON) of the calls whose gas is compared, e.g. `[{"method": "writeMessage", "args": ["hi"]}]`
+    #[clap(long = "post-deploy", visible_alias = "calls", value_parser)]
+    pub calls: Option<PathBuf>,
+
+    /// Other compiler settings shared by every build. The optimizer settings and the build cache are overridden
+    #[clap(flatten)]
+    pub compiler: CompilerConfig,
+}
+
+// Credentials used to sign transactions, in order of precedence:
+// private key, keystore, mnemonic
+#[derive(Args, Debug, Clone)]
+pub struct SignerConfig {
+    #[clap(long, value_parser = parse_secret, env = "MNEMONIC")]
+    pub mnemonic: Option<String>,
+
+    /// Full derivation path for the mnemonic, e.g. `m/44'/60'/0'/0/3`
+    #[clap(
+        long,
+        value_parser,
+        env = "DERIVATION_PATH",
+        conflicts_with = "account_index"
+    )]
+    pub derivation_path: Option<String>,
+
+    /// Index of the account derived from the mnemonic on the default path
+    #[clap(long, value_parser, env = "ACCOUNT_INDEX", default_value_t = 0)]
+    pub account_index: u32,
+
+    /// Hex encoded private key used for signing
+    #[clap(long, value_parser = parse_secret, env = "PRIVATE_KEY", hide_env_values = true)]
+    pub private_key: Option<String>,
+
+    /// Path to an encrypted JSON (web3) keystore file
+    #[clap(long, value_parser, env = "KEYSTORE")]
+    pub keystore: Option<PathBuf>,
+
+    /// Keystore password. Prompted for without echo when omitted
+    #[clap(long, value_parser = parse_secret, env = "KEYSTORE_PASSWORD", hide_env_values = true)]
+    pub keystore_password: Option<String>,
+}
+
+// Source of the USD price of the native token, used to show costs in USD
+#[derive(Args, Debug, Clone)]
+pub struct PriceFeedConfig {
+    /// Also show costs in USD, with the native token price from this feed
+    #[clap(long, value_enum, env = "PRICE_FEED")]
+    pub price_feed: Option<PriceFeed>,
+
+    /// Chainlink aggregator of the native token's USD price, the ETH/USD one on mainnet by default
+    #[clap(long, value_parser, env = "PRICE_FEED_ADDRESS")]
+    pub price_feed_address: Option<Address>,
+
+    /// Coingecko id of the native token, e.g. `matic-network` on Polygon
+    #[clap(long, value_parser, env = "COINGECKO_ID", default_value = "ethereum")]
+    pub coingecko_id: String,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceFeed {
+    /// Coingecko's public price API
+    Coingecko,
+    /// Chainlink aggregator on the connected chain, see --price-feed-address
+    Chainlink,
+}
+
+// Transaction fee settings. Amounts are in wei unless a unit is given, e.g. `30gwei`
+#[derive(Args, Debug, Clone)]
+pub struct FeeConfig {
+    /// Send a legacy (pre EIP-1559) transaction
+    #[clap(long, value_parser)]
+    pub legacy: bool,
+
+    /// Gas price of a legacy transaction. Taken from the gas oracle when omitted
+    #[clap(long, value_parser = parse_amount, requires = "legacy")]
+    pub gas_price: Option<U256>,
+
+    /// Estimated by the gas oracle when omitted
+    #[clap(long, value_parser = parse_amount, conflicts_with = "legacy")]
+    pub max_fee_per_gas: Option<U256>,
+
+    /// Estimated by the gas oracle when omitted
+    #[clap(long, value_parser = parse_amount, conflicts_with = "legacy")]
+    pub max_priority_fee_per_gas: Option<U256>,
+
+    /// Where fees that are not given come from
+    #[clap(long, value_enum, env = "GAS_ORACLE", default_value = "node")]
+    pub gas_oracle: GasOracleKind,
+
+    /// Percentile of the recent priority fees the `fee-history` oracle pays
+    #[clap(
+        long,
+        value_parser = parse_percentile,
+        env = "FEE_PERCENTILE",
+        default_value_t = 50.0
+    )]
+    pub fee_percentile: f64,
+
+    /// API key of the `etherscan` or `blocknative` gas oracle
+    #[clap(long, value_parser = parse_secret, env = "GAS_ORACLE_KEY")]
+    pub gas_oracle_key: Option<String>,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GasOracleKind {
+    /// The node's own estimate
+    #[default]
+    Node,
+    /// The next block's base fee and a percentile of recent priority fees, from `eth_feeHistory`
+    FeeHistory,
+    /// The gas tracker of the chain's Etherscan explorer
+    Etherscan,
+    /// The Blocknative gas platform
+    Blocknative,
+}
+
+// Resending of transactions that stay pending with higher fees, by
+// `tx::bump_until_mined`
+#[derive(Args, Debug, Clone)]
+pub struct FeeBumpConfig {
+    /// Resend a transaction still pending after this many seconds with higher fees
+    #[clap(long, value_parser)]
+    pub bump_after: Option<u64>,
+
+    /// Percentage the fees are raised by on every resend. Nodes require at least 10
+    #[clap(
+        long,
+        value_parser = clap::value_parser!(u64).range(10..),
+        default_value_t = 15
+    )]
+    pub bump_percent: u64,
+
+    /// How many times a transaction is resent before its fees stay as they are
+    #[clap(long, value_parser, default_value_t = 5)]
+    pub max_bumps: u32,
+
+    /// Highest max fee per gas, or gas price, a resend may pay, e.g. `150gwei`. A bump that would pass it is resent at the cap, the last resend
+    #[clap(long, value_parser = parse_amount, requires = "bump_after")]
+    pub bump_fee_cap: Option<U256>,
+}
+
+// Multisig mode: the transactions are proposed to a Gnosis Safe through the
+// Safe Transaction Service instead of being sent by the signer, which has to
+// be an owner or delegate of the Safe
+#[derive(Args, Debug, Clone)]
+pub struct SafeConfig {
+    /// Propose the deployment or upgrade to this Safe for its owners to sign instead of sending it
+    #[clap(long, value_parser, env = "SAFE")]
+    pub safe: Option<Address>,
+
+    /// Safe Transaction Service to propose to. Defaults to the one of the chain
+    #[clap(long, value_parser, env = "SAFE_SERVICE_URL", requires = "safe")]
+    pub safe_service_url: Option<String>,
+
+    /// Safe nonce of the proposal, to queue it behind pending ones. Read from the Safe when omitted
+    #[clap(long, value_parser = parse_u256, requires = "safe")]
+    pub safe_nonce: Option<U256>,
+}
+
+// Deployment through an ERC-4337 smart account: the signer owns the account
+// and signs a UserOperation, which a bundler submits to the EntryPoint
+#[derive(Args, Debug, Clone)]
+pub struct SmartAccountConfig {
+    /// Deploy from this ERC-4337 smart account with a UserOperation signed by its owner
+    #[clap(
+        long,
+        value_parser,
+        env = "SMART_ACCOUNT",
+        conflicts_with = "safe",
+        requires = "bundler_url"
+    )]
+    pub smart_account: Option<Address>,
+
+    /// Bundler RPC endpoint estimating and submitting the UserOperation
+    #[clap(long, value_parser, env = "BUNDLER_URL", requires = "smart_account")]
+    pub bundler_url: Option<String>,
+
+    /// EntryPoint the smart account and bundler use. Defaults to the v0.6 one
+    #[clap(long, value_parser, requires = "smart_account")]
+    pub entry_point: Option<Address>,
+
+    /// Paymaster RPC endpoint sponsoring the UserOperation, for deployments the account pays nothing for
+    #[clap(long, value_parser, env = "PAYMASTER_URL", requires = "smart_account")]
+    pub paymaster_url: Option<String>,
+}
+
+// Options of the local Anvil chain spawned when no RPC endpoint is given
+#[derive(Args, Debug, Clone)]
+pub struct AnvilConfig {
+    /// Port the local chain listens on. A free one is picked when omitted
+    #[clap(long, value_parser, conflicts_with = "rpc_url")]
+    pub port: Option<u16>,
+
+    /// Seconds between blocks of the local chain, 0 mines a block per transaction
+    #[clap(long, value_parser, default_value_t = 1, conflicts_with = "rpc_url")]
+    pub block_time: u64,
+
+    /// Fork the local chain from this RPC endpoint to rehearse a deployment against its live state
+    #[clap(long, value_parser, conflicts_with_all = ["rpc_url", "verify"])]
+    pub fork_url: Option<String>,
+
+    /// Block number to fork from. The latest one when omitted
+    #[clap(long, value_parser, requires = "fork_url")]
+    pub fork_block: Option<u64>,
+
+    /// Number of funded accounts of the local chain
+    #[clap(long, value_parser, conflicts_with = "rpc_url")]
+    pub accounts: Option<u32>,
+
+    /// Balance of every account of the local chain, in ether
+    #[clap(long, value_parser, conflicts_with = "rpc_url")]
+    pub balance: Option<u64>,
+
+    /// Hardfork of the local chain, e.g. `shanghai` or `cancun`
+    #[clap(long, value_parser, conflicts_with = "rpc_url")]
+    pub hardfork: Option<String>,
+
+    /// Keep the local chain running after deploying until Ctrl-C, so the contracts can be used
+    #[clap(long, value_parser, conflicts_with_all = ["rpc_url", "dry_run"])]
+    pub keep_alive: bool,
+
+    /// Deploy to a running local Anvil node, e.g. one left by `--keep-alive`, signing with its default accounts
+    #[clap(
+        long,
+        value_parser,
+        conflicts_with_all = ["rpc_url", "keep_alive", "port", "fork_url", "accounts", "balance", "hardfork"]
+    )]
+    pub attach: Option<String>,
+
+    /// Send the deployment and calls from this account of the local chain or fork, e.g. a protocol's multisig
+    #[clap(long, value_parser, conflicts_with_all = ["rpc_url", "dry_run"])]
+    pub impersonate: Option<Address>,
+}
+
+// Precompiled artifacts to deploy from
+#[derive(Debug, Clone)]
+pub enum ArtifactsSource {
+    // Hardhat artifacts folder, holding `contracts/**/<Contract>.json`
+    Hardhat(PathBuf),
+}
+
+// Solidity compiler settings. They are always passed to solc explicitly and
+// recorded in deployment manifests so builds can be reproduced and verified.
+#[derive(Args, Debug, Clone, Default)]
+pub struct CompilerConfig {
+    /// Enable the solc optimizer
+    #[clap(long, value_parser)]
+    pub optimize: bool,
+
+    /// Number of optimizer runs, 200 when omitted. Implies --optimize
+    #[clap(long, value_parser, env = "OPTIMIZER_RUNS")]
+    pub optimizer_runs: Option<usize>,
+
+    /// Compile through the Yul IR pipeline
+    #[clap(long, value_parser)]
+    pub via_ir: bool,
+
+    /// Source language of the contracts. Yul objects are read from `<name>.yul` and have no ABI
+    #[clap(long, value_enum, default_value = "solidity")]
+    pub language: Language,
+
+    /// Target EVM version, e.g. `paris` or `cancun`. Defaults to the compiler's
+    #[clap(long, value_parser, env = "EVM_VERSION")]
+    pub evm_version: Option<EvmVersion>,
+
+    /// Whether a contract over the EIP-170 runtime size limit of 24576 bytes fails the build
+    #[clap(long, value_enum, default_value = "fail")]
+    pub size_limit: SizeLimit,
+
+    /// Hash of the metadata appended to the bytecode. `none` keeps the bytecode independent of source comments and paths
+    #[clap(long, value_enum)]
+    pub bytecode_hash: Option<MetadataHash>,
+
+    /// Leave the CBOR-encoded metadata out of the bytecode entirely, needs solc 0.8.18 or later
+    #[clap(long, value_parser)]
+    pub no_cbor_metadata: bool,
+
+    /// Compile every source instead of reusing the build cache for unchanged ones
+    #[clap(long, value_parser)]
+    pub no_cache: bool,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    Solidity,
+    Yul,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataHash {
+    Ipfs,
+    Bzzr1,
+    None,
+}
+
+impl From<MetadataHash> for BytecodeHash {
+    fn from(hash: MetadataHash) -> Self {
+        match hash {
+            MetadataHash::Ipfs => BytecodeHash::Ipfs,
+            MetadataHash::Bzzr1 => BytecodeHash::Bzzr1,
+            MetadataHash::None => BytecodeHash::None,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SizeLimit {
+    /// Fail before anything is deployed
+    #[default]
+    Fail,
+    /// Print a warning and carry on, e.g. for chains without the limit
+    Warn,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct CompileConfig {
+    /// Directory the compiled artifacts are written to
+    #[clap(
+        long,
+        value_parser,
+        env = "ARTIFACTS_DIR",
+        default_value = "contracts/artifacts"
+    )]
+    pub output_dir: PathBuf,
+
+    /// Compile this standard-JSON input as is and write solc's output to the output directory
+    #[clap(long, value_parser)]
+    pub standard_json: Option<PathBuf>,
+
+    /// Write the standard-JSON input the project compiles with to this file instead of compiling
+    #[clap(long, value_parser, conflicts_with = "standard_json")]
+    pub emit_standard_json: Option<PathBuf>,
+
+    /// solc version compiling a --standard-json input, e.g. `0.8.29`. The one on the PATH when omitted
+    #[clap(long, value_parser, requires = "standard_json")]
+    pub solc_version: Option<String>,
+
+    #[clap(flatten)]
+    pub compiler: CompilerConfig,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct CallConfig {
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    /// Credentials of the account `@deployer` stands for in the arguments
+    #[clap(flatten)]
+    pub signer: SignerConfig,
+
+    /// Address of the deployed contract. Resolved from the deployment manifest when omitted
+    #[clap(long, value_parser, required_unless_present = "contract_name")]
+    pub address: Option<Address>,
+
+    /// Path to an ABI or artifact JSON file
+    #[clap(long, value_parser, conflicts_with = "contract_name")]
+    pub abi: Option<PathBuf>,
+
+    /// Name of a contract in the project to take the ABI from, or a deployment as `Name@network`
+    #[clap(
+        long,
+        value_parser,
+        env = "CONTRACT_NAME",
+        required_unless_present = "abi"
+    )]
+    pub contract_name: Option<String>,
+
+    /// Method name or full signature, e.g. `getMessages` or `balanceOf(address)`
+    #[clap(long, value_parser)]
+    pub method: String,
+
+    /// Method arguments. `@Name` stands for the address of the deployment recorded under that
+    /// name on the connected chain, `@deployer` for the address of the signer
+    #[clap(long, value_parser, num_args = 1.., allow_hyphen_values = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SendConfig {
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    /// Websocket endpoint of the same node, subscribed to for new blocks while waiting for confirmations
+    #[clap(long, value_parser = parse_secret, env = "WS_URL")]
+    pub ws_url: Option<String>,
+
+    #[clap(flatten)]
+    pub signer: SignerConfig,
+
+    /// Address of the deployed contract. Resolved from the deployment manifest when omitted
+    #[clap(long, value_parser, required_unless_present = "contract_name")]
+    pub address: Option<Address>,
+
+    /// Path to an ABI or artifact JSON file
+    #[clap(long, value_parser, conflicts_with = "contract_name")]
+    pub abi: Option<PathBuf>,
+
+    /// Name of a contract in the project to take the ABI from, or a deployment as `Name@network`
+    #[clap(
+        long,
+        value_parser,
+        env = "CONTRACT_NAME",
+        required_unless_present = "abi"
+    )]
+    pub contract_name: Option<String>,
+
+    /// Method name or full signature, e.g. `writeMessage` or `transfer(address,uint256)`
+    #[clap(long, value_parser)]
+    pub method: String,
+
+    /// Method arguments. `@Name` stands for the address of the deployment recorded under that
+    /// name on the connected chain, `@deployer` for the address of the signer
+    #[clap(long, value_parser, num_args = 1.., allow_hyphen_values = true)]
+    pub args: Vec<String>,
+
+    #[clap(flatten)]
+    pub fees: FeeConfig,
+
+    /// Amount to send along with the transaction, in wei unless a unit is given, e.g. `1ether`
+    #[clap(long, value_parser = parse_amount)]
+    pub value: Option<U256>,
+
+    /// Number of confirmations to wait for
+    #[clap(long, value_parser, env = "CONFIRMATIONS", default_value_t = 1)]
+    pub confirmations: usize,
+
+    /// Nonce of the transaction, e.g. to replace a pending one. Taken from the node when omitted
+    #[clap(long, value_parser = parse_u256)]
+    pub nonce: Option<U256>,
+
+    /// Take over the deployment lock of the network from another run, e.g. one that died on another machine
+    #[clap(long, value_parser)]
+    pub force_unlock: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct AccountsConfig {
+    /// RPC endpoint to read balances and nonces from. Only the addresses are listed when omitted
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: Option<String>,
+
+    /// Credentials to list the accounts of. Anvil's default accounts when none are given
+    #[clap(flatten)]
+    pub signer: SignerConfig,
+
+    /// Number of accounts derived from a mnemonic, from --account-index on
+    #[clap(long, value_parser, default_value_t = 10)]
+    pub count: u32,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ChainInfoConfig {
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    /// Websocket endpoint of the node to check as well
+    #[clap(long, value_parser = parse_secret, env = "WS_URL")]
+    pub ws_url: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct FundConfig {
+    /// Account to fund, an address or `@Name` of a deployment of the chain
+    #[clap(value_parser)]
+    pub account: String,
+
+    /// Amount to fund, in wei unless a unit is given, e.g. `1ether`
+    #[clap(value_parser = parse_amount)]
+    pub amount: U256,
+
+    /// Only top the account up to the amount, sending nothing when it holds as much already
+    #[clap(long, value_parser)]
+    pub at_least: bool,
+
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    /// Credentials of the account sending the transfer. Local nodes need none
+    #[clap(flatten)]
+    pub signer: SignerConfig,
+
+    #[clap(flatten)]
+    pub fees: FeeConfig,
+
+    /// Number of confirmations to wait for
+    #[clap(long, value_parser, env = "CONFIRMATIONS", default_value_t = 1)]
+    pub confirmations: usize,
+
+    /// Take over the deployment lock of the network from another run, e.g. one that died on another machine
+    #[clap(long, value_parser)]
+    pub force_unlock: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ConsoleConfig {
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    /// Credentials used to send transactions. Only read-only methods can be called without them
+    #[clap(flatten)]
+    pub signer: SignerConfig,
+
+    /// Address of the deployed contract. Resolved from the deployment manifest when omitted
+    #[clap(long, value_parser, required_unless_present = "contract_name")]
+    pub address: Option<Address>,
+
+    /// Path to an ABI or artifact JSON file
+    #[clap(long, value_parser, conflicts_with = "contract_name")]
+    pub abi: Option<PathBuf>,
+
+    /// Name of a contract in the project to take the ABI from, or a deployment as `Name@network`
+    #[clap(
+        long,
+        value_parser,
+        env = "CONTRACT_NAME",
+        required_unless_present = "abi"
+    )]
+    pub contract_name: Option<String>,
+
+    #[clap(flatten)]
+    pub fees: FeeConfig,
+
+    /// Number of confirmations to wait for on every transaction
+    #[clap(long, value_parser, env = "CONFIRMATIONS", default_value_t = 1)]
+    pub confirmations: usize,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct LogsConfig {
+    /// Contract whose compiled ABI decodes the logs, or a deployment as `Name@network`
+    #[clap(value_parser)]
+    pub contract_name: String,
+
+    /// Event name or full signature, e.g. `MessageWritten` or `Transfer(address,address,uint256)`
+    #[clap(value_parser)]
+    pub event: String,
+
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    /// Address of the deployed contract. Resolved from the deployment manifest when omitted
+    #[clap(long, value_parser)]
+    pub address: Option<Address>,
+
+    /// First block to search, e.g. `1200` or `earliest`. Defaults to the deployment block
+    #[clap(long, value_parser)]
+    pub from_block: Option<BlockNumber>,
+
+    #[clap(long, value_parser, default_value = "latest")]
+    pub to_block: BlockNumber,
+
+    #[clap(long, value_enum, default_value_t = LogsFormat::Table)]
+    pub format: LogsFormat,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct EventsConfig {
+    /// HTTP or websocket endpoint. Websockets subscribe to new logs, HTTP polls for them
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    /// Websocket endpoint of the same node to subscribe to new logs with, when --rpc-url is an HTTP one
+    #[clap(long, value_parser = parse_secret, env = "WS_URL")]
+    pub ws_url: Option<String>,
+
+    /// Address of the deployed contract. Resolved from the deployment manifest when omitted
+    #[clap(long, value_parser, required_unless_present = "contract_name")]
+    pub address: Option<Address>,
+
+    /// Path to an ABI or artifact JSON file
+    #[clap(long, value_parser, conflicts_with = "contract_name")]
+    pub abi: Option<PathBuf>,
+
+    /// Name of a contract in the project to take the ABI from, or a deployment as `Name@network`
+    #[clap(
+        long,
+        value_parser,
+        env = "CONTRACT_NAME",
+        required_unless_present = "abi"
+    )]
+    pub contract_name: Option<String>,
+
+    /// First block to print events of. Defaults to the first block, or to the next one with --follow
+    #[clap(long, value_parser)]
+    pub from_block: Option<u64>,
+
+    /// Keep streaming new events until interrupted, reconnecting when the node drops
+    #[clap(long, value_parser)]
+    pub follow: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct StorageConfig {
+    /// Contract whose storage layout decodes the slots, or a deployment as `Name@network`
+    #[clap(value_parser)]
+    pub contract_name: String,
+
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    /// Address of the deployed contract. Resolved from the deployment manifest when omitted
+    #[clap(long, value_parser)]
+    pub address: Option<Address>,
+
+    /// Print the raw word of a slot, decimal or `0x` hex, instead of decoding variables
+    #[clap(long, value_parser = parse_u256, conflicts_with = "variable")]
+    pub slot: Option<U256>,
+
+    /// Only decode this state variable
+    #[clap(long, value_parser)]
+    pub variable: Option<String>,
+
+    /// Mapping key, array index or struct member to select, repeated for nested ones,
+    /// e.g. `--variable balances --key 0xabc..`
+    #[clap(long = "key", value_parser, requires = "variable")]
+    pub keys: Vec<String>,
+
+    /// Block to read the storage at. Defaults to the latest
+    #[clap(long, value_parser)]
+    pub block: Option<u64>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct LayoutConfig {
+    #[clap(value_parser)]
+    pub contract_name: String,
+
+    #[clap(long, value_enum, default_value_t = LayoutFormat::Table)]
+    pub format: LayoutFormat,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutFormat {
+    /// One line per state variable
+    Table,
+    /// JSON array of state variables
+    Json,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct DecodeCalldataConfig {
+    /// Hex calldata, starting with the 4-byte function selector
+    #[clap(value_parser)]
+    pub calldata: Bytes,
+
+    /// Path to an ABI or artifact JSON file to decode with instead of the project's contracts
+    #[clap(long, value_parser)]
+    pub abi: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct AbiEncodeConfig {
+    /// Function signature, e.g. `transfer(address,uint256)`, or the method name with --abi or --contract-name
+    #[clap(value_parser)]
+    pub signature: String,
+
+    /// Arguments, in the same literal forms as those of `send`
+    #[clap(value_parser, allow_hyphen_values = true)]
+    pub args: Vec<String>,
+
+    /// Only the encoded arguments, without the 4-byte selector, e.g. for constructor arguments
+    #[clap(long, value_parser)]
+    pub no_selector: bool,
+
+    /// Path to an ABI or artifact JSON file to look the method up in
+    #[clap(long, value_parser, conflicts_with = "contract_name")]
+    pub abi: Option<PathBuf>,
+
+    /// Contract of the project to look the method up in
+    #[clap(long, value_parser)]
+    pub contract_name: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct AbiDecodeConfig {
+    /// Function signature, e.g. `transfer(address,uint256)` or `balanceOf(address) returns (uint256)`, or the method name with --abi or --contract-name
+    #[clap(value_parser)]
+    pub signature: String,
+
+    /// Hex calldata starting with the selector, or return data with --output
+    #[clap(value_parser)]
+    pub data: Bytes,
+
+    /// Decode return data against the outputs of the function instead of calldata
+    #[clap(long, value_parser)]
+    pub output: bool,
+
+    /// Path to an ABI or artifact JSON file to look the method up in
+    #[clap(long, value_parser, conflicts_with = "contract_name")]
+    pub abi: Option<PathBuf>,
+
+    /// Contract of the project to look the method up in
+    #[clap(long, value_parser)]
+    pub contract_name: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct TraceConfig {
+    #[clap(value_parser)]
+    pub tx_hash: H256,
+
+    /// Node supporting `debug_traceTransaction` or `trace_transaction`, e.g. Anvil
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct TxConfig {
+    #[clap(subcommand)]
+    pub command: TxCommand,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub enum TxCommand {
+    /// Resend a pending transaction at the same nonce with higher fees
+    #[clap()]
+    Bump(TxReplaceConfig),
+    /// Replace a pending transaction with an empty transfer to the sender itself
+    #[clap()]
+    Cancel(TxReplaceConfig),
+    /// List the transactions recorded in the transaction database, most recent first
+    #[clap()]
+    List(TxListConfig),
+    /// Print everything the transaction database recorded about a transaction
+    #[clap()]
+    Show(TxShowConfig),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct TxListConfig {
+    /// Only the transactions of this chain id
+    #[clap(long, value_parser)]
+    pub chain_id: Option<u64>,
+
+    /// Only the transactions with this status
+    #[clap(long, value_enum)]
+    pub status: Option<TxStatus>,
+
+    /// Most transactions to list
+    #[clap(long, value_parser, default_value_t = 20)]
+    pub limit: usize,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct TxShowConfig {
+    /// Hash of the transaction
+    #[clap(value_parser)]
+    pub tx_hash: H256,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct TxReplaceConfig {
+    /// Hash of the pending transaction
+    #[clap(value_parser)]
+    pub tx_hash: H256,
+
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    /// Credentials of the account that sent the transaction
+    #[clap(flatten)]
+    pub signer: SignerConfig,
+
+    /// Lowest fees of the replacement. The fees of the pending transaction are always raised by --bump-percent
+    #[clap(flatten)]
+    pub fees: FeeConfig,
+
+    /// Percentage the fees of the pending transaction are raised by. Nodes require at least 10
+    #[clap(
+        long,
+        value_parser = clap::value_parser!(u64).range(10..),
+        default_value_t = 15
+    )]
+    pub bump_percent: u64,
+
+    /// Number of confirmations to wait for
+    #[clap(long, value_parser, env = "CONFIRMATIONS", default_value_t = 1)]
+    pub confirmations: usize,
+
+    /// Take over the deployment lock of the network from another run, e.g. one that died on another machine
+    #[clap(long, value_parser)]
+    pub force_unlock: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct InitConfig {
+    /// Folder to create the project in
+    #[clap(value_parser, default_value = ".")]
+    pub path: PathBuf,
+
+    /// Overwrite files that already exist
+    #[clap(long, value_parser)]
+    pub force: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct CleanConfig {
+    /// Directory `compile` wrote the artifacts to
+    #[clap(
+        long,
+        value_parser,
+        env = "ARTIFACTS_DIR",
+        default_value = "contracts/artifacts"
+    )]
+    pub output_dir: PathBuf,
+
+    /// Chain whose manifests without code at their address are removed. Local chain manifests are removed unless checked
+    #[clap(long, value_parser)]
+    pub rpc_url: Option<String>,
+
+    /// Only print what would be removed
+    #[clap(long, value_parser)]
+    pub dry_run: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SecretsConfig {
+    #[clap(subcommand)]
+    pub command: SecretsCommand,
+}
+
+// The passphrase of the store is taken from `SECRETS_PASSPHRASE` or prompted
+// for, the store is `SECRETS_FILE` or `secrets.json` of the config directory
+#[derive(Parser, Debug, Clone)]
+pub enum SecretsCommand {
+    /// Encrypt a secret under a name, replacing a previous one. The value is prompted for without echo
+    #[clap()]
+    Set(SecretNameConfig),
+    /// Print a decrypted secret
+    #[clap()]
+    Get(SecretNameConfig),
+    /// List the names of the stored secrets
+    #[clap()]
+    List,
+    /// Remove a secret
+    #[clap()]
+    Remove(SecretNameConfig),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SecretNameConfig {
+    /// Name of the secret, referred to as `secret:<name>` in network profiles
+    #[clap(value_parser)]
+    pub name: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct RegistryConfig {
+    #[clap(subcommand)]
+    pub command: RegistryCommand,
+}
+
+// Deployments are referred to as `Name@network`, the network being a profile
+// of `solidity-deploy.toml`, a chain name or a chain id
+#[derive(Parser, Debug, Clone)]
+pub enum RegistryCommand {
+    /// List the recorded deployments
+    #[clap()]
+    List(RegistryListConfig),
+    /// Print the record of a deployment, e.g. `MessageStorage@sepolia`
+    #[clap()]
+    Show(RegistryEntryConfig),
+    /// Remove the record of a deployment
+    #[clap()]
+    Rm(RegistryRmConfig),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct RegistryListConfig {
+    /// Only list the deployments of this network
+    #[clap(value_parser, value_name = "NETWORK")]
+    pub only: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct RegistryEntryConfig {
+    /// Deployment as `Name@network`
+    #[clap(value_parser)]
+    pub reference: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct RegistryRmConfig {
+    /// Deployment as `Name@network`
+    #[clap(value_parser)]
+    pub reference: String,
+
+    /// Remove the record even if a proxy's record refers to it
+    #[clap(long, value_parser)]
+    pub force: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SignConfig {
+    #[clap(long, value_parser, env = "CONTRACT_NAME")]
+    pub contract_name: String,
+
+    /// Constructor arguments, e.g. `--constructor-args 42 0xabc.. "[1,2]" "(1,hello)"`
+    #[clap(long, value_parser, num_args = 1.., allow_hyphen_values = true)]
+    pub constructor_args: Vec<String>,
+
+    /// Chain the transaction is for, by name or id. Nothing is asked from a node
+    #[clap(long, value_parser = parse_chain, env = "CHAIN")]
+    pub chain: ChainInfo,
+
+    /// Nonce of the signing account on that chain
+    #[clap(long, value_parser = parse_u256)]
+    pub nonce: U256,
+
+    /// Gas limit of the deployment, e.g. from `estimate`
+    #[clap(long, value_parser = parse_u256)]
+    pub gas_limit: U256,
+
+    /// Gas price of a legacy transaction, e.g. `30gwei`
+    #[clap(
+        long,
+        value_parser = parse_amount,
+        required_unless_present = "max_fee_per_gas",
+        conflicts_with_all = ["max_fee_per_gas", "max_priority_fee_per_gas"]
+    )]
+    pub gas_price: Option<U256>,
+
+    /// Most the transaction pays per gas, e.g. `40gwei`
+    #[clap(long, value_parser = parse_amount, requires = "max_priority_fee_per_gas")]
+    pub max_fee_per_gas: Option<U256>,
+
+    /// Tip per gas for the block producer, e.g. `2gwei`
+    #[clap(long, value_parser = parse_amount, requires = "max_fee_per_gas")]
+    pub max_priority_fee_per_gas: Option<U256>,
+
+    /// Native token sent to the constructor, in wei unless a unit is given
+    #[clap(long, value_parser = parse_amount)]
+    pub value: Option<U256>,
+
+    #[clap(flatten)]
+    pub signer: SignerConfig,
+
+    /// File to write the signed transaction to, as JSON, instead of printing it
+    #[clap(long, value_parser)]
+    pub out: Option<PathBuf>,
+
+    /// Print the raw signed transaction as hex instead of JSON
+    #[clap(long, value_parser, conflicts_with = "out")]
+    pub raw: bool,
+
+    #[clap(flatten)]
+    pub compiler: CompilerConfig,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct BroadcastConfig {
+    /// Signed transaction written by `sign`, as JSON or raw hex
+    #[clap(value_parser)]
+    pub file: PathBuf,
+
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    /// Number of confirmations to wait for
+    #[clap(long, value_parser, env = "CONFIRMATIONS", default_value_t = 1)]
+    pub confirmations: usize,
+
+    /// Take over the deployment lock of the network from another run, e.g. one that died on another machine
+    #[clap(long, value_parser)]
+    pub force_unlock: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SignTypedDataConfig {
+    /// JSON payload as given to `eth_signTypedData_v4`, with `types`, `primaryType`, `domain` and `message`. `-` reads it from stdin
+    #[clap(value_parser)]
+    pub file: PathBuf,
+
+    #[clap(flatten)]
+    pub signer: SignerConfig,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SnapshotConfig {
+    #[clap(subcommand)]
+    pub command: SnapshotCommand,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub enum SnapshotCommand {
+    /// Snapshot the current state, printing the id to revert to
+    #[clap()]
+    Take(SnapshotTakeConfig),
+    /// Roll back to a snapshot and take a new one of the same state, since reverting uses up the snapshot
+    #[clap()]
+    Revert(SnapshotRevertConfig),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SnapshotTakeConfig {
+    /// Endpoint of the local Anvil or Hardhat node
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SnapshotRevertConfig {
+    /// Id of the snapshot, as printed by `snapshot take`
+    #[clap(value_parser = parse_u256)]
+    pub id: U256,
+
+    /// Endpoint of the local Anvil or Hardhat node
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasReportFormat {
+    /// Min, average and max gas per function, like `forge test --gas-report`
+    Text,
+    /// JSON object of the same figures by contract
+    Json,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeployTarget {
+    /// Ethereum and the chains running the EVM as is
+    Evm,
+    /// zkSync Era, whose EraVM runs bytecode of its own
+    Zksync,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogsFormat {
+    /// One line per log with its decoded arguments
+    Table,
+    /// JSON array of logs
+    Json,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct VerifyConfig {
+    /// Chain the contract is deployed on, by name or id, e.g. `sepolia`
+    #[clap(long, value_parser = parse_chain, env = "CHAIN")]
+    pub chain: ChainInfo,
+
+    #[clap(long, value_parser = parse_secret, env = "ETHERSCAN_API_KEY")]
+    pub etherscan_api_key: String,
+
+    /// Address of the deployed contract. Resolved from the deployment manifest when omitted
+    #[clap(long, value_parser)]
+    pub address: Option<Address>,
+
+    #[clap(long, value_parser, env = "CONTRACT_NAME")]
+    pub contract_name: String,
+
+    /// Constructor arguments the contract was deployed with
+    #[clap(long, value_parser, num_args = 1.., allow_hyphen_values = true)]
+    pub constructor_args: Vec<String>,
+
+    /// Full solc version, e.g. `v0.8.29+commit.ab55807c`. Taken from the manifest or detected when omitted
+    #[clap(long, value_parser)]
+    pub compiler_version: Option<String>,
+
+    #[clap(flatten)]
+    pub compiler: CompilerConfig,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct VerifyBytecodeConfig {
+    /// Contract in the project to compare with
+    #[clap(value_parser)]
+    pub contract_name: String,
+
+    /// Address of the deployed contract
+    #[clap(value_parser)]
+    pub address: Address,
+
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    #[clap(flatten)]
+    pub compiler: CompilerConfig,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct PredictAddressConfig {
+    /// Deploying account, or the CREATE2 factory. Defaults to the deterministic deployment proxy with --salt
+    #[clap(long, value_parser, required_unless_present = "salt")]
+    pub deployer: Option<Address>,
+
+    /// Nonce of the deploying account (CREATE)
+    #[clap(
+        long,
+        value_parser,
+        required_unless_present = "salt",
+        conflicts_with = "salt"
+    )]
+    pub nonce: Option<u64>,
+
+    /// CREATE2 salt as hex, left-padded to 32 bytes
+    #[clap(long, value_parser = parse_salt)]
+    pub salt: Option<H256>,
+
+    /// Hex init code, i.e. creation bytecode followed by the encoded constructor arguments
+    #[clap(
+        long,
+        value_parser,
+        requires = "salt",
+        conflicts_with = "contract_name"
+    )]
+    pub init_code: Option<Bytes>,
+
+    /// Name of a contract in the project to build the init code from
+    #[clap(long, value_parser, requires = "salt")]
+    pub contract_name: Option<String>,
+
+    #[clap(
+        long,
+        value_parser,
+        num_args = 1..,
+        allow_hyphen_values = true,
+        requires = "contract_name"
+    )]
+    pub constructor_args: Vec<String>,
+
+    #[clap(flatten)]
+    pub compiler: CompilerConfig,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct MineSaltConfig {
+    /// Hex digits the address must start with, e.g. `0xdead`
+    #[clap(long, value_parser = parse_nibbles, required_unless_present = "suffix")]
+    pub prefix: Option<Vec<u8>>,
+
+    /// Hex digits the address must end with, e.g. `beef`
+    #[clap(long, value_parser = parse_nibbles)]
+    pub suffix: Option<Vec<u8>>,
+
+    /// Name of a contract in the project to build the init code from
+    #[clap(
+        long,
+        value_parser,
+        env = "CONTRACT_NAME",
+        required_unless_present = "init_code"
+    )]
+    pub contract_name: Option<String>,
+
+    #[clap(
+        long,
+        value_parser,
+        num_args = 1..,
+        allow_hyphen_values = true,
+        requires = "contract_name"
+    )]
+    pub constructor_args: Vec<String>,
+
+    /// Hex init code, i.e. creation bytecode followed by the encoded constructor arguments
+    #[clap(long, value_parser, conflicts_with = "contract_name")]
+    pub init_code: Option<Bytes>,
+
+    /// CREATE2 factory deploying the contract. Defaults to the deterministic deployment proxy `deploy --create2` goes through
+    #[clap(long, value_parser)]
+    pub deployer: Option<Address>,
+
+    /// Threads searching, all cores by default
+    #[clap(long, value_parser)]
+    pub threads: Option<usize>,
+
+    #[clap(flatten)]
+    pub compiler: CompilerConfig,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct UpgradeConfig {
+    #[clap(long, value_parser = parse_secret, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    #[clap(flatten)]
+    pub signer: SignerConfig,
+
+    /// Name the proxy is recorded under in the deployment manifest
+    #[clap(long, value_parser)]
+    pub name: String,
+
+    /// Contract of the new implementation. Defaults to the contract currently behind the proxy
+    #[clap(long, value_parser)]
+    pub contract_name: Option<String>,
+
+    #[clap(long, value_parser, num_args = 1.., allow_hyphen_values = true)]
+    pub constructor_args: Vec<String>,
+
+    /// Method called through the proxy as part of the upgrade, e.g. `initializeV2`
+    #[clap(long, value_parser)]
+    pub call: Option<String>,
+
+    #[clap(
+        long,
+        value_parser,
+        num_args = 1..,
+        allow_hyphen_values = true,
+        requires = "call"
+    )]
+    pub call_args: Vec<String>,
+
+    /// Upgrade even when the storage layout check fails or cannot be done
+    #[clap(long, value_parser)]
+    pub force: bool,
+
+    /// Take over the deployment lock of the network from another run, e.g. one that died on another machine
+    #[clap(long, value_parser)]
+    pub force_unlock: bool,
+
+    #[clap(flatten)]
+    pub safe: SafeConfig,
+
+    /// Number of confirmations to wait for on every transaction
+    #[clap(long, value_parser, env = "CONFIRMATIONS", default_value_t = 1)]
+    pub confirmations: usize,
+
+    #[clap(flatten)]
+    pub fees: FeeConfig,
+
+    #[clap(flatten)]
+    pub compiler: CompilerConfig,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct FlattenConfig {
+    /// Contract to flatten, read from `contracts/<name>.sol`
+    #[clap(value_parser)]
+    pub contract_name: String,
+
+    /// File to write the flattened source to instead of printing it
+    #[clap(long, value_parser)]
+    pub out: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub compiler: CompilerConfig,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct AbiConfig {
+    #[clap(value_parser)]
+    pub contract_name: String,
+
+    #[clap(long, value_enum, default_value_t = AbiFormat::Pretty)]
+    pub format: AbiFormat,
+
+    /// File to write the ABI to instead of printing it
+    #[clap(long, value_parser)]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbiFormat {
+    /// Compact JSON on a single line
+    Json,
+    /// Indented JSON
+    Pretty,
+    /// One signature per line, e.g. `function writeMessage(string message)`
+    Human,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SelectorsConfig {
+    #[clap(value_parser, required_unless_present = "check_collisions")]
+    pub contract_name: Option<String>,
+
+    /// Fail when different functions of the project share a selector, as they would behind one proxy
+    #[clap(long, value_parser)]
+    pub check_collisions: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ConvertConfig {
+    /// Amount, optionally followed by its unit, e.g. `1.5ether` or `30gwei`
+    #[clap(value_parser)]
+    pub value: String,
+
+    /// Unit of the amount when it has none, a name such as `gwei` or the decimals of a token such as `6`. Wei by default
+    #[clap(long, value_parser)]
+    pub from: Option<String>,
+
+    /// Unit to convert to, a name or a number of decimals. Wei, gwei and ether when omitted
+    #[clap(long, value_parser)]
+    pub to: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct HashConfig {
+    /// Text to hash, a hex string with --hex, or a function or event signature
+    #[clap(value_parser, allow_hyphen_values = true)]
+    pub input: String,
+
+    #[clap(long, value_enum, default_value = "keccak")]
+    pub kind: HashKind,
+
+    /// Hash the bytes of a hex string such as `0x1234` instead of the text
+    #[clap(long, value_parser)]
+    pub hex: bool,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashKind {
+    /// keccak256 of the input
+    Keccak,
+    /// 4-byte selector of a function signature, e.g. `transfer(address,uint256)`
+    Selector,
+    /// topic0 of an event signature, e.g. `Transfer(address indexed,address indexed,uint256)`
+    Topic,
+    /// Storage slot of an ERC-1967 style name, keccak256 minus one, e.g. `eip1967.proxy.implementation`
+    Slot,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct AbiDiffConfig {
+    #[clap(value_parser)]
+    pub contract_name: String,
+
+    /// Previous ABI or artifact JSON file to compare with
+    #[clap(long, value_parser, required_unless_present = "chain")]
+    pub abi: Option<PathBuf>,
+
+    /// Compare with the ABI recorded in the deployment manifest of this chain, by name or id
+    #[clap(long, value_parser = parse_chain, conflicts_with = "abi")]
+    pub chain: Option<ChainInfo>,
+
+    /// Deployment name in the manifest. Defaults to the contract name
+    #[clap(long, value_parser, requires = "chain")]
+    pub name: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct BindgenConfig {
+    /// Contracts to generate bindings for. All of them when omitted
+    #[clap(value_parser)]
+    pub contract_names: Vec<String>,
+
+    /// Module directory the bindings are written to
+    #[clap(long, value_parser, default_value = "src/bindings")]
+    pub out: PathBuf,
+
+    /// Write all bindings into a single `mod.rs` instead of one file per contract
+    #[clap(long, value_parser)]
+    pub single_file: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ExportTsConfig {
+    /// Contracts to export. All of them when omitted
+    #[clap(value_parser)]
+    pub contract_names: Vec<String>,
+
+    /// Directory the `<Contract>.ts` modules are written to
+    #[clap(long, value_parser, default_value = "deployments/ts")]
+    pub out: PathBuf,
+}
+
+pub async fn build_config() -> Result<Config> {
+    // The environment file and the profile only set defaults, so they are
+    // applied before the flags are parsed, the file first so it wins over the
+    // profile
+    env::load_env_file()?;
+    network::apply_project_settings()?;
+    network::apply_network_profile()?;
+    let mut config = Config::parse();
+    // Other commands would print nothing at all
+    if config.json && !config.command.reports_json() {
+        return Err(eyre!(
+            "--json is supported by deploy, compile, send, upgrade, broadcast and tx bump/cancel only"
+        ));
+    }
+    if network::ask_network(&config)? {
+        config = Config::parse();
+    }
+    network::check_environment_profile(&config).await?;
+    Ok(config)
+}
+
+// Value of a flag given on the command line, read before clap parses it
+pub fn flag_before_parsing(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1).take_while(|arg| arg != "--");
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+        if let Some(value) = arg
+            .strip_prefix(flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(value.to_owned());
+        }
+    }
+    None
+}
+
+// Parse an amount of wei, optionally with a unit suffix: `1000`, `30gwei`, `0.5ether`
+pub fn parse_amount(value: &str) -> Result<U256, String> {
+    let value = value.trim();
+    let (amount, unit) = match value.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(index) => value.split_at(index),
+        None => (value, "wei"),
+    };
+    parse_units(amount.trim(), unit.trim())
+        .map(Into::into)
+        .map_err(|e| format!("invalid amount {value:?}: {e}"))
+}
+
+// Resolve a chain name or id through the chain registry
+pub fn parse_chain(value: &str) -> Result<ChainInfo, String> {
+    resolve_chain(value).map_err(|e| e.to_string())
+}
+
+// Parse a percentile between 0 and 100
+pub fn parse_percentile(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(percentile) if (0.0..=100.0).contains(&percentile) => Ok(percentile),
+        _ => Err(format!("invalid percentile {value:?}, expected 0 to 100")),
+    }
+}
+
+// Parse a 256-bit number, decimal or `0x` hex
+pub fn parse_u256(value: &str) -> Result<U256, String> {
+    let value = value.trim();
+    let parsed = match value.strip_prefix("0x") {
+        Some(digits) => U256::from_str_radix(digits, 16).map_err(|e| e.to_string()),
+        None => U256::from_dec_str(value).map_err(|e| e.to_string()),
+    };
+    parsed.map_err(|e| format!("invalid number {value:?}: {e}"))
+}
+
+// Parse a hex salt of up to 32 bytes, left-padding it with zeros
+pub fn parse_salt(value: &str) -> Result<H256, String> {
+    let digits = value.trim().trim_start_matches("0x");
+    let bytes = hex::decode(digits).map_err(|e| format!("invalid salt {value:?}: {e}"))?;
+    if bytes.len() > 32 {
+        return Err(format!("invalid salt {value:?}: longer than 32 bytes"));
+    }
+    let mut salt = [0u8; 32];
+    salt[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(H256(salt))
+}
+
+// Parse hex digits of an address pattern, one nibble each, e.g. `0xdead`
+pub fn parse_nibbles(value: &str) -> Result<Vec<u8>, String> {
+    let digits = value.trim().trim_start_matches("0x");
+    if digits.is_empty() || digits.len() > 40 {
+        return Err(format!(
+            "invalid pattern {value:?}: expected 1 to 40 hex digits"
+        ));
+    }
+    digits
+        .chars()
+        .map(|c| {
+            c.to_digit(16)
+                .map(|nibble| nibble as u8)
+                .ok_or_else(|| format!("invalid pattern {value:?}: {c:?} is not a hex digit"))
+        })
+        .collect()
+}
+
+// Parse a `Name:0x..` library address
+pub fn parse_library(value: &str) -> Result<(String, Address), String> {
+    let (name, address) = value
+        .split_once(':')
+        .ok_or_else(|| format!("invalid library {value:?}, expected Name:0x.."))?;
+    let address = address
+        .parse()
+        .map_err(|e| format!("invalid library address {address:?}: {e}"))?;
+    Ok((name.to_owned(), address))
 }
 
-pub fn build_config() -> Config {
-    Config::parse()
+// Parse a `<format>:<dir>` artifacts source, e.g. `hardhat:./artifacts`
+pub fn parse_artifacts_source(value: &str) -> Result<ArtifactsSource, String> {
+    match value.split_once(':') {
+        Some(("hardhat", dir)) if !dir.is_empty() => {
+            Ok(ArtifactsSource::Hardhat(PathBuf::from(dir)))
+        }
+        _ => Err(format!(
+            "invalid artifacts source {value:?}, expected hardhat:<dir>"
+        )),
+    }
 }
@@ -0,0 +1,294 @@
+use crate::cli_config::{CompilerConfig, Language, SizeLimit};
+use crate::error::DeployError;
+use crate::output::{self, CompiledContract, Event};
+use crate::progress::{self, Progress};
+use crate::remappings;
+use ethers_solc::artifacts::output_selection::ContractOutputSelection;
+use ethers_solc::artifacts::{Settings, SettingsMetadata};
+use ethers_solc::{
+    Artifact, ConfigurableArtifacts, Project, ProjectCompileOutput, ProjectPathsConfig, SolcConfig,
+};
+use eyre::{eyre, ContextCompat, Ok, Result};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::{debug, info, instrument, warn};
+
+// Solidity sources of the project, a folder or a single `.sol` file, set from
+// `--contracts-dir` once the command line is parsed
+static CONTRACTS: OnceLock<PathBuf> = OnceLock::new();
+// Record of the compiled sources and their artifacts, relative to the working
+// directory, so unchanged sources are not compiled again
+const BUILD_CACHE_DIR: &str = "cache/";
+// Largest runtime bytecode a contract may deploy, per EIP-170
+const MAX_RUNTIME_SIZE: usize = 24_576;
+
+/// Set the sources compiled by default, a folder or a single `.sol` file.
+/// Only the first call has an effect, later ones are ignored.
+pub fn set_contracts_path(path: PathBuf) {
+    let _ = CONTRACTS.set(path);
+}
+
+/// Sources given with `--contracts-dir`, `contracts/` by default
+pub fn contracts_path() -> &'static Path {
+    CONTRACTS
+        .get()
+        .map_or(Path::new("contracts/"), |path| path.as_path())
+}
+
+/// Folder holding the sources, the one of the file when a single file is given
+pub fn contracts_dir() -> &'static Path {
+    source_dir(contracts_path())
+}
+
+fn source_dir(path: &Path) -> &Path {
+    if !path.is_file() {
+        return path;
+    }
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Function to compile a Solidity project from the given root folder path, or
+/// only the given file and its imports. Sources unchanged since the last build
+/// are taken from the build cache instead of being compiled again.
+/// Artifacts are written to `artifacts_dir` when given, to the cache otherwise.
+#[instrument(skip_all, fields(root = %root.display()))]
+pub async fn compile(
+    root: &Path,
+    artifacts_dir: Option<&Path>,
+    compiler: &CompilerConfig,
+) -> Result<ProjectCompileOutput<ConfigurableArtifacts>> {
+    let project = build_project(root, artifacts_dir, compiler)?;
+
+    // Compile the Solidity project
+    // Counting the sources reads them, so only done when progress is shown
+    let progress = match root.is_file() || !progress::enabled() {
+        true => Progress::spinner(format!("Compiling {}", root.display())),
+        false => Progress::spinner(format!(
+            "Compiling {} source files",
+            project.sources()?.len()
+        )),
+    };
+    let output = if root.is_file() {
+        project.compile_file(root)?
+    } else {
+        project.compile()?
+    };
+    drop(progress);
+
+    if output.is_unchanged() {
+        info!("No sources changed, using cached artifacts");
+    }
+    for file in output.compiled_artifacts().0.keys() {
+        info!("Compiled {file}");
+    }
+
+    // Check if there were any compiler errors
+    if output.has_compiler_errors() {
+        Err(DeployError::CompileFailed {
+            errors: output
+                .output()
+                .errors
+                .iter()
+                .filter(|error| error.severity.is_error())
+                .map(ToString::to_string)
+                .collect(),
+        }
+        .into())
+    } else {
+        output::emit(Event::Compiled {
+            contracts: output
+                .clone()
+                .into_artifacts()
+                .map(|(id, artifact)| CompiledContract {
+                    name: id.name,
+                    init_size: artifact.get_bytecode_bytes().map_or(0, |code| code.len()),
+                    runtime_size: artifact
+                        .get_deployed_bytecode_bytes()
+                        .map_or(0, |code| code.len()),
+                })
+                .collect(),
+        });
+        Ok(output.clone()) // Return the compiled output if successful
+    }
+}
+
+/// Full solc version a contract was compiled with, e.g. `0.8.29+commit.ab55807c`
+pub fn compiler_version(
+    output: &ProjectCompileOutput<ConfigurableArtifacts>,
+    contract_name: &str,
+) -> Result<String> {
+    output
+        .clone()
+        .into_artifacts()
+        .find(|(id, _)| id.name == contract_name)
+        .map(|(id, _)| id.version.to_string())
+        .with_context(|| format!("Contract {contract_name} not found in compiler output"))
+}
+
+/// Build the Solidity project for the given root folder path, keeping the
+/// compiler settings around for later steps such as verification
+pub fn build_project(
+    root: &Path,
+    artifacts_dir: Option<&Path>,
+    compiler: &CompilerConfig,
+) -> Result<Project> {
+    if !root.exists() {
+        return Err(eyre!("Project root {root:?} does not exist!")); // Error handling for non-existent project root
+    }
+    let root = source_dir(root); // The folder of a single source file is the root
+
+    // Dependencies live next to the contracts folder, in the working directory
+    let base = std::env::current_dir()?;
+    let remappings = remappings::detect_remappings(&base)?;
+
+    // Define the paths to be used for the Solidity project
+    let cache_dir = base.join(BUILD_CACHE_DIR);
+    let paths = ProjectPathsConfig::builder()
+        .root(root)
+        .sources(root)
+        .remappings(remappings)
+        .libs([base.join("lib"), base.join("node_modules")])
+        .allowed_path(&base)
+        .cache(cache_dir.join("solidity-files-cache.json"))
+        .artifacts(artifacts_dir.map_or(cache_dir.join("artifacts"), Path::to_path_buf))
+        .build()?; // Build the project path configuration
+
+    // Storage layouts are requested so proxy upgrades can be checked for
+    // safety and storage can be decoded and listed. Yul has no storage layout, solc rejects the selection.
+    let extra_output = match compiler.language {
+        Language::Solidity => vec![ContractOutputSelection::StorageLayout],
+        Language::Yul => vec![],
+    };
+    let mut settings = Settings::default().with_extra_output(extra_output.clone());
+    // Optimizer and EVM settings are always explicit so builds are reproducible
+    settings.optimizer.enabled = Some(compiler.optimize || compiler.optimizer_runs.is_some());
+    settings.optimizer.runs = Some(compiler.optimizer_runs.unwrap_or(200));
+    if compiler.via_ir {
+        settings.via_ir = Some(true);
+    }
+    if let Some(evm_version) = compiler.evm_version {
+        settings.evm_version = Some(evm_version);
+    }
+    // Metadata settings are left to solc unless given, so default builds match
+    // those of other tools
+    if compiler.bytecode_hash.is_some() || compiler.no_cbor_metadata {
+        settings.metadata = Some(SettingsMetadata {
+            bytecode_hash: compiler.bytecode_hash.map(Into::into),
+            cbor_metadata: compiler.no_cbor_metadata.then_some(false),
+            ..Default::default()
+        });
+    }
+    let artifacts = ConfigurableArtifacts::new(extra_output, []);
+
+    // Build the project object, enabling auto-detection of the Solidity compiler
+    let mut builder = Project::builder()
+        .paths(paths)
+        .solc_config(SolcConfig::builder().settings(settings).build())
+        .artifacts(artifacts)
+        .set_auto_detect(true); // Automatically detect Solidity compiler
+    if compiler.no_cache {
+        builder = builder.ephemeral(); // Compile every source without touching the cache
+        if artifacts_dir.is_none() {
+            builder = builder.no_artifacts(); // Avoid writing artifacts to disk
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// Log the constructor and functions of every compiled contract
+pub async fn print_project(project: ProjectCompileOutput<ConfigurableArtifacts>) -> Result<()> {
+    let artifacts = project.into_artifacts(); // Extract the compiled artifacts (contracts)
+    for (id, artifact) in artifacts {
+        let name = id.name; // Get the contract's name
+        let abi = artifact.abi.context("No ABI found for artifact {name}")?; // Get the ABI and ensure it exists
+
+        debug!("CONTRACT: {:?}", name); // Log the contract name
+
+        let contract = &abi.abi;
+        let functions = contract.functions(); // Get the list of functions from the contract
+        let functions = functions.cloned(); // Clone the function list for iteration
+        let constructor = contract.constructor(); // Get the constructor if available
+
+        // If the contract has a constructor, log its arguments
+        if let Some(constructor) = constructor {
+            let args = &constructor.inputs;
+            debug!("CONSTRUCTOR args: {:?}", args); // Log the constructor arguments
+        }
+
+        // Log each function's name and parameters
+        for func in functions {
+            let name = &func.name; // Get the function name
+            let params = &func.inputs; // Get the function parameters
+            debug!("FUNCTION {name} {params:?}"); // Log function details
+        }
+    }
+    Ok(())
+}
+
+/// Check the runtime bytecode of every contract against the EIP-170 limit,
+/// which otherwise only surfaces as a failed deployment without a reason
+pub fn check_contract_sizes(
+    output: &ProjectCompileOutput<ConfigurableArtifacts>,
+    size_limit: SizeLimit,
+) -> Result<()> {
+    let oversized = output
+        .clone()
+        .into_artifacts()
+        .filter_map(|(id, artifact)| {
+            let size = artifact.get_deployed_bytecode_bytes()?.len();
+            (size > MAX_RUNTIME_SIZE).then(|| format!("{} ({size} bytes)", id.name))
+        })
+        .collect::<Vec<_>>();
+    if oversized.is_empty() {
+        return Ok(());
+    }
+    let message = format!(
+        "Runtime bytecode exceeds the EIP-170 limit of {MAX_RUNTIME_SIZE} bytes: {}",
+        oversized.join(", ")
+    );
+    match size_limit {
+        SizeLimit::Fail => Err(eyre!(
+            "{message}. Enable the optimizer, split the contract or pass --size-limit warn"
+        )),
+        SizeLimit::Warn => {
+            warn!("{message}");
+            Ok(())
+        }
+    }
+}
+
+/// Print a per-contract summary of bytecode sizes and any compiler warnings
+pub fn print_compile_summary(output: &ProjectCompileOutput<ConfigurableArtifacts>) {
+    println!("{}", "=".repeat(80));
+    println!(
+        "{:<40} {:>18} {:>18} {:>10}",
+        "CONTRACT", "INIT CODE (bytes)", "RUNTIME (bytes)", "MARGIN"
+    );
+    for (id, artifact) in output.clone().into_artifacts() {
+        let init_size = artifact.get_bytecode_bytes().map_or(0, |code| code.len());
+        let runtime_size = artifact
+            .get_deployed_bytecode_bytes()
+            .map_or(0, |code| code.len());
+        // Bytes left under the runtime size limit, negative when over it
+        let margin = MAX_RUNTIME_SIZE as i64 - runtime_size as i64;
+        println!(
+            "{:<40} {:>18} {:>18} {:>10}",
+            id.name, init_size, runtime_size, margin
+        );
+    }
+
+    let warnings = output
+        .output()
+        .errors
+        .iter()
+        .filter(|error| error.severity.is_warning())
+        .collect::<Vec<_>>();
+    println!("{}", "=".repeat(80));
+    println!("WARNINGS: {}", warnings.len());
+    for warning in warnings {
+        warn!("{warning}");
+    }
+}
@@ -0,0 +1,236 @@
+use crate::abi_args::{parse_tokens, print_named_tokens};
+use crate::abi_export::human_readable;
+use crate::cli_config::ConsoleConfig;
+use crate::contract_abi::{find_function, function_signature};
+use crate::interact::{call_method, print_logs, print_receipt, resolve_contract, send_method};
+use crate::rpc::{self, RpcProvider};
+use crate::signer::wallet_from_config;
+use ethers::abi::{Abi, StateMutability};
+use ethers::middleware::SignerMiddleware;
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::Address;
+use ethers_providers::Middleware;
+use eyre::{eyre, ContextCompat, Result};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::{Context, Editor, Helper, Highlighter, Hinter, Validator};
+
+// Console commands besides the contract's methods
+const BUILTINS: [&str; 3] = ["help", "exit", "quit"];
+
+// Entry point of the `console` subcommand: a prompt reading one method call
+// per line, e.g. `writeMessage "hello world"`. View and pure methods are
+// called with `eth_call`, all others are sent as transactions.
+pub async fn console(config: ConsoleConfig) -> Result<()> {
+    let provider = rpc::connect(&config.rpc_url)?;
+    let (address, abi) = resolve_contract(
+        &provider,
+        config.address,
+        config.abi.as_deref(),
+        config.contract_name.as_deref(),
+    )
+    .await?;
+
+    let client = match wallet_from_config(&config.signer)? {
+        Some(wallet) => {
+            let chain_id = provider.get_chainid().await?;
+            let wallet = wallet.with_chain_id(chain_id.as_u64());
+            println!("Sender: {:?}", wallet.address());
+            Some(SignerMiddleware::new(provider.clone(), wallet))
+        }
+        None => {
+            println!("No credentials given, only read-only methods can be called");
+            None
+        }
+    };
+
+    let mut editor = Editor::<MethodCompleter, DefaultHistory>::new()?;
+    editor.set_helper(Some(MethodCompleter::new(&abi)));
+    println!("Console for {address:?}, type `help` to list the methods");
+
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+
+        match line {
+            "exit" | "quit" => break,
+            "help" => {
+                for signature in human_readable(&abi)
+                    .into_iter()
+                    .filter(|signature| signature.starts_with("function "))
+                {
+                    println!("  {signature}");
+                }
+            }
+            _ => {
+                let result =
+                    run_command(&provider, client.as_ref(), &config, &abi, address, line).await;
+                if let Err(e) = result {
+                    println!("Error: {e}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Call or send the method on one console line
+async fn run_command(
+    provider: &RpcProvider,
+    client: Option<&SignerMiddleware<RpcProvider, LocalWallet>>,
+    config: &ConsoleConfig,
+    abi: &Abi,
+    address: Address,
+    line: &str,
+) -> Result<()> {
+    let mut words = split_words(line)?;
+    let method = words.remove(0);
+    let function = find_function(abi, &method, words.len())?;
+    let tokens = parse_tokens(&function.inputs, &words)?;
+
+    match function.state_mutability {
+        StateMutability::View | StateMutability::Pure => {
+            let decoded = call_method(provider, abi, address, function, &tokens).await?;
+            println!("RETURNS:");
+            print_named_tokens(&function.outputs, &decoded);
+        }
+        StateMutability::NonPayable | StateMutability::Payable => {
+            let client = client.with_context(|| {
+                format!(
+                    "{} changes state, restart the console with --private-key, --keystore or --mnemonic to send it",
+                    function_signature(function)
+                )
+            })?;
+            let receipt = send_method(
+                client,
+                abi,
+                address,
+                function,
+                &tokens,
+                None,
+                &config.fees,
+                config.confirmations,
+                None,
+            )
+            .await?;
+            print_receipt(&receipt);
+            print_logs(abi, &receipt.logs);
+        }
+    }
+    Ok(())
+}
+
+// Split a console line into words. Quotes group words with spaces, brackets
+// and parentheses keep arrays and tuples together, e.g.
+// `writeMessage "hello world"` or `setValues [1, 2, 3]`.
+// Quotes inside arrays and tuples are kept for the ABI tokenizer.
+fn split_words(line: &str) -> Result<Vec<String>> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut depth = 0usize;
+    for c in line.chars() {
+        match quote {
+            Some(open) => {
+                if c != open || depth > 0 {
+                    word.push(c);
+                }
+                if c == open {
+                    quote = None;
+                }
+            }
+            None => match c {
+                '"' | '\'' => {
+                    if depth > 0 {
+                        word.push(c);
+                    }
+                    quote = Some(c);
+                    in_word = true;
+                }
+                '[' | '(' => {
+                    depth += 1;
+                    word.push(c);
+                    in_word = true;
+                }
+                ']' | ')' => {
+                    depth = depth.saturating_sub(1);
+                    word.push(c);
+                    in_word = true;
+                }
+                c if c.is_whitespace() && depth == 0 => {
+                    if in_word {
+                        words.push(std::mem::take(&mut word));
+                        in_word = false;
+                    }
+                }
+                c => {
+                    word.push(c);
+                    in_word = true;
+                }
+            },
+        }
+    }
+    if quote.is_some() {
+        return Err(eyre!("Unterminated quote in {line:?}"));
+    }
+    if depth > 0 {
+        return Err(eyre!("Unbalanced brackets in {line:?}"));
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+// Completes the first word of a line with the method names and full
+// signatures of the contract
+#[derive(Helper, Highlighter, Hinter, Validator)]
+struct MethodCompleter {
+    candidates: Vec<String>,
+}
+
+impl MethodCompleter {
+    fn new(abi: &Abi) -> Self {
+        let mut candidates = BUILTINS.map(str::to_owned).to_vec();
+        for function in abi.functions() {
+            candidates.push(function.name.clone());
+            candidates.push(function_signature(function));
+        }
+        candidates.sort();
+        candidates.dedup();
+        MethodCompleter { candidates }
+    }
+}
+
+impl Completer for MethodCompleter {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let prefix = &line[..pos];
+        if prefix.contains(char::is_whitespace) {
+            return Ok((pos, vec![]));
+        }
+        let matches = self
+            .candidates
+            .iter()
+            .filter(|candidate| candidate.starts_with(prefix))
+            .cloned()
+            .collect();
+        Ok((0, matches))
+    }
+}
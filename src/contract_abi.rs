@@ -0,0 +1,144 @@
+use crate::cli_config::CompilerConfig;
+use crate::compiler::{compile, contracts_path};
+use ethers::abi::{Abi, AbiParser, Event, Function};
+use ethers_solc::Artifact;
+use eyre::{eyre, ContextCompat, Result};
+use std::path::Path;
+
+// Load a contract ABI either from a JSON file or by compiling the project and
+// looking the contract up by name
+pub async fn load_abi(abi_path: Option<&Path>, contract_name: Option<&str>) -> Result<Abi> {
+    match (abi_path, contract_name) {
+        (Some(path), _) => load_abi_file(path),
+        (None, Some(name)) => {
+            // Compiler settings do not affect the ABI
+            let project = compile(contracts_path(), None, &CompilerConfig::default()).await?;
+            let artifact = project.find_first(name).with_context(|| {
+                format!(
+                    "Contract {name} not found in {}",
+                    contracts_path().display()
+                )
+            })?;
+            artifact
+                .get_abi()
+                .map(|abi| abi.into_owned())
+                .with_context(|| format!("Missing abi from contract {name}"))
+        }
+        (None, None) => Err(eyre!("Either an ABI file or a contract name is required")),
+    }
+}
+
+// Read an ABI from disk. Accepts both a bare ABI array and a compiler
+// artifact (ethers-solc, Hardhat, Foundry) with an `abi` field.
+pub fn load_abi_file(path: &Path) -> Result<Abi> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| eyre!("Failed to read ABI file {path:?}: {e}"))?;
+    let json: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| eyre!("Failed to parse ABI file {path:?}: {e}"))?;
+    let abi = match json {
+        serde_json::Value::Object(mut artifact) => artifact
+            .remove("abi")
+            .with_context(|| format!("No `abi` field in artifact {path:?}"))?,
+        abi => abi,
+    };
+    Ok(serde_json::from_value(abi)?)
+}
+
+// Resolve a method either by its full signature (`transfer(address,uint256)`)
+// or by name, using the argument count to pick between overloads
+pub fn find_function<'a>(abi: &'a Abi, method: &str, arg_count: usize) -> Result<&'a Function> {
+    if method.contains('(') {
+        let wanted = method.replace(' ', "");
+        return abi
+            .functions()
+            .find(|function| function_signature(function) == wanted)
+            .with_context(|| format!("Function {method} not found in ABI"));
+    }
+
+    let candidates = abi
+        .functions_by_name(method)
+        .map_err(|_| eyre!("Function {method} not found in ABI"))?;
+    let mut matching = candidates
+        .iter()
+        .filter(|function| function.inputs.len() == arg_count);
+    match (matching.next(), matching.next()) {
+        (Some(function), None) => Ok(function),
+        (Some(_), Some(_)) => Err(eyre!(
+            "Function {method} is overloaded, use the full signature, e.g. {}",
+            function_signature(&candidates[0])
+        )),
+        (None, _) => Err(eyre!(
+            "Function {method} does not take {arg_count} argument(s), expected one of: {}",
+            candidates
+                .iter()
+                .map(function_signature)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+// Parse a function from its signature alone, e.g. `transfer(address,uint256)`
+// or `balanceOf(address) returns (uint256)` for one whose outputs matter
+pub fn parse_signature(signature: &str) -> Result<Function> {
+    AbiParser::default()
+        .parse_function(signature)
+        .map_err(|e| eyre!("Invalid function signature {signature:?}: {e}"))
+}
+
+// Parse an event from its signature alone, e.g. `Transfer(address indexed,address,uint256)`
+pub fn parse_event_signature(signature: &str) -> Result<Event> {
+    let signature = signature.trim();
+    let declaration = match signature.starts_with("event ") {
+        true => signature.to_owned(),
+        false => format!("event {signature}"),
+    };
+    AbiParser::default()
+        .parse_event(&declaration)
+        .map_err(|e| eyre!("Invalid event signature {signature:?}: {e}"))
+}
+
+// Canonical signature without outputs, e.g. `writeMessage(string)`
+pub fn function_signature(function: &Function) -> String {
+    let inputs = function
+        .inputs
+        .iter()
+        .map(|param| param.kind.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{}({inputs})", function.name)
+}
+
+// Resolve an event either by its full signature (`Transfer(address,address,uint256)`)
+// or by a name that is not overloaded
+pub fn find_event<'a>(abi: &'a Abi, event: &str) -> Result<&'a Event> {
+    if event.contains('(') {
+        let wanted = event.replace(' ', "");
+        return abi
+            .events()
+            .find(|candidate| event_signature(candidate) == wanted)
+            .with_context(|| format!("Event {event} not found in ABI"));
+    }
+
+    let candidates = abi
+        .events_by_name(event)
+        .map_err(|_| eyre!("Event {event} not found in ABI"))?;
+    match candidates.as_slice() {
+        [event] => Ok(event),
+        _ => Err(eyre!(
+            "Event {event} is overloaded, use the full signature, e.g. {}",
+            event_signature(&candidates[0])
+        )),
+    }
+}
+
+// Canonical event signature, e.g. `MessageWritten(string,address)`
+pub fn event_signature(event: &Event) -> String {
+    let inputs = event
+        .inputs
+        .iter()
+        .map(|param| param.kind.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{}({inputs})", event.name)
+}
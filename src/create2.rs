@@ -0,0 +1,61 @@
+use ethers::types::{Address, Bytes, TransactionRequest, H160, H256};
+use ethers::utils::{get_create2_address_from_hash, keccak256};
+use ethers_providers::Middleware;
+use eyre::{eyre, Result};
+
+// Deterministic deployment proxy (github.com/Arachnid/deterministic-deployment-proxy),
+// present at this address on most EVM chains. It takes `salt ++ init code` as
+// calldata and creates the contract with CREATE2.
+pub const CREATE2_FACTORY: Address = H160([
+    0x4e, 0x59, 0xb4, 0x48, 0x47, 0xb3, 0x79, 0x57, 0x85, 0x88, 0x92, 0x0c, 0xa7, 0x8f, 0xbf, 0x26,
+    0xc0, 0xb4, 0x95, 0x6c,
+]);
+
+pub fn init_code_hash(init_code: &[u8]) -> H256 {
+    keccak256(init_code).into()
+}
+
+// Address a CREATE2 deployment from `deployer` with `salt` ends up at
+pub fn create2_address(deployer: Address, salt: H256, init_code_hash: H256) -> Address {
+    get_create2_address_from_hash(deployer, salt.as_bytes(), init_code_hash.as_bytes())
+}
+
+// Transaction deploying `init_code` through the deterministic deployment proxy
+pub fn create2_transaction(salt: H256, init_code: &Bytes) -> TransactionRequest {
+    let mut data = salt.as_bytes().to_vec();
+    data.extend_from_slice(init_code);
+    TransactionRequest::new()
+        .to(CREATE2_FACTORY)
+        .data(Bytes::from(data))
+}
+
+// Make sure the proxy exists on the chain and nothing lives at `address` yet
+pub async fn ensure_deployable<M: Middleware>(client: &M, address: Address) -> Result<()>
+where
+    M::Error: 'static,
+{
+    if client.get_code(CREATE2_FACTORY, None).await?.is_empty() {
+        return Err(eyre!(
+            "Deterministic deployment proxy {CREATE2_FACTORY:?} is not deployed on this chain"
+        ));
+    }
+    if !client.get_code(address, None).await?.is_empty() {
+        return Err(eyre!(
+            "A contract is already deployed at {address:?}, pick another salt"
+        ));
+    }
+    Ok(())
+}
+
+// Check the contract landed at the expected address
+pub async fn ensure_deployed<M: Middleware>(client: &M, address: Address) -> Result<()>
+where
+    M::Error: 'static,
+{
+    if client.get_code(address, None).await?.is_empty() {
+        return Err(eyre!(
+            "CREATE2 deployment left no code at the expected address {address:?}"
+        ));
+    }
+    Ok(())
+}
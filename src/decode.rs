@@ -0,0 +1,57 @@
+use crate::abi_args::print_named_tokens;
+use crate::cli_config::{CompilerConfig, DecodeCalldataConfig};
+use crate::compiler::{compile, contracts_path};
+use crate::contract_abi::{function_signature, load_abi_file};
+use ethers::abi::Abi;
+use ethers::utils::hex;
+use eyre::{eyre, ContextCompat, Result};
+
+// Entry point of the `decode-calldata` subcommand: find the functions whose
+// selector matches the calldata and print their decoded arguments
+pub async fn decode_calldata(config: DecodeCalldataConfig) -> Result<()> {
+    let selector = config
+        .calldata
+        .get(..4)
+        .context("Calldata is shorter than a 4-byte function selector")?;
+    let abis = match &config.abi {
+        Some(path) => vec![(path.display().to_string(), load_abi_file(path)?)],
+        None => project_abis().await?,
+    };
+
+    let mut decoded = 0;
+    for (contract_name, abi) in &abis {
+        for function in abi
+            .functions()
+            .filter(|function| function.short_signature() == selector)
+        {
+            let signature = function_signature(function);
+            match function.decode_input(&config.calldata[4..]) {
+                Ok(tokens) => {
+                    decoded += 1;
+                    println!("{contract_name}: {signature}");
+                    print_named_tokens(&function.inputs, &tokens);
+                }
+                Err(e) => println!(
+                    "{contract_name}: {signature} matches the selector but not the arguments: {e}"
+                ),
+            }
+        }
+    }
+    if decoded == 0 {
+        return Err(eyre!(
+            "No function with selector 0x{} decodes the calldata",
+            hex::encode(selector)
+        ));
+    }
+    Ok(())
+}
+
+// ABI of every contract in the project, by contract name
+pub async fn project_abis() -> Result<Vec<(String, Abi)>> {
+    // Compiler settings do not affect the ABI
+    let output = compile(contracts_path(), None, &CompilerConfig::default()).await?;
+    Ok(output
+        .into_artifacts()
+        .filter_map(|(id, artifact)| artifact.abi.map(|abi| (id.name, abi.abi)))
+        .collect())
+}
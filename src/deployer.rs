@@ -0,0 +1,1149 @@
+use crate::artifacts::{load_hardhat_artifacts, ContractSource};
+use crate::calls::{execute_calls, load_calls, PlannedCall};
+use crate::cli_config::{
+    AnvilConfig, ArtifactsSource, CompilerConfig, DeployConfig, DeployTarget, FeeBumpConfig,
+    SignerConfig,
+};
+use crate::compiler::{
+    build_project, check_contract_sizes, compile, contracts_dir, contracts_path, print_project,
+};
+use crate::create2::{create2_address, create2_transaction, init_code_hash, CREATE2_FACTORY};
+use crate::error::DeployError;
+use crate::fees::Fees;
+use crate::gas_report::GasReport;
+use crate::hooks::{run_hooks, HookContext, HookEvent};
+use crate::journal::{journal_path, Journal};
+use crate::keystore::read_password;
+use crate::lock::DeployLock;
+use crate::manifest::{Deployment, ProxyInfo};
+use crate::network::load_project_config;
+use crate::output::{self, Event};
+use crate::plan::{execute_plan, load_plan, plan_name, Plan};
+use crate::price_feed::usd_price;
+use crate::proxy::ProxyKind;
+use crate::revert::revert_error;
+use crate::rpc::RpcProvider;
+use crate::steps::StepRegistry;
+use crate::tx::{bump_until_mined, wait_for_receipt};
+use crate::units::format_eth;
+use crate::{
+    abi_args, audit, chains, create2, ens, fees, multichain, node_info, nonce, prompt, proxy, rpc,
+    safe, signer, simulate, smart_account, verify, watch, zksync,
+};
+use ethers::abi::{Abi, AbiEncode, Token, Tokenize};
+use ethers::contract::{Contract, ContractFactory};
+use ethers::middleware::SignerMiddleware;
+use ethers::prelude::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionReceipt, TransactionRequest, H256, U256};
+use ethers::utils::Anvil;
+use ethers_providers::{Middleware, PendingTransaction};
+use ethers_solc::artifacts::{CompactBytecode, Settings};
+use ethers_solc::{ConfigurableArtifacts, ConfigurableContractArtifact, ProjectCompileOutput};
+use eyre::{eyre, ContextCompat, Result};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// Chain id of the local Anvil chain, also used for forks of live networks
+pub const LOCAL_CHAIN_ID: u64 = 31337;
+// Forking fetches state from the remote endpoint, so Anvil takes longer to start
+const FORK_STARTUP_TIMEOUT_MS: u64 = 60_000;
+
+/// Entry point of the `deploy` subcommand: connect to the chain, or spawn a
+/// local Anvil one, then compile and deploy the contract or plan of `config`.
+/// With `--networks`, do so on every network of the list.
+pub async fn deploy(config: DeployConfig) -> Result<()> {
+    if !config.networks.is_empty() {
+        return multichain::deploy_to_networks(config).await;
+    }
+    deploy_to_chain(config).await.map(|_| ())
+}
+
+/// Deploy the contract or plan of `config` to its chain and return the
+/// deployments recorded, none for a dry run or a Safe proposal
+pub async fn deploy_to_chain(mut config: DeployConfig) -> Result<Vec<Deployment>> {
+    // zkSync Era has a compiler and deployment flow of its own
+    if config.target == DeployTarget::Zksync {
+        return zksync::deploy(config)
+            .await
+            .map(|deployment| vec![deployment]);
+    }
+
+    // Spawn a local Anvil chain unless an RPC endpoint or a running node was given
+    let anvil = match (&config.rpc_url, &config.anvil.attach) {
+        (None, None) => Some(local_chain(&config.anvil, &config.signer).spawn()),
+        _ => None,
+    };
+    let endpoint = match (&config.rpc_url, &config.anvil.attach, &anvil) {
+        (Some(rpc_url), _, _) => rpc_url.clone(),
+        (None, Some(attach), _) => attach.clone(),
+        (None, None, Some(anvil)) => anvil.endpoint(),
+        (None, None, None) => unreachable!("Anvil is spawned when no RPC URL is given"),
+    };
+    println!("HTTP Endpoint: {}", endpoint); // Print the node's HTTP endpoint
+
+    // Sign with the supplied credentials, falling back to the first Anvil account
+    let wallet: LocalWallet = match (signer::wallet_from_config(&config.signer)?, &anvil) {
+        (Some(wallet), _) => wallet,
+        (None, Some(anvil)) => anvil.keys()[0].clone().into(),
+        // A running Anvil node has the accounts of its default mnemonic
+        (None, None) if config.anvil.attach.is_some() => {
+            signer::wallet_from_mnemonic(signer::ANVIL_MNEMONIC, None, 0)?
+        }
+        // Typed in without echo when someone is at the terminal
+        (None, None) if prompt::interactive() => {
+            let private_key = read_password("Private key of the deploying account: ")?;
+            signer::wallet_from_private_key(&private_key)?
+        }
+        (None, None) => {
+            return Err(eyre!(
+                "--private-key, --keystore or --mnemonic is required when deploying to --rpc-url"
+            ))
+        }
+    };
+    let first_address = wallet.address(); // Get the wallet's address (derived from the private key)
+    println!(
+        "wallet first address: {}",
+        first_address.encode_hex() // Convert the address to hexadecimal and print it
+    );
+    // Websocket endpoints push new blocks, HTTP ones are polled for them
+    let provider = match &config.ws_url {
+        Some(ws_url) => {
+            println!("WebSocket Endpoint: {ws_url}");
+            rpc::connect_ws(ws_url).await?
+        }
+        None => rpc::connect(&endpoint)?.interval(Duration::from_millis(10)),
+    };
+    let chain_id = provider.get_chainid().await?; // Get the chain ID for the Ethereum network
+    println!("Connected to chain id {}", chain_id); // Print the chain ID
+    if config.anvil.fork_url.is_some() {
+        let block = provider.get_block_number().await?;
+        println!("Rehearsing on a fork of the live chain at block {block}");
+    }
+    let chain = match &config.chain {
+        Some(chain) => {
+            chain.check(chain_id.as_u64())?;
+            chain.clone()
+        }
+        None => chains::chain_info(chain_id.as_u64())?,
+    };
+    if config.preflight {
+        node_info::preflight(&provider, config.ws_url.as_deref(), &chain, &config.fees).await?;
+    }
+
+    // Transactions of an impersonated account are signed by the node itself
+    let sender = match config.anvil.impersonate {
+        Some(address) => {
+            impersonate(&provider, address).await?;
+            address
+        }
+        None => wallet.address(),
+    };
+    // A smart account pays for its own UserOperations, or its paymaster does
+    if config.smart_account.smart_account.is_none() {
+        let balance = signer::ensure_funded(&provider, sender).await?; // Fail fast before compiling
+        println!(
+            "Sender {} balance: {}",
+            sender.encode_hex(), // Encode the address to hexadecimal for printing
+            format_eth(balance)
+        );
+    }
+
+    let contracts = match &config.artifacts {
+        Some(ArtifactsSource::Hardhat(dir)) => {
+            let artifacts = load_hardhat_artifacts(dir)?;
+            println!(
+                "Loaded {} Hardhat artifact(s) from {}",
+                artifacts.len(),
+                dir.display()
+            );
+            ContractSource::Hardhat(artifacts)
+        }
+        None => {
+            let project = compile(contracts_path(), None, &config.compiler).await?;
+            print_project(project.clone()).await?;
+            check_contract_sizes(&project, config.compiler.size_limit)?;
+            ContractSource::Compiled(project)
+        }
+    };
+
+    // A plan is validated up front so an invalid one never touches the chain
+    let plan = match &config.plan {
+        Some(path) => Some(load_plan(path)?),
+        None => None,
+    };
+    let run_name = match (&config.plan, &config.contract_name) {
+        (Some(path), _) => plan_name(path),
+        (None, Some(contract_name)) => contract_name.clone(),
+        (None, None) if config.all => "all".to_owned(),
+        (None, None) => return Err(eyre!("Either --contract-name, --plan or --all is required")),
+    };
+    // Constructor arguments left out are asked for when someone is at the
+    // terminal, checked against the ABI type of each
+    if plan.is_none() && !config.all && config.constructor_args.is_empty() && prompt::interactive()
+    {
+        let (abi, _) = contracts.find(&run_name)?;
+        if let Some(constructor) = abi.constructor().filter(|c| !c.inputs.is_empty()) {
+            config.constructor_args = prompt::constructor_args(&run_name, &constructor.inputs)?;
+        }
+    }
+    let calls = match &config.calls {
+        Some(path) => load_calls(path)?,
+        None => vec![],
+    };
+    // Hooks of the project, then those of the plan
+    let hooks = match &plan {
+        Some(plan) => load_project_config()?.hooks.merged(&plan.hooks),
+        None => load_project_config()?.hooks,
+    };
+
+    if config.dry_run {
+        let init_code = InitCode::new(&contracts, &run_name, &config)?;
+        simulate::simulate_deployment(
+            &endpoint,
+            wallet.address(),
+            &init_code.code,
+            config.salt,
+            &init_code.abi,
+            &calls,
+        )
+        .await?;
+        return Ok(vec![]);
+    }
+
+    // A Safe cannot CREATE from a call, its deployments go through the CREATE2
+    // proxy, with a zero salt unless one is given
+    if config.safe.safe.is_some() {
+        let init_code = InitCode::new(&contracts, &run_name, &config)?;
+        let (address, data) = init_code
+            .create2(&provider, config.salt.unwrap_or_default())
+            .await?;
+        println!("CREATE2 address once executed: {address:?}");
+        safe::propose(
+            Arc::new(provider.clone()),
+            &wallet,
+            &config.safe,
+            CREATE2_FACTORY,
+            U256::zero(),
+            data,
+        )
+        .await?;
+        return Ok(vec![]);
+    }
+
+    // One run at a time broadcasts to a network, so two runs cannot race the
+    // same nonces. An Anvil chain spawned for this run is its own.
+    let _lock = anvil
+        .is_none()
+        .then(|| DeployLock::acquire(chain_id.as_u64(), "deploy", config.force_unlock))
+        .transpose()?;
+
+    // Smart accounts deploy through the CREATE2 proxy as well, from a
+    // UserOperation the bundler includes in one of its transactions
+    if let Some(smart_account) = config.smart_account.smart_account {
+        let init_code = InitCode::new(&contracts, &run_name, &config)?;
+        let salt = config.salt.unwrap_or_default();
+        let (address, data) = init_code.create2(&provider, salt).await?;
+        println!("CREATE2 address: {address:?}");
+        let fees = fees::resolve_fees(&provider, &chain.fee_config(&config.fees)).await?;
+        let receipt = smart_account::execute(
+            &provider,
+            &wallet,
+            &config.smart_account,
+            &fees,
+            CREATE2_FACTORY,
+            U256::zero(),
+            data,
+        )
+        .await?;
+        create2::ensure_deployed(&provider, address).await?;
+
+        let InitCode {
+            abi,
+            constructor_args,
+            linked,
+            ..
+        } = init_code;
+        let (settings, libraries) = with_libraries(
+            &build_project(contracts_path(), None, &config.compiler)?
+                .solc_config
+                .settings,
+            linked,
+        );
+        let deployment = Deployment {
+            name: run_name.clone(),
+            contract_name: run_name.clone(),
+            chain_id: chain_id.as_u64(),
+            address,
+            transaction_hash: receipt.transaction_hash,
+            block_number: receipt.block_number.map(|number| number.as_u64()),
+            deployer: smart_account,
+            encoded_constructor_args: ethers::abi::encode(&constructor_args).into(),
+            constructor_args: config.constructor_args.clone(),
+            compiler: contracts.compiler(&run_name, &settings)?,
+            storage_layout: contracts.storage_layout(&run_name),
+            abi,
+            salt: Some(salt),
+            proxy: None,
+            libraries,
+        };
+        println!("Contract deployed at {address:?}");
+        println!("Deployment manifest: {}", deployment.save()?.display());
+        return Ok(vec![deployment]);
+    }
+
+    let wallet = wallet.with_chain_id(chain_id.as_u64());
+    let client = Arc::new(SignerMiddleware::new(provider.clone(), wallet));
+
+    nonce::check_nonce(&provider, sender, config.nonce).await?;
+    let fees = fees::resolve_fees(&provider, &chain.fee_config(&config.fees)).await?;
+    println!("fees: {fees}");
+
+    // Every broadcast step is journaled so an interrupted run can be resumed
+    let journal = Journal::open(journal_path(chain_id.as_u64(), &run_name), config.resume)?;
+    let mut context = DeployContext {
+        client,
+        chain_id: chain_id.as_u64(),
+        contracts,
+        fees,
+        journal,
+        salt: config.salt,
+        libraries: config.libraries.iter().cloned().collect(),
+        compiler_settings: build_project(contracts_path(), None, &config.compiler)?
+            .solc_config
+            .settings,
+        gas_report: GasReport::default(),
+        confirmations: config.confirmations,
+        nonce: config.nonce,
+        fee_bump: config.fee_bump.bump_after.map(|_| config.fee_bump.clone()),
+        from: config.anvil.impersonate,
+        steps: StepRegistry::default(),
+    };
+
+    run_hooks(
+        &hooks,
+        &HookContext::new(HookEvent::PreDeploy, &run_name, &chain, sender),
+    )
+    .await?;
+    let result = async {
+        let deployments =
+            deploy_all(&mut context, &config, plan.as_ref(), &run_name, &calls).await?;
+        if let Some(ens_name) = &config.ens_name {
+            // Without a plan the run deploys a single contract, or a proxy
+            let deployment = deployments.last().context("Nothing was deployed to name")?;
+            ens::assign_name(
+                &mut context,
+                ens_name,
+                deployment.address,
+                config.ens_reverse,
+            )
+            .await?;
+        }
+        Ok::<_, eyre::Report>(deployments)
+    }
+    .await;
+    let deployments = match result {
+        Ok(deployments) => deployments,
+        Err(e) => {
+            let hook_context =
+                HookContext::new(HookEvent::OnFailure, &run_name, &chain, sender).with_error(&e);
+            if let Err(hook_error) = run_hooks(&hooks, &hook_context).await {
+                warn!("{hook_error}");
+            }
+            return Err(e);
+        }
+    };
+    context.journal.finish()?;
+    run_hooks(
+        &hooks,
+        &HookContext::new(HookEvent::PostDeploy, &run_name, &chain, sender)
+            .with_deployments(&deployments),
+    )
+    .await?;
+    if let Some(format) = config.gas_report {
+        let price = usd_price(&provider, &config.price_feed).await;
+        context.gas_report.print(format, price)?;
+    }
+
+    if config.watch {
+        watch::watch_and_redeploy(&mut context, &config, plan.as_ref(), &run_name, &calls).await?;
+        return Ok(deployments);
+    }
+
+    if config.verify {
+        let api_key = config
+            .etherscan_api_key
+            .context("--etherscan-api-key is required to verify")?;
+        for deployment in &deployments {
+            // Etherscan links ERC-1967 proxies to their implementation itself
+            let deployment = match &deployment.proxy {
+                Some(proxy) => Deployment::load(deployment.chain_id, &proxy.implementation_name)?,
+                None => deployment.clone(),
+            };
+            verify::verify_contract(
+                &chain,
+                &api_key,
+                deployment.address,
+                &deployment.contract_name,
+                &deployment.encoded_constructor_args,
+                Some(&deployment.compiler),
+            )
+            .await?;
+        }
+    }
+
+    // The local chain stops with the process, taking the contracts with it
+    if config.anvil.keep_alive {
+        println!("Local chain running at {endpoint}, press Ctrl-C to stop it");
+        tokio::signal::ctrl_c().await?;
+    }
+
+    Ok(deployments)
+}
+
+/// Deploy the plan, or the single contract (behind a proxy if asked for)
+pub async fn deploy_all(
+    context: &mut DeployContext,
+    config: &DeployConfig,
+    plan: Option<&Plan>,
+    run_name: &str,
+    calls: &[PlannedCall],
+) -> Result<Vec<Deployment>> {
+    if config.all {
+        return deploy_every_contract(context).await;
+    }
+    match plan {
+        Some(plan) => execute_plan(context, plan).await,
+        None => match config.proxy {
+            Some(kind) => Ok(vec![
+                context
+                    .deploy_proxy(
+                        run_name,
+                        run_name,
+                        &config.constructor_args,
+                        kind,
+                        config.initializer.as_deref(),
+                        &config.initializer_args,
+                        config.proxy_admin,
+                        calls,
+                    )
+                    .await?,
+            ]),
+            None => Ok(vec![
+                context
+                    .deploy_contract(run_name, run_name, &config.constructor_args, calls)
+                    .await?,
+            ]),
+        },
+    }
+}
+
+// Deploy every contract of the project that needs no constructor arguments
+async fn deploy_every_contract(context: &mut DeployContext) -> Result<Vec<Deployment>> {
+    let mut deployments = vec![];
+    for contract_name in context.contracts.deployable()? {
+        // Libraries may have been deployed already to link an earlier contract
+        if context.libraries.contains_key(&contract_name) {
+            continue;
+        }
+        let (abi, _) = context.contracts.find(&contract_name)?;
+        if abi
+            .constructor()
+            .is_some_and(|constructor| !constructor.inputs.is_empty())
+        {
+            println!(
+                "Skipping {contract_name}, its constructor takes arguments. Deploy it with --plan"
+            );
+            continue;
+        }
+        let deployment = context
+            .deploy_contract(&contract_name, &contract_name, &[], &[])
+            .await?;
+        // Later contracts linking it as a library reuse this deployment
+        context.libraries.insert(contract_name, deployment.address);
+        deployments.push(deployment);
+    }
+    Ok(deployments)
+}
+
+/// Client the transactions of a deployment are signed and sent with
+pub type DeployClient = SignerMiddleware<RpcProvider, LocalWallet>;
+
+/// Shared state of a deployment run
+pub struct DeployContext {
+    pub client: Arc<DeployClient>,
+    pub chain_id: u64,
+    pub contracts: ContractSource,
+    pub fees: Fees,
+    pub journal: Journal,
+    // Deploy through the CREATE2 proxy with this salt instead of a plain CREATE
+    pub salt: Option<H256>,
+    // Addresses of libraries to link, extended with those deployed on the way
+    pub libraries: BTreeMap<String, Address>,
+    // Compiler settings the project was built with, recorded in manifests
+    pub compiler_settings: Settings,
+    // Gas used by the deployments and calls of the run, for `--gas-report`
+    pub gas_report: GasReport,
+    // Confirmations to wait for on every transaction
+    pub confirmations: usize,
+    // Nonce of the next transaction when given with `--nonce`, otherwise the
+    // signer takes it from the node
+    pub nonce: Option<U256>,
+    // Resending of transactions that stay pending, with `--bump-after`
+    pub fee_bump: Option<FeeBumpConfig>,
+    // Account impersonated on a local chain with `--impersonate`, sending
+    // every transaction instead of the signer
+    pub from: Option<Address>,
+    // Kinds of custom plan steps
+    pub steps: StepRegistry,
+}
+
+impl DeployContext {
+    /// Deploy a contract, record its manifest under `name` and run its
+    /// post-deploy calls. Steps confirmed by a resumed run are skipped.
+    pub async fn deploy_contract(
+        &mut self,
+        name: &str,
+        contract_name: &str,
+        constructor_values: &[String],
+        calls: &[PlannedCall],
+    ) -> Result<Deployment> {
+        println!("{}", "=".repeat(80));
+        println!("DEPLOYING {name} ({contract_name})");
+        output::emit(Event::Deploying {
+            name,
+            contract_name,
+        });
+        let (abi, bytecode) = self.contracts.find(contract_name)?;
+        let (bytecode, linked) = self.link_libraries(contract_name, bytecode).await?;
+        let constructor_args = encode_constructor_args(&abi, contract_name, constructor_values)?;
+        println!("constructor args: {:?}", constructor_args);
+
+        let init_code = deploy_code(&abi, &bytecode, &constructor_args)?;
+        let (contract_address, receipt) = self.create(name, &abi, &init_code).await?;
+        let code = self.client.get_code(contract_address, None).await?;
+        self.gas_report
+            .record_deployment(name, contract_name, &receipt, code.len());
+
+        let (settings, libraries) = with_libraries(&self.compiler_settings, linked);
+
+        // Record the deployment so later commands can resolve it by name
+        let deployment = Deployment {
+            name: name.to_owned(),
+            contract_name: contract_name.to_owned(),
+            chain_id: self.chain_id,
+            address: contract_address,
+            transaction_hash: receipt.transaction_hash,
+            block_number: receipt.block_number.map(|number| number.as_u64()),
+            deployer: receipt.from,
+            abi: abi.clone(),
+            constructor_args: constructor_values.to_vec(),
+            encoded_constructor_args: ethers::abi::encode(&constructor_args).into(),
+            compiler: self.contracts.compiler(contract_name, &settings)?,
+            salt: self.salt,
+            proxy: None,
+            libraries,
+            storage_layout: self.contracts.storage_layout(contract_name),
+        };
+        println!("Deployment manifest: {}", deployment.save()?.display());
+        emit_deployed(&deployment);
+
+        let receipts = execute_calls(
+            self.client.as_ref(),
+            &abi,
+            contract_address,
+            calls,
+            self.from,
+            Some(&self.fees),
+            Some(&mut self.journal),
+            self.confirmations,
+            self.nonce.as_mut(),
+            self.fee_bump.as_ref(),
+        )
+        .await?;
+        self.gas_report.record_calls(name, calls, &receipts);
+
+        Ok(deployment)
+    }
+
+    /// Deploy a contract as the implementation behind a new ERC-1967 proxy.
+    /// The implementation is recorded as `<name>Implementation`, the proxy as
+    /// `name` with the implementation's ABI so calls go through the proxy.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn deploy_proxy(
+        &mut self,
+        name: &str,
+        contract_name: &str,
+        constructor_values: &[String],
+        kind: ProxyKind,
+        initializer: Option<&str>,
+        initializer_values: &[String],
+        admin: Option<Address>,
+        calls: &[PlannedCall],
+    ) -> Result<Deployment> {
+        let implementation_name = format!("{name}Implementation");
+        let implementation = self
+            .deploy_contract(&implementation_name, contract_name, constructor_values, &[])
+            .await?;
+
+        println!("{}", "=".repeat(80));
+        println!("DEPLOYING {name} ({kind} proxy)");
+        output::emit(Event::Deploying {
+            name,
+            contract_name,
+        });
+        let data = match initializer {
+            Some(initializer) => {
+                proxy::encode_initializer(&implementation.abi, initializer, initializer_values)?
+            }
+            None => Bytes::default(),
+        };
+        let admin = match kind {
+            ProxyKind::Transparent => Some(admin.unwrap_or(self.sender())),
+            ProxyKind::Uups => None,
+        };
+        let (proxy_abi, proxy_bytecode) = proxy::compile_proxy(kind)?;
+        let proxy_args = proxy::proxy_constructor_args(kind, implementation.address, admin, &data);
+        let init_code = deploy_code(&proxy_abi, &proxy_bytecode, &proxy_args)?;
+        // Reverts of the proxy constructor come from the initializer
+        let (proxy_address, receipt) = self.create(name, &implementation.abi, &init_code).await?;
+        let code = self.client.get_code(proxy_address, None).await?;
+        self.gas_report
+            .record_deployment(name, contract_name, &receipt, code.len());
+
+        let deployment = Deployment {
+            name: name.to_owned(),
+            contract_name: contract_name.to_owned(),
+            chain_id: self.chain_id,
+            address: proxy_address,
+            transaction_hash: receipt.transaction_hash,
+            block_number: receipt.block_number.map(|number| number.as_u64()),
+            deployer: receipt.from,
+            abi: implementation.abi.clone(),
+            constructor_args: vec![],
+            encoded_constructor_args: ethers::abi::encode(&proxy_args).into(),
+            compiler: implementation.compiler.clone(),
+            salt: self.salt,
+            proxy: Some(ProxyInfo {
+                kind,
+                implementation: implementation.address,
+                implementation_name,
+                admin,
+            }),
+            storage_layout: None,
+            libraries: BTreeMap::new(),
+        };
+        println!("Deployment manifest: {}", deployment.save()?.display());
+        emit_deployed(&deployment);
+
+        let receipts = execute_calls(
+            self.client.as_ref(),
+            &deployment.abi,
+            proxy_address,
+            calls,
+            self.from,
+            Some(&self.fees),
+            Some(&mut self.journal),
+            self.confirmations,
+            self.nonce.as_mut(),
+            self.fee_bump.as_ref(),
+        )
+        .await?;
+        self.gas_report.record_calls(name, calls, &receipts);
+
+        Ok(deployment)
+    }
+
+    /// Account the transactions of the run are sent from
+    pub fn sender(&self) -> Address {
+        self.from.unwrap_or(self.client.address())
+    }
+
+    // Fill in the library placeholders of a contract's bytecode, deploying the
+    // libraries without a known address first. Returns the linked bytecode and
+    // the `(source file, library, address)` of every linked library.
+    async fn link_libraries(
+        &mut self,
+        contract_name: &str,
+        mut bytecode: CompactBytecode,
+    ) -> Result<(Bytes, Vec<(String, String, Address)>)> {
+        let mut linked = vec![];
+        for (file, libraries) in bytecode.link_references.clone() {
+            for library in libraries.into_keys() {
+                let address = match self.libraries.get(&library) {
+                    Some(address) => *address,
+                    None => {
+                        println!("{contract_name} needs library {library}, deploying it first");
+                        let deployment =
+                            Box::pin(self.deploy_contract(&library, &library, &[], &[])).await?;
+                        self.libraries.insert(library.clone(), deployment.address);
+                        deployment.address
+                    }
+                };
+                println!("Linking library {library} at {address:?}");
+                bytecode.link(&file, &library, address);
+                linked.push((file.clone(), library, address));
+            }
+        }
+        let bytecode = bytecode
+            .object
+            .into_bytes()
+            .with_context(|| format!("Contract {contract_name} still has unlinked libraries"))?;
+        Ok((bytecode, linked))
+    }
+
+    // Send the creation transaction of `init_code` as the journal step
+    // `deploy {name}` and return the address of the new contract. Reverts are
+    // decoded with `abi`.
+    async fn create(
+        &mut self,
+        name: &str,
+        abi: &Abi,
+        init_code: &Bytes,
+    ) -> Result<(Address, TransactionReceipt)> {
+        let expected_address = self
+            .salt
+            .map(|salt| create2_address(CREATE2_FACTORY, salt, init_code_hash(init_code)));
+        if let Some(expected_address) = expected_address {
+            println!("CREATE2 address: {expected_address:?}");
+        }
+
+        let step = format!("deploy {name}");
+        let receipt = match self.journal.completed(self.client.as_ref(), &step).await? {
+            Some(receipt) => receipt,
+            None => {
+                let mut tx: TypedTransaction = match (self.salt, expected_address) {
+                    (Some(salt), Some(expected_address)) => {
+                        create2::ensure_deployable(self.client.as_ref(), expected_address).await?;
+                        create2_transaction(salt, init_code).into()
+                    }
+                    _ => TransactionRequest::new().data(init_code.clone()).into(),
+                };
+                if let Some(from) = self.from {
+                    tx.set_from(from);
+                }
+                self.fees.apply(&mut tx);
+                if let Some(nonce) = self.nonce.as_mut() {
+                    tx.set_nonce(*nonce);
+                    *nonce += U256::one();
+                }
+                let pending_tx = self
+                    .client
+                    .send_transaction(tx.clone(), None)
+                    .await
+                    .map_err(|e| revert_error(e, Some(abi), &format!("Deployment of {name}")))?;
+                audit::record(self.client.as_ref(), &step, &tx, pending_tx.tx_hash()).await;
+                self.journal.record_sent(&step, pending_tx.tx_hash())?;
+                let transaction = pending_tx.tx_hash();
+                let receipt = wait_for_receipt(
+                    self.client.as_ref(),
+                    pending_tx,
+                    tx,
+                    Some(&self.fees),
+                    self.fee_bump.as_ref(),
+                    self.confirmations,
+                    |hash| self.journal.record_sent(&step, hash),
+                )
+                .await?
+                .ok_or(DeployError::Dropped { transaction })?;
+                if receipt.status.is_some_and(|status| status.is_zero()) {
+                    return Err(eyre!(
+                        "Deployment of {name} reverted in transaction {:?}",
+                        receipt.transaction_hash
+                    ));
+                }
+                self.journal.record_confirmed(&step)?;
+                output::emit(Event::tx_confirmed(&receipt));
+                receipt
+            }
+        };
+        let contract_address = match expected_address {
+            Some(expected_address) => {
+                create2::ensure_deployed(self.client.as_ref(), expected_address).await?;
+                expected_address
+            }
+            None => receipt
+                .contract_address
+                .context("Deployment receipt has no contract address")?,
+        };
+        println!(
+            "Contract address: {}",
+            contract_address.encode_hex() // Print the deployed contract's address
+        );
+        Ok((contract_address, receipt))
+    }
+}
+
+// Init code of the contract a dry run, Safe proposal or smart account
+// deploys on its own: its bytecode with the `--libraries` linked, as these
+// cannot deploy missing libraries first, and the constructor arguments
+struct InitCode {
+    abi: Abi,
+    constructor_args: Vec<Token>,
+    code: Bytes,
+    // `(source file, library, address)` of every linked library
+    linked: Vec<(String, String, Address)>,
+}
+
+impl InitCode {
+    fn new(contracts: &ContractSource, contract_name: &str, config: &DeployConfig) -> Result<Self> {
+        let (abi, mut bytecode) = contracts.find(contract_name)?;
+        let mut linked = vec![];
+        for (file, libraries) in bytecode.link_references.clone() {
+            for library in libraries.into_keys() {
+                let (_, address) = config
+                    .libraries
+                    .iter()
+                    .find(|(name, _)| *name == library)
+                    .with_context(|| {
+                        format!("Contract {contract_name} uses library {library}, link it with --libraries")
+                    })?;
+                println!("Linking library {library} at {address:?}");
+                bytecode.link(&file, &library, *address);
+                linked.push((file.clone(), library, *address));
+            }
+        }
+        let bytecode = bytecode
+            .object
+            .into_bytes()
+            .with_context(|| format!("Contract {contract_name} still has unlinked libraries"))?;
+        let constructor_args =
+            encode_constructor_args(&abi, contract_name, &config.constructor_args)?;
+        let code = deploy_code(&abi, &bytecode, &constructor_args)?;
+        Ok(InitCode {
+            abi,
+            constructor_args,
+            code,
+            linked,
+        })
+    }
+
+    // Address of the contract deployed through the CREATE2 proxy with `salt`,
+    // checked to be free, and the calldata of the proxy deploying it
+    async fn create2(&self, provider: &RpcProvider, salt: H256) -> Result<(Address, Bytes)> {
+        let address = create2_address(CREATE2_FACTORY, salt, init_code_hash(&self.code));
+        create2::ensure_deployable(provider, address).await?;
+        let data = create2_transaction(salt, &self.code)
+            .data
+            .unwrap_or_default();
+        Ok((address, data))
+    }
+}
+
+// Compiler settings with the linked libraries, which are part of those needed
+// to verify, and the libraries by name for the manifest
+fn with_libraries(
+    settings: &Settings,
+    linked: Vec<(String, String, Address)>,
+) -> (Settings, BTreeMap<String, Address>) {
+    let mut settings = settings.clone();
+    let mut libraries = BTreeMap::new();
+    for (file, library, address) in linked {
+        settings
+            .libraries
+            .libs
+            .entry(PathBuf::from(file))
+            .or_default()
+            .insert(library.clone(), format!("{address:?}"));
+        libraries.insert(library, address);
+    }
+    (settings, libraries)
+}
+
+fn emit_deployed(deployment: &Deployment) {
+    output::emit(Event::Deployed {
+        name: &deployment.name,
+        contract_name: &deployment.contract_name,
+        chain_id: deployment.chain_id,
+        address: deployment.address,
+        transaction_hash: deployment.transaction_hash,
+        block_number: deployment.block_number,
+    });
+}
+
+// Let the local node send transactions for `address` without its key, topping
+// up its balance so it can pay for gas
+async fn impersonate(provider: &RpcProvider, address: Address) -> Result<()> {
+    provider
+        .request::<_, ()>("anvil_impersonateAccount", [address])
+        .await?;
+    let minimum = U256::exp10(22); // 10,000 ether
+    if provider.get_balance(address, None).await? < minimum {
+        provider
+            .request::<_, ()>("anvil_setBalance", (address, minimum))
+            .await?;
+    }
+    println!("Impersonating {address:?}");
+    Ok(())
+}
+
+// Local Anvil chain shaped by the `--port`, `--block-time`, `--fork-url`, ...
+// options, with the accounts of the signer's mnemonic when one is given
+fn local_chain(config: &AnvilConfig, signer: &SignerConfig) -> Anvil {
+    let mut anvil = Anvil::new();
+    if config.block_time > 0 {
+        anvil = anvil.block_time(config.block_time);
+    }
+    if let Some(port) = config.port {
+        anvil = anvil.port(port);
+    }
+    if let Some(fork_url) = &config.fork_url {
+        // A fork keeps the chain id of a local chain, so the manifests of a
+        // rehearsal never overwrite the ones of the forked network
+        anvil = anvil
+            .fork(fork_url)
+            .chain_id(LOCAL_CHAIN_ID)
+            .timeout(FORK_STARTUP_TIMEOUT_MS);
+    }
+    if let Some(fork_block) = config.fork_block {
+        anvil = anvil.fork_block_number(fork_block);
+    }
+    if let Some(accounts) = config.accounts {
+        anvil = anvil.args(["--accounts".to_owned(), accounts.to_string()]);
+    }
+    if let Some(balance) = config.balance {
+        anvil = anvil.args(["--balance".to_owned(), balance.to_string()]);
+    }
+    if let Some(hardfork) = &config.hardfork {
+        anvil = anvil.args(["--hardfork", hardfork.as_str()]);
+    }
+    if let Some(mnemonic) = &signer.mnemonic {
+        anvil = anvil.mnemonic(mnemonic);
+    }
+    if let Some(derivation_path) = &signer.derivation_path {
+        anvil = anvil.args(["--derivation-path", derivation_path.as_str()]);
+    }
+    anvil
+}
+
+/// Find a compiled contract by name in its `<name>.sol` source file and
+/// return its ABI and creation bytecode
+pub fn find_contract(
+    project: &ProjectCompileOutput<ConfigurableArtifacts>,
+    contract_name: &str,
+) -> Result<(Abi, Bytes)> {
+    let (abi, bytecode) = find_unlinked_contract(project, contract_name)?;
+    let libraries = bytecode
+        .link_references
+        .values()
+        .flat_map(|libraries| libraries.keys().cloned())
+        .collect::<Vec<_>>();
+    let bytecode = bytecode.object.into_bytes().with_context(|| {
+        format!(
+            "Contract {contract_name} uses the libraries {}, deploy it with `deploy` to link them",
+            libraries.join(", ")
+        )
+    })?;
+    Ok((abi, bytecode))
+}
+
+/// Like `find_contract`, but the bytecode may still hold library placeholders.
+/// Contracts without their own `<name>.sol`, such as libraries declared next to
+/// the contract using them, are looked up by name alone.
+pub fn find_unlinked_contract(
+    project: &ProjectCompileOutput<ConfigurableArtifacts>,
+    contract_name: &str,
+) -> Result<(Abi, CompactBytecode)> {
+    let (contract, is_yul) = find_artifact(project, contract_name)?;
+    let abi = match contract.abi {
+        Some(abi) => abi.abi,
+        None if is_yul => Abi::default(), // Yul objects come without an ABI
+        None => return Err(eyre!("Missing abi from contract")), // Ensure that ABI is available
+    };
+    let bytecode = contract
+        .bytecode
+        .context("Missing bytecode from contract")?; // Ensure that bytecode is available
+    Ok((abi, bytecode))
+}
+
+/// Compiler output of a contract, looked up in `<name>.sol` or, for Yul
+/// objects without a Solidity source, `<name>.yul`. Also tells whether it is a
+/// Yul object.
+pub fn find_artifact(
+    project: &ProjectCompileOutput<ConfigurableArtifacts>,
+    contract_name: &str,
+) -> Result<(ConfigurableContractArtifact, bool)> {
+    let source_path = ["sol", "yul"]
+        .map(|extension| contracts_dir().join(format!("{contract_name}.{extension}")))
+        .into_iter()
+        .find(|path| path.exists())
+        .unwrap_or_default();
+    let is_yul = source_path
+        .extension()
+        .is_some_and(|extension| extension == "yul");
+    let contract = match std::fs::canonicalize(source_path) {
+        Ok(contract_absolute_path) => {
+            println!("contract path: {}", contract_absolute_path.display());
+            project
+                .find(contract_absolute_path.to_str().unwrap(), contract_name) // Find the contract by its name and path
+                .cloned()
+        }
+        Err(_) => project
+            .clone()
+            .into_artifacts()
+            .find(|(id, _)| id.name == contract_name)
+            .map(|(_, artifact)| artifact),
+    }
+    .ok_or_else(|| DeployError::ArtifactMissing {
+        contract: contract_name.to_owned(),
+    })?;
+    Ok((contract, is_yul))
+}
+
+/// Encode constructor arguments against the constructor's ABI inputs
+pub fn encode_constructor_args(
+    abi: &Abi,
+    contract_name: &str,
+    values: &[String],
+) -> Result<Vec<Token>> {
+    match abi.constructor() {
+        Some(constructor) => abi_args::parse_tokens(&constructor.inputs, values),
+        None if values.is_empty() => Ok(vec![]),
+        None => Err(eyre!(
+            "Contract {contract_name} has no constructor but constructor arguments were given"
+        )),
+    }
+}
+
+/// Creation bytecode followed by the ABI encoded constructor arguments
+pub fn deploy_code(abi: &Abi, bytecode: &Bytes, constructor_args: &[Token]) -> Result<Bytes> {
+    match abi.constructor() {
+        Some(constructor) => Ok(constructor
+            .encode_input(bytecode.to_vec(), constructor_args)?
+            .into()),
+        None => Ok(bytecode.clone()),
+    }
+}
+
+/// Deploys contracts of the project from other programs, such as tests and
+/// services, without the manifests, journal and plans of the `deploy`
+/// subcommand:
+///
+/// ```no_run
+/// # use ethers::prelude::*;
+/// # use solidity_deploy_rs::deployer::Deployer;
+/// # async fn example(provider: Provider<Http>, wallet: LocalWallet) -> eyre::Result<()> {
+/// let (storage, receipt): (Contract<_>, _) = Deployer::new(provider)
+///     .with_signer(wallet)
+///     .with_confirmations(3)
+///     .deploy("MessageStorage", ())
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The handle can be any binding generated by `abigen!` for the contract.
+pub struct Deployer<P> {
+    provider: P,
+    signer: Option<LocalWallet>,
+    confirmations: usize,
+    compiler: CompilerConfig,
+    fee_bump: Option<FeeBumpConfig>,
+}
+
+impl<P> Deployer<P>
+where
+    P: Middleware + Clone + 'static,
+    P::Error: 'static,
+{
+    /// Deployer sending through `provider`, waiting for one confirmation
+    pub fn new(provider: P) -> Self {
+        Deployer {
+            provider,
+            signer: None,
+            confirmations: 1,
+            compiler: CompilerConfig::default(),
+            fee_bump: None,
+        }
+    }
+
+    /// Account the deployments are signed with and paid by
+    pub fn with_signer(mut self, signer: LocalWallet) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Blocks to wait for once a deployment is mined
+    pub fn with_confirmations(mut self, confirmations: usize) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    /// Compiler settings of the project, the defaults of `compile` otherwise
+    pub fn with_compiler(mut self, compiler: CompilerConfig) -> Self {
+        self.compiler = compiler;
+        self
+    }
+
+    /// Resend a deployment still pending after `fee_bump.bump_after` seconds
+    /// with higher fees, as `deploy --bump-after` does
+    pub fn with_fee_bump(mut self, fee_bump: FeeBumpConfig) -> Self {
+        self.fee_bump = Some(fee_bump);
+        self
+    }
+
+    /// Compile the project, deploy `contract_name` with the constructor
+    /// arguments `args` and return its handle with the deployment receipt
+    pub async fn deploy<C, T>(
+        &self,
+        contract_name: &str,
+        args: T,
+    ) -> Result<(C, TransactionReceipt)>
+    where
+        C: From<Contract<SignerMiddleware<P, LocalWallet>>>,
+        T: Tokenize,
+    {
+        let signer = self
+            .signer
+            .clone()
+            .context("No signer to deploy with, set one with `with_signer`")?;
+        let chain_id = self.provider.get_chainid().await?.as_u64();
+        signer::ensure_funded(&self.provider, signer.address()).await?;
+        let client = Arc::new(SignerMiddleware::new(
+            self.provider.clone(),
+            signer.with_chain_id(chain_id),
+        ));
+
+        let project = compile(contracts_path(), None, &self.compiler).await?;
+        let (abi, bytecode) = find_contract(&project, contract_name)?;
+        let what = format!("Deployment of {contract_name}");
+        let deployment =
+            ContractFactory::new(abi.clone(), bytecode, client.clone()).deploy(args)?;
+        let Some(fee_bump) = &self.fee_bump else {
+            let (contract, receipt) = deployment
+                .confirmations(self.confirmations)
+                .send_with_receipt()
+                .await
+                .map_err(|e| revert_error(e, Some(&abi), &what))?;
+            return Ok((contract.into(), receipt));
+        };
+
+        // Sent here, as the deployer of ethers would wait for the first hash
+        // only, not for the replacements
+        let tx = deployment.tx;
+        let first_hash = client
+            .send_transaction(tx.clone(), None)
+            .await
+            .map_err(|e| revert_error(e, Some(&abi), &what))?
+            .tx_hash();
+        let receipt = bump_until_mined(client.as_ref(), first_hash, tx, None, fee_bump, |_| Ok(()))
+            .await?
+            .ok_or(DeployError::Dropped {
+                transaction: first_hash,
+            })?;
+        let receipt = match self.confirmations {
+            0 | 1 => receipt,
+            confirmations => PendingTransaction::new(receipt.transaction_hash, client.provider())
+                .confirmations(confirmations)
+                .await?
+                .context("Deployment dropped from the chain while confirming it")?,
+        };
+        let address = receipt
+            .contract_address
+            .with_context(|| format!("{what} created no contract"))?;
+        Ok((Contract::new(address, abi, client).into(), receipt))
+    }
+}
@@ -0,0 +1,124 @@
+use crate::calls::{execute_calls, PlannedCall};
+use crate::deployer::DeployContext;
+use ethers::abi::{parse_abi, Abi};
+use ethers::contract::Contract;
+use ethers::types::Address;
+use ethers_providers::ens::namehash;
+use ethers_providers::Middleware;
+use eyre::{eyre, Result};
+
+// ENS registry, at the same address on mainnet and the testnets
+const ENS_REGISTRY: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+
+// Point `name` at a deployed contract by setting the address record of its
+// resolver, which has to accept the deployer as the name's owner or manager.
+// With `reverse`, the contract's reverse record is set to `name` as well,
+// which the reverse registrar only allows the contract's owner to do.
+pub async fn assign_name(
+    context: &mut DeployContext,
+    name: &str,
+    address: Address,
+    reverse: bool,
+) -> Result<()> {
+    let registry_address: Address = ENS_REGISTRY.parse()?;
+    if context
+        .client
+        .get_code(registry_address, None)
+        .await?
+        .is_empty()
+    {
+        return Err(eyre!("ENS is not deployed on chain {}", context.chain_id));
+    }
+    let registry = Contract::new(
+        registry_address,
+        parse_abi(&[
+            "function resolver(bytes32) view returns (address)",
+            "function owner(bytes32) view returns (address)",
+        ])?,
+        context.client.clone(),
+    );
+
+    let node = namehash(name);
+    let resolver: Address = registry.method("resolver", node)?.call().await?;
+    if resolver.is_zero() {
+        return Err(eyre!(
+            "ENS name {name} has no resolver, set one before assigning it"
+        ));
+    }
+    println!("ENS {name}: setting the address record on resolver {resolver:?}");
+    send(
+        context,
+        &parse_abi(&["function setAddr(bytes32,address)"])?,
+        resolver,
+        PlannedCall {
+            method: "setAddr(bytes32,address)".to_owned(),
+            args: vec![format!("{node:?}"), format!("{address:?}")],
+            value: None,
+            expect: None,
+            timelock: None,
+        },
+    )
+    .await?;
+
+    if reverse {
+        let reverse_registrar: Address = registry
+            .method("owner", namehash("addr.reverse"))?
+            .call()
+            .await?;
+        let reverse_abi = parse_abi(&[
+            "function defaultResolver() view returns (address)",
+            "function setNameForAddr(address,address,address,string) returns (bytes32)",
+        ])?;
+        let default_resolver: Address = Contract::new(
+            reverse_registrar,
+            reverse_abi.clone(),
+            context.client.clone(),
+        )
+        .method("defaultResolver", ())?
+        .call()
+        .await?;
+        println!("ENS {name}: setting the reverse record of {address:?}");
+        send(
+            context,
+            &reverse_abi,
+            reverse_registrar,
+            PlannedCall {
+                method: "setNameForAddr".to_owned(),
+                args: vec![
+                    format!("{address:?}"),
+                    format!("{:?}", context.sender()),
+                    format!("{default_resolver:?}"),
+                    name.to_owned(),
+                ],
+                value: None,
+                expect: None,
+                timelock: None,
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// Send an ENS call like any post-deploy call, journaled and with the run's fees
+async fn send(
+    context: &mut DeployContext,
+    abi: &Abi,
+    address: Address,
+    call: PlannedCall,
+) -> Result<()> {
+    execute_calls(
+        context.client.as_ref(),
+        abi,
+        address,
+        &[call],
+        context.from,
+        Some(&context.fees),
+        Some(&mut context.journal),
+        context.confirmations,
+        context.nonce.as_mut(),
+        context.fee_bump.as_ref(),
+    )
+    .await?;
+    Ok(())
+}
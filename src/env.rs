@@ -0,0 +1,161 @@
+use crate::cli_config::flag_before_parsing;
+use eyre::{eyre, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+// File in the working directory loaded unless `--env-file` names another one
+const ENV_FILE: &str = ".env";
+
+// Load the variables of `--env-file`, or `.env` when present, into the
+// environment before the flags are parsed, so they fall back to them like to
+// variables of the shell. Variables already set in the shell take precedence.
+pub fn load_env_file() -> Result<()> {
+    let path = match flag_before_parsing("--env-file") {
+        Some(path) => PathBuf::from(path),
+        None if Path::new(ENV_FILE).exists() => PathBuf::from(ENV_FILE),
+        None => return Ok(()),
+    };
+    for (name, value) in read_env_file(&path)? {
+        if std::env::var_os(&name).is_none() {
+            std::env::set_var(name, value);
+        }
+    }
+    Ok(())
+}
+
+// Variables of a `.env` file: `KEY=value` lines, optionally prefixed with
+// `export` and with the value in single or double quotes. Blank lines and
+// `#` comments are skipped.
+pub fn read_env_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| eyre!("Failed to read {}: {e}", path.display()))?;
+    let mut variables = BTreeMap::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = line.split_once('=').ok_or_else(|| {
+            eyre!(
+                "{}:{}: expected KEY=value, got {line:?}",
+                path.display(),
+                number + 1
+            )
+        })?;
+        let value = value.trim();
+        let value = match (value.chars().next(), value.chars().last()) {
+            (Some(quote @ ('"' | '\'')), Some(end)) if value.len() > 1 && quote == end => {
+                &value[1..value.len() - 1]
+            }
+            _ => value,
+        };
+        variables.insert(name.trim().to_owned(), value.to_owned());
+    }
+    Ok(variables)
+}
+
+// Replace the `${VAR}` placeholders in the content of `file` with variables of
+// the environment, which include those of the loaded `.env`.
+// `${VAR:-default}` has a default for unset or empty variables and `$${` is a
+// literal `${`. Placeholders in `#` comments are left as they are, so a
+// commented-out setting does not need its variable. Every missing variable is
+// reported at once.
+pub fn interpolate(content: &str, file: &Path) -> Result<String> {
+    if !content.contains("${") {
+        return Ok(content.to_owned());
+    }
+
+    let mut output = String::with_capacity(content.len());
+    let mut missing = BTreeSet::new();
+    for line in content.split_inclusive('\n') {
+        let (code, comment) = line.split_at(comment_start(line).unwrap_or(line.len()));
+        substitute(code, file, &mut output, &mut missing)?;
+        output.push_str(comment);
+    }
+
+    if !missing.is_empty() {
+        return Err(eyre!(
+            "{} uses unset variable(s) {}, set them in the environment or {ENV_FILE}",
+            file.display(),
+            missing.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    Ok(output)
+}
+
+// Index of the `#` starting the comment of a TOML or YAML line: one outside
+// quotes, at the start of the line or after whitespace
+fn comment_start(line: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut previous = ' ';
+    for (index, c) in line.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => return Some(index),
+            None => {}
+        }
+        previous = c;
+    }
+    None
+}
+
+// Append `text` to `output` with its placeholders replaced, collecting the
+// names of unset variables in `missing`
+fn substitute<'a>(
+    text: &'a str,
+    file: &Path,
+    output: &mut String,
+    missing: &mut BTreeSet<&'a str>,
+) -> Result<()> {
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(escaped) = after.strip_prefix("${") {
+            output.push_str("${");
+            rest = escaped;
+            continue;
+        }
+        let Some(placeholder) = after.strip_prefix('{') else {
+            output.push('$');
+            rest = after;
+            continue;
+        };
+        let end = placeholder
+            .find('}')
+            .ok_or_else(|| eyre!("Unterminated ${{ in {}", file.display()))?;
+        let (name, default) = match placeholder[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&placeholder[..end], None),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(eyre!(
+                "Invalid placeholder ${{{}}} in {}",
+                &placeholder[..end],
+                file.display()
+            ));
+        }
+        let value = std::env::var(name).ok();
+        // Like the shell, a default also replaces an empty value
+        let value = match (value, default) {
+            (Some(value), Some(default)) if value.is_empty() => Some(default.to_owned()),
+            (None, Some(default)) => Some(default.to_owned()),
+            (value, _) => value,
+        };
+        match value {
+            Some(value) => output.push_str(&value),
+            None => {
+                missing.insert(name);
+            }
+        }
+        rest = &placeholder[end + 1..];
+    }
+    output.push_str(rest);
+    Ok(())
+}
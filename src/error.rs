@@ -0,0 +1,82 @@
+use crate::revert::revert_reason;
+use ethers::abi::Abi;
+use ethers::types::{Address, H256};
+use ethers_providers::MiddlewareError;
+use std::fmt;
+
+/// Kinds of failure a caller may want to handle, such as a revert to report
+/// to a user or a timeout to retry. Functions of this crate return
+/// `eyre::Report`s, those of one of these kinds can be told apart with
+/// `report.downcast_ref::<DeployError>()`, which also sees through the
+/// context added on the way up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeployError {
+    /// solc rejected the sources
+    CompileFailed { errors: Vec<String> },
+    /// No compiled contract of that name
+    ArtifactMissing { contract: String },
+    /// The sender cannot pay for the transaction, `None` when the node did not
+    /// say which account
+    InsufficientFunds { account: Option<Address> },
+    /// The transaction or call reverted, with the decoded reason
+    Reverted { reason: String },
+    /// The node rejected or failed the request
+    RpcError { message: String },
+    /// The node did not answer in time
+    Timeout { message: String },
+    /// The transaction left the mempool without being mined
+    Dropped { transaction: H256 },
+}
+
+impl DeployError {
+    /// Kind of a failed node request, with the revert reason decoded against
+    /// `abi` when the node returned the data the call reverted with
+    pub fn from_middleware<E: MiddlewareError>(error: &E, abi: Option<&Abi>) -> Self {
+        if let Some(reason) = revert_reason(error, abi) {
+            return DeployError::Reverted { reason };
+        }
+        let message = error.to_string();
+        let lowercase = message.to_lowercase();
+        if lowercase.contains("insufficient funds") {
+            DeployError::InsufficientFunds { account: None }
+        } else if lowercase.contains("timed out") || lowercase.contains("timeout") {
+            DeployError::Timeout { message }
+        } else {
+            DeployError::RpcError { message }
+        }
+    }
+}
+
+impl fmt::Display for DeployError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeployError::CompileFailed { errors } => {
+                write!(f, "Compiling solidity project failed")?;
+                for error in errors {
+                    write!(f, "\n{error}")?;
+                }
+                Ok(())
+            }
+            DeployError::ArtifactMissing { contract } => write!(f, "Contract {contract} not found"),
+            DeployError::InsufficientFunds {
+                account: Some(account),
+            } => {
+                write!(
+                    f,
+                    "Account {account:?} has insufficient funds on the target chain"
+                )
+            }
+            DeployError::InsufficientFunds { account: None } => {
+                write!(f, "Insufficient funds for the transaction")
+            }
+            DeployError::Reverted { reason } => write!(f, "execution reverted: {reason}"),
+            DeployError::RpcError { message } => write!(f, "{message}"),
+            DeployError::Timeout { message } => write!(f, "timed out: {message}"),
+            DeployError::Dropped { transaction } => {
+                write!(f, "Transaction {transaction:?} dropped from mempool")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DeployError {}
@@ -0,0 +1,183 @@
+use crate::calls::load_calls;
+use crate::cli_config::EstimateConfig;
+use crate::compiler::{check_contract_sizes, compile, contracts_path};
+use crate::deployer::{deploy_code, encode_constructor_args, find_contract};
+use crate::fees::resolve_fees;
+use crate::l1_fee::{is_op_stack, l1_fee};
+use crate::price_feed::{format_usd, usd_price};
+use crate::revert::revert_error;
+use crate::units::format_amount;
+use crate::{rpc, signer};
+use ethers::contract::ContractFactory;
+use ethers::middleware::SignerMiddleware;
+use ethers::prelude::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Eip1559TransactionRequest, U256};
+use ethers::utils::Anvil;
+use ethers_providers::{Http, Middleware, Provider};
+use eyre::{ContextCompat, Result};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Estimate the gas and cost of a deployment (and optional post-deploy calls)
+// without broadcasting anything to the target chain
+pub async fn estimate(config: EstimateConfig) -> Result<()> {
+    let project = compile(contracts_path(), None, &config.compiler).await?;
+    check_contract_sizes(&project, config.compiler.size_limit)?;
+    let (abi, bytecode) = find_contract(&project, &config.contract_name)?;
+    let constructor_args =
+        encode_constructor_args(&abi, &config.contract_name, &config.constructor_args)?;
+    let deploy_code = deploy_code(&abi, &bytecode, &constructor_args)?;
+
+    // Estimate against a local Anvil chain unless an RPC endpoint was given
+    let anvil = match config.rpc_url {
+        Some(_) => None,
+        None => Some(Anvil::new().spawn()),
+    };
+    let endpoint = match (&config.rpc_url, &anvil) {
+        (Some(rpc_url), _) => rpc_url.clone(),
+        (None, Some(anvil)) => anvil.endpoint(),
+        (None, None) => unreachable!("Anvil is spawned when no RPC URL is given"),
+    };
+    let provider = rpc::connect(&endpoint)?;
+    let from = match signer::wallet_from_config(&config.signer)? {
+        Some(wallet) => Some(wallet.address()),
+        None => anvil.as_ref().map(|anvil| anvil.addresses()[0]),
+    };
+
+    let fees = resolve_fees(&provider, &config.fees).await?;
+    println!("Current fees: {fees}");
+    // OP-stack chains charge an L1 data fee on top of the gas, often most of
+    // the cost of a deployment
+    let op_stack = is_op_stack(&provider).await?;
+    if op_stack {
+        println!("OP-stack chain, costs include the L1 data fee");
+    }
+
+    let mut deploy_tx = Eip1559TransactionRequest::new().data(deploy_code);
+    if let Some(from) = from {
+        deploy_tx = deploy_tx.from(from);
+    }
+    let mut deploy_tx = TypedTransaction::Eip1559(deploy_tx);
+    let deploy_gas = provider.estimate_gas(&deploy_tx, None).await.map_err(|e| {
+        revert_error(
+            e,
+            Some(&abi),
+            &format!("Deployment of {}", config.contract_name),
+        )
+    })?;
+    deploy_tx.set_gas(deploy_gas);
+    let deploy_l1_fee = if op_stack {
+        Some(l1_fee(&provider, &deploy_tx).await?)
+    } else {
+        None
+    };
+    let mut rows = vec![(
+        format!("deploy {}", config.contract_name),
+        deploy_gas,
+        deploy_l1_fee,
+    )];
+
+    if let Some(calls_path) = &config.calls {
+        let calls = load_calls(calls_path)?;
+
+        // Post-deploy calls need the contract to exist, so they are simulated on
+        // a scratch fork of the target chain that is thrown away afterwards
+        let fork = Anvil::new().fork(endpoint).spawn();
+        let wallet: LocalWallet = fork.keys()[0].clone().into();
+        let fork_provider =
+            Provider::<Http>::try_from(fork.endpoint())?.interval(Duration::from_millis(10));
+        let client = Arc::new(SignerMiddleware::new(
+            fork_provider,
+            wallet.with_chain_id(fork.chain_id()),
+        ));
+
+        let factory = ContractFactory::new(abi.clone(), bytecode, client.clone());
+        let contract = factory.deploy_tokens(constructor_args)?.send().await?;
+
+        for call in calls {
+            let mut tx: TypedTransaction = call.to_transaction(&abi, contract.address())?.into();
+            let gas = client
+                .estimate_gas(&tx, None)
+                .await
+                .map_err(|e| revert_error(e, Some(&abi), &format!("Call {}", call.method)))?;
+            tx.set_gas(gas);
+            let call_l1_fee = if op_stack {
+                Some(l1_fee(&provider, &tx).await?)
+            } else {
+                None
+            };
+            rows.push((call.method.clone(), gas, call_l1_fee));
+
+            // Apply the call so later calls see its state changes
+            client
+                .send_transaction(tx, None)
+                .await?
+                .await?
+                .context("Simulated call dropped from mempool")?;
+        }
+    }
+
+    let price = usd_price(&provider, &config.price_feed).await;
+    print_estimate(&rows, fees.max_gas_price(), price);
+    Ok(())
+}
+
+// Rows are the steps with their gas and, on OP-stack chains, L1 data fee
+fn print_estimate(rows: &[(String, U256, Option<U256>)], gas_price: U256, usd_price: Option<f64>) {
+    // Shown with a price feed only
+    let usd = |cost: U256| match usd_price {
+        Some(price) => format!(" {:>14}", format_usd(cost, price)),
+        None => String::new(),
+    };
+    // Shown on OP-stack chains only
+    let op_stack = rows.iter().any(|(_, _, l1_fee)| l1_fee.is_some());
+    let l1 = |l1_fee: Option<U256>| {
+        if op_stack {
+            format!(" {:>24}", format_amount(l1_fee.unwrap_or_default(), 18))
+        } else {
+            String::new()
+        }
+    };
+    println!("{}", "=".repeat(80));
+    println!(
+        "{:<40} {:>14}{} {:>24}{}",
+        "STEP",
+        "GAS",
+        if op_stack {
+            format!(" {:>24}", "L1 FEE (ETH)")
+        } else {
+            String::new()
+        },
+        "MAX COST (ETH)",
+        match usd_price {
+            Some(_) => format!(" {:>14}", "MAX COST (USD)"),
+            None => String::new(),
+        }
+    );
+    let mut total_gas = U256::zero();
+    let mut total_l1_fee = U256::zero();
+    for (name, gas, l1_fee) in rows {
+        total_gas += *gas;
+        total_l1_fee += l1_fee.unwrap_or_default();
+        let cost = *gas * gas_price + l1_fee.unwrap_or_default();
+        println!(
+            "{:<40} {:>14}{} {:>24}{}",
+            name,
+            gas,
+            l1(*l1_fee),
+            format_amount(cost, 18),
+            usd(cost)
+        );
+    }
+    let total_cost = total_gas * gas_price + total_l1_fee;
+    println!("{}", "=".repeat(80));
+    println!(
+        "{:<40} {:>14}{} {:>24}{}",
+        "TOTAL",
+        total_gas,
+        l1(Some(total_l1_fee)),
+        format_amount(total_cost, 18),
+        usd(total_cost)
+    );
+}
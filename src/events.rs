@@ -0,0 +1,180 @@
+use crate::cli_config::EventsConfig;
+use crate::interact::{print_log, resolve_contract};
+use crate::rpc;
+use ethers::abi::Abi;
+use ethers::types::{Address, Filter, Log};
+use ethers_providers::{Middleware, StreamExt};
+use eyre::{eyre, Result};
+use std::time::Duration;
+
+// How often new blocks are looked for over HTTP
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Longest wait between two attempts to reach a node that dropped
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+// Entry point of the `events` subcommand: print every event of a deployed
+// contract, then with `--follow` keep streaming new ones until interrupted.
+// Websocket endpoints are subscribed to, HTTP endpoints polled.
+pub async fn events(config: EventsConfig) -> Result<()> {
+    let ws_url = ws_url(&config);
+    let provider = match ws_url {
+        Some(ws_url) => rpc::connect_ws(ws_url).await?,
+        None => rpc::connect(&config.rpc_url)?,
+    };
+    let (address, abi, mut next_block) = print_history(&provider, &config).await?;
+    if !config.follow {
+        return Ok(());
+    }
+
+    println!("FOLLOWING events of {address:?}, press Ctrl+C to stop");
+    let mut failures = 0;
+    loop {
+        let result = match ws_url {
+            Some(ws_url) => subscribe(ws_url, address, &abi, &mut next_block, &mut failures).await,
+            None => {
+                poll(
+                    &config.rpc_url,
+                    address,
+                    &abi,
+                    &mut next_block,
+                    &mut failures,
+                )
+                .await
+            }
+        };
+        // Both only return once the connection to the node is lost
+        let error = result
+            .err()
+            .unwrap_or_else(|| eyre!("Subscription closed by the node"));
+        failures += 1;
+        let delay =
+            (Duration::from_secs(1) * 2u32.saturating_pow(failures - 1)).min(MAX_RECONNECT_DELAY);
+        println!(
+            "Connection lost: {error}, reconnecting in {}s (attempt {failures})",
+            delay.as_secs()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+// Websocket endpoint to subscribe to: that of `--ws-url`, or `--rpc-url`
+// when it is one
+fn ws_url(config: &EventsConfig) -> Option<&str> {
+    let rpc_url = config.rpc_url.as_str();
+    let websocket = rpc_url.starts_with("ws://") || rpc_url.starts_with("wss://");
+    config
+        .ws_url
+        .as_deref()
+        .or_else(|| websocket.then_some(rpc_url))
+}
+
+// Print the events up to the latest block and return the contract together
+// with the first block whose events are still to be printed
+async fn print_history<M: Middleware>(
+    provider: &M,
+    config: &EventsConfig,
+) -> Result<(Address, Abi, u64)>
+where
+    M::Error: 'static,
+{
+    let (address, abi) = resolve_contract(
+        provider,
+        config.address,
+        config.abi.as_deref(),
+        config.contract_name.as_deref(),
+    )
+    .await?;
+    let latest = provider.get_block_number().await?.as_u64();
+    let from_block = match (config.from_block, config.follow) {
+        (Some(from_block), _) => from_block,
+        (None, true) => latest + 1,
+        (None, false) => 0,
+    };
+    if from_block <= latest {
+        print_range(provider, address, &abi, from_block, latest).await?;
+    }
+    Ok((address, abi, latest + 1))
+}
+
+async fn print_range<M: Middleware>(
+    provider: &M,
+    address: Address,
+    abi: &Abi,
+    from_block: u64,
+    to_block: u64,
+) -> Result<()>
+where
+    M::Error: 'static,
+{
+    let filter = Filter::new()
+        .address(address)
+        .from_block(from_block)
+        .to_block(to_block);
+    for log in provider.get_logs(&filter).await? {
+        print_event(abi, &log);
+    }
+    Ok(())
+}
+
+// Subscribe to the contract's logs, first catching up on the blocks missed
+// while (re)connecting. Events of the block the connection dropped in may be
+// printed twice, but none are skipped.
+async fn subscribe(
+    ws_url: &str,
+    address: Address,
+    abi: &Abi,
+    next_block: &mut u64,
+    failures: &mut u32,
+) -> Result<()> {
+    let provider = rpc::connect_ws(ws_url).await?;
+    let mut stream = provider
+        .subscribe_logs(&Filter::new().address(address))
+        .await?;
+    *failures = 0;
+
+    let latest = provider.get_block_number().await?.as_u64();
+    if latest >= *next_block {
+        print_range(&provider, address, abi, *next_block, latest).await?;
+        *next_block = latest + 1;
+    }
+    let caught_up = *next_block;
+    while let Some(log) = stream.next().await {
+        let block = log.block_number.map_or(caught_up, |number| number.as_u64());
+        // Already printed while catching up
+        if block < caught_up {
+            continue;
+        }
+        print_event(abi, &log);
+        *next_block = block;
+    }
+    Ok(())
+}
+
+// Poll for new blocks and print their events. Only returns on an error.
+async fn poll(
+    rpc_url: &str,
+    address: Address,
+    abi: &Abi,
+    next_block: &mut u64,
+    failures: &mut u32,
+) -> Result<()> {
+    let provider = rpc::connect(rpc_url)?;
+    loop {
+        let latest = provider.get_block_number().await?.as_u64();
+        *failures = 0;
+        if latest >= *next_block {
+            print_range(&provider, address, abi, *next_block, latest).await?;
+            *next_block = latest + 1;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn print_event(abi: &Abi, log: &Log) {
+    println!(
+        "BLOCK {} TX {:?}",
+        log.block_number.unwrap_or_default(),
+        log.transaction_hash.unwrap_or_default()
+    );
+    print_log(abi, log);
+}
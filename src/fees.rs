@@ -0,0 +1,300 @@
+use crate::chains::chain_info;
+use crate::cli_config::{FeeConfig, GasOracleKind};
+use crate::units::format_gwei;
+use ethers::middleware::gas_oracle::{BlockNative, Etherscan, GasOracle};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{
+    BlockNumber, Eip1559TransactionRequest, Transaction, TransactionRequest, U256,
+};
+use ethers_providers::Middleware;
+use eyre::{eyre, ContextCompat, Result};
+use std::fmt;
+
+// Blocks of `eth_feeHistory` the recent priority fees are taken from
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+// Least nodes raise the fees of a replacement by, in percent
+pub const MIN_BUMP_PERCENT: u64 = 10;
+
+// Fees attached to an outgoing transaction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fees {
+    Legacy {
+        gas_price: U256,
+    },
+    Eip1559 {
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+    },
+}
+
+// Resolve the fees from the CLI flags, asking the gas oracle for whatever was
+// not given
+pub async fn resolve_fees<M: Middleware>(provider: &M, config: &FeeConfig) -> Result<Fees>
+where
+    M::Error: 'static,
+{
+    if config.legacy {
+        let gas_price = match config.gas_price {
+            Some(gas_price) => gas_price,
+            None => oracle_gas_price(provider, config).await?,
+        };
+        return Ok(Fees::Legacy { gas_price });
+    }
+
+    let (max_fee_per_gas, max_priority_fee_per_gas) =
+        match (config.max_fee_per_gas, config.max_priority_fee_per_gas) {
+            (Some(max_fee), Some(priority_fee)) => (max_fee, priority_fee),
+            (max_fee, priority_fee) => {
+                let (estimated_max_fee, estimated_priority_fee) =
+                    oracle_eip1559_fees(provider, config).await?;
+                (
+                    max_fee.unwrap_or(estimated_max_fee),
+                    priority_fee.unwrap_or(estimated_priority_fee),
+                )
+            }
+        };
+    if max_priority_fee_per_gas > max_fee_per_gas {
+        return Err(eyre!(
+            "Max priority fee per gas {max_priority_fee_per_gas} exceeds max fee per gas {max_fee_per_gas}"
+        ));
+    }
+
+    Ok(Fees::Eip1559 {
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+    })
+}
+
+// Gas price of a legacy transaction from the configured oracle
+async fn oracle_gas_price<M: Middleware>(provider: &M, config: &FeeConfig) -> Result<U256>
+where
+    M::Error: 'static,
+{
+    match config.gas_oracle {
+        GasOracleKind::Node | GasOracleKind::FeeHistory => Ok(provider.get_gas_price().await?),
+        GasOracleKind::Etherscan => Ok(etherscan_oracle(provider, config).await?.fetch().await?),
+        GasOracleKind::Blocknative => Ok(blocknative_oracle(config)?.fetch().await?),
+    }
+}
+
+// Max fee and max priority fee per gas from the configured oracle
+async fn oracle_eip1559_fees<M: Middleware>(
+    provider: &M,
+    config: &FeeConfig,
+) -> Result<(U256, U256)>
+where
+    M::Error: 'static,
+{
+    match config.gas_oracle {
+        GasOracleKind::Node => Ok(provider.estimate_eip1559_fees(None).await?),
+        GasOracleKind::FeeHistory => fee_history_fees(provider, config.fee_percentile).await,
+        // The gas tracker only suggests a total price, the part above the
+        // base fee is the tip
+        GasOracleKind::Etherscan => {
+            let gas_price = etherscan_oracle(provider, config).await?.fetch().await?;
+            let base_fee = next_base_fee(provider).await?;
+            let priority_fee = gas_price.saturating_sub(base_fee);
+            Ok((base_fee * 2 + priority_fee, priority_fee))
+        }
+        GasOracleKind::Blocknative => {
+            Ok(blocknative_oracle(config)?.estimate_eip1559_fees().await?)
+        }
+    }
+}
+
+// Twice the next block's base fee, so the transaction stays valid over a few
+// full blocks, plus the configured percentile of the priority fees paid in
+// the last blocks
+async fn fee_history_fees<M: Middleware>(provider: &M, percentile: f64) -> Result<(U256, U256)>
+where
+    M::Error: 'static,
+{
+    let history = provider
+        .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &[percentile])
+        .await?;
+    // The last base fee is the one of the next block
+    let base_fee = *history
+        .base_fee_per_gas
+        .last()
+        .context("Node returned an empty fee history")?;
+    let mut rewards = history
+        .reward
+        .iter()
+        .filter_map(|reward| reward.first().copied())
+        .filter(|reward| !reward.is_zero())
+        .collect::<Vec<_>>();
+    rewards.sort();
+    // Empty blocks pay no tips, 1 gwei is enough to be included
+    let priority_fee = rewards
+        .get(rewards.len() / 2)
+        .copied()
+        .unwrap_or_else(|| U256::exp10(9));
+    Ok((base_fee * 2 + priority_fee, priority_fee))
+}
+
+async fn next_base_fee<M: Middleware>(provider: &M) -> Result<U256>
+where
+    M::Error: 'static,
+{
+    let history = provider.fee_history(1u64, BlockNumber::Latest, &[]).await?;
+    history
+        .base_fee_per_gas
+        .last()
+        .copied()
+        .context("Node returned an empty fee history")
+}
+
+async fn etherscan_oracle<M: Middleware>(provider: &M, config: &FeeConfig) -> Result<Etherscan>
+where
+    M::Error: 'static,
+{
+    let api_key = config.gas_oracle_key.as_deref().context(
+        "--gas-oracle-key with an Etherscan API key is required by the etherscan gas oracle",
+    )?;
+    let chain = chain_info(provider.get_chainid().await?.as_u64())?;
+    Ok(Etherscan::new(chain.explorer_client(api_key)?))
+}
+
+fn blocknative_oracle(config: &FeeConfig) -> Result<BlockNative> {
+    let api_key = config.gas_oracle_key.clone().context(
+        "--gas-oracle-key with a Blocknative API key is required by the blocknative gas oracle",
+    )?;
+    Ok(BlockNative::new(Some(api_key)))
+}
+
+impl Fees {
+    // Set the fees on a transaction, converting it to the matching envelope
+    pub fn apply(&self, tx: &mut TypedTransaction) {
+        match *self {
+            Fees::Legacy { gas_price } => {
+                if let TypedTransaction::Eip1559(inner) = tx {
+                    let legacy: TransactionRequest = inner.clone().into();
+                    *tx = TypedTransaction::Legacy(legacy);
+                }
+                tx.set_gas_price(gas_price);
+            }
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                let mut inner = match tx {
+                    TypedTransaction::Eip1559(inner) => inner.clone(),
+                    other => {
+                        let mut inner = Eip1559TransactionRequest::new();
+                        inner.from = other.from().copied();
+                        inner.to = other.to().cloned();
+                        inner.gas = other.gas().copied();
+                        inner.value = other.value().copied();
+                        inner.data = other.data().cloned();
+                        inner.nonce = other.nonce().copied();
+                        inner.chain_id = other.chain_id();
+                        inner
+                    }
+                };
+                inner.max_fee_per_gas = Some(max_fee_per_gas);
+                inner.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+                *tx = TypedTransaction::Eip1559(inner);
+            }
+        }
+    }
+
+    // Fees a sent transaction pays
+    pub fn of_transaction(tx: &Transaction) -> Fees {
+        match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+            (Some(max_fee_per_gas), Some(max_priority_fee_per_gas)) => Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            },
+            _ => Fees::Legacy {
+                gas_price: tx.gas_price.unwrap_or_default(),
+            },
+        }
+    }
+
+    // Fees raised by a percentage, rounded up, as nodes require for a
+    // transaction replacing another one at the same nonce
+    pub fn bumped(&self, percent: u64) -> Fees {
+        let bump = |value: U256| (value * (100 + percent) + 99) / 100;
+        match *self {
+            Fees::Legacy { gas_price } => Fees::Legacy {
+                gas_price: bump(gas_price),
+            },
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => Fees::Eip1559 {
+                max_fee_per_gas: bump(max_fee_per_gas),
+                max_priority_fee_per_gas: bump(max_priority_fee_per_gas),
+            },
+        }
+    }
+
+    // Fees paying at most `cap` for a unit of gas, the priority fee limited
+    // to it along with the max fee
+    pub fn capped(self, cap: U256) -> Fees {
+        match self {
+            Fees::Legacy { gas_price } => Fees::Legacy {
+                gas_price: gas_price.min(cap),
+            },
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => Fees::Eip1559 {
+                max_fee_per_gas: max_fee_per_gas.min(cap),
+                max_priority_fee_per_gas: max_priority_fee_per_gas.min(cap),
+            },
+        }
+    }
+
+    // Raise the fees to at least `other`. Fees of another transaction type
+    // leave them as they are.
+    pub fn at_least(self, other: Fees) -> Fees {
+        match (self, other) {
+            (Fees::Legacy { gas_price }, Fees::Legacy { gas_price: other }) => Fees::Legacy {
+                gas_price: gas_price.max(other),
+            },
+            (
+                Fees::Eip1559 {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                },
+                Fees::Eip1559 {
+                    max_fee_per_gas: other_max_fee,
+                    max_priority_fee_per_gas: other_priority_fee,
+                },
+            ) => Fees::Eip1559 {
+                max_fee_per_gas: max_fee_per_gas.max(other_max_fee),
+                max_priority_fee_per_gas: max_priority_fee_per_gas.max(other_priority_fee),
+            },
+            (fees, _) => fees,
+        }
+    }
+
+    // Highest price a single unit of gas may cost
+    pub fn max_gas_price(&self) -> U256 {
+        match *self {
+            Fees::Legacy { gas_price } => gas_price,
+            Fees::Eip1559 {
+                max_fee_per_gas, ..
+            } => max_fee_per_gas,
+        }
+    }
+}
+
+impl fmt::Display for Fees {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Fees::Legacy { gas_price } => write!(f, "legacy, gas price {}", format_gwei(gas_price)),
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => write!(
+                f,
+                "EIP-1559, max fee {}, max priority fee {}",
+                format_gwei(max_fee_per_gas),
+                format_gwei(max_priority_fee_per_gas)
+            ),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::cli_config::FlattenConfig;
+use crate::compiler::{build_project, contracts_dir, contracts_path};
+use eyre::{eyre, Result};
+
+// Entry point of the `flatten` subcommand: inline every import of a contract
+// into a single source file, e.g. for manual verification or audits
+pub fn flatten(config: FlattenConfig) -> Result<()> {
+    let project = build_project(contracts_path(), None, &config.compiler)?;
+    let source_path = contracts_dir().join(format!("{}.sol", config.contract_name));
+    let source_path = std::fs::canonicalize(&source_path).map_err(|e| {
+        eyre!(
+            "Source of {} not found at {source_path:?}: {e}",
+            config.contract_name
+        )
+    })?;
+
+    let flattened = merge_headers(&project.flatten(&source_path)?);
+    match config.out {
+        Some(out) => {
+            std::fs::write(&out, flattened)?;
+            println!("Flattened {} to {}", config.contract_name, out.display());
+        }
+        None => print!("{flattened}"),
+    }
+    Ok(())
+}
+
+// Every inlined file brings its own SPDX identifier and pragmas. Keep a single
+// combined license and each distinct pragma once, at the top of the file.
+fn merge_headers(source: &str) -> String {
+    let mut licenses: Vec<&str> = vec![];
+    let mut pragmas: Vec<&str> = vec![];
+    let mut body: Vec<&str> = vec![];
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if let Some(license) = trimmed.strip_prefix("// SPDX-License-Identifier:") {
+            let license = license.trim();
+            if !licenses.contains(&license) {
+                licenses.push(license);
+            }
+        } else if trimmed.starts_with("pragma ") {
+            if !pragmas.contains(&trimmed) {
+                pragmas.push(trimmed);
+            }
+        } else if !(line.is_empty() && body.last().is_some_and(|last| last.is_empty())) {
+            body.push(line);
+        }
+    }
+
+    let mut flattened = String::new();
+    if !licenses.is_empty() {
+        flattened.push_str(&format!(
+            "// SPDX-License-Identifier: {}\n",
+            licenses.join(" AND ")
+        ));
+    }
+    for pragma in pragmas {
+        flattened.push_str(pragma);
+        flattened.push('\n');
+    }
+    for line in body {
+        flattened.push_str(line);
+        flattened.push('\n');
+    }
+    flattened
+}
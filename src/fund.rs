@@ -0,0 +1,171 @@
+use crate::audit;
+use crate::cli_config::FundConfig;
+use crate::error::DeployError;
+use crate::fees::{resolve_fees, Fees};
+use crate::lock::DeployLock;
+use crate::registry::{resolve_alias, resolve_aliases};
+use crate::rpc::{self, RpcTransport};
+use crate::signer::{ensure_funded, wallet_from_config};
+use crate::units::format_eth;
+use ethers::middleware::SignerMiddleware;
+use ethers::signers::Signer;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, TransactionRequest, U256};
+use ethers_providers::Middleware;
+use eyre::{eyre, ContextCompat, Result};
+
+// Entry point of the `fund` subcommand: send an account the amount, or top it
+// up to the amount with `--at-least`. Local nodes mint it, other chains get a
+// transfer from the signer.
+pub async fn fund(config: FundConfig) -> Result<()> {
+    let provider = rpc::connect(&config.rpc_url)?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let account = resolve_account(&config.account, chain_id)?;
+    let balance = provider.get_balance(account, None).await?;
+    let target = match config.at_least {
+        true if balance >= config.amount => {
+            println!(
+                "{account:?} already holds {}, at least {}",
+                format_eth(balance),
+                format_eth(config.amount)
+            );
+            return Ok(());
+        }
+        true => config.amount,
+        false => balance + config.amount,
+    };
+
+    if let Some(method) = set_balance_method(&provider).await {
+        set_balance(&provider, method, account, target).await?;
+    } else {
+        let wallet = wallet_from_config(&config.signer)?
+            .context("--private-key, --keystore or --mnemonic is required to fund accounts on this chain")?
+            .with_chain_id(chain_id);
+        // A transfer uses the nonces of the signer as deployments do. Local
+        // nodes mint the balance instead, sending nothing.
+        let _lock = DeployLock::acquire(chain_id, "fund", config.force_unlock)?;
+        ensure_funded(&provider, wallet.address()).await?;
+        let fees = resolve_fees(&provider, &config.fees).await?;
+        let client = SignerMiddleware::new(provider, wallet);
+        transfer(
+            &client,
+            None,
+            account,
+            target - balance,
+            &fees,
+            config.confirmations,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// Top `account` up to at least `minimum`, as a deployment plan funds the
+// accounts it lists before deploying anything. Funding an account that holds
+// enough already sends nothing, so a resumed plan does not fund it twice.
+pub async fn top_up<M: Middleware<Provider = RpcTransport>>(
+    client: &M,
+    from: Option<Address>,
+    account: Address,
+    minimum: U256,
+    fees: &Fees,
+    confirmations: usize,
+) -> Result<()>
+where
+    M::Error: 'static,
+{
+    let balance = client.get_balance(account, None).await?;
+    if balance >= minimum {
+        println!(
+            "{account:?} already holds {}, at least {}",
+            format_eth(balance),
+            format_eth(minimum)
+        );
+        return Ok(());
+    }
+    match set_balance_method(client.provider()).await {
+        Some(method) => set_balance(client.provider(), method, account, minimum).await,
+        None => {
+            transfer(
+                client,
+                from,
+                account,
+                minimum - balance,
+                fees,
+                confirmations,
+            )
+            .await
+        }
+    }
+}
+
+// `@Name` of a deployment of the chain, or an address
+fn resolve_account(account: &str, chain_id: u64) -> Result<Address> {
+    let account = resolve_aliases(account, |name| resolve_alias(name, chain_id, None))?;
+    account
+        .parse()
+        .map_err(|e| eyre!("Invalid account {account:?}: {e}"))
+}
+
+// RPC method setting balances on a local development node, `None` on other
+// chains. Anvil and Hardhat tell themselves apart by their client version.
+async fn set_balance_method<M: Middleware>(provider: &M) -> Option<&'static str> {
+    let version = provider.client_version().await.ok()?;
+    if version.starts_with("anvil") {
+        Some("anvil_setBalance")
+    } else if version.starts_with("HardhatNetwork") {
+        Some("hardhat_setBalance")
+    } else {
+        None
+    }
+}
+
+async fn set_balance<M: Middleware>(
+    provider: &M,
+    method: &str,
+    account: Address,
+    balance: U256,
+) -> Result<()> {
+    provider
+        .provider()
+        .request::<_, ()>(method, (account, balance))
+        .await
+        .map_err(|e| eyre!("{method} failed: {e}"))?;
+    println!("Set the balance of {account:?} to {}", format_eth(balance));
+    Ok(())
+}
+
+// Send `amount` to `account` and wait for the transfer to be mined
+async fn transfer<M: Middleware<Provider = RpcTransport>>(
+    client: &M,
+    from: Option<Address>,
+    account: Address,
+    amount: U256,
+    fees: &Fees,
+    confirmations: usize,
+) -> Result<()>
+where
+    M::Error: 'static,
+{
+    let mut tx = TransactionRequest::new().to(account).value(amount);
+    if let Some(from) = from {
+        tx = tx.from(from);
+    }
+    let mut tx = TypedTransaction::Legacy(tx);
+    fees.apply(&mut tx);
+    println!("SEND {} to {account:?}", format_eth(amount));
+    let pending_tx = client
+        .send_transaction(tx.clone(), None)
+        .await
+        .map_err(|e| eyre!("Transfer to {account:?} failed: {e}"))?;
+    let transaction = pending_tx.tx_hash();
+    audit::record(client, "fund", &tx, transaction).await;
+    println!("Transaction hash: {transaction:?}");
+    let receipt = rpc::confirm(client.provider(), pending_tx, confirmations)
+        .await?
+        .ok_or(DeployError::Dropped { transaction })?;
+    if receipt.status.is_some_and(|status| status.is_zero()) {
+        return Err(eyre!("Transfer to {account:?} reverted in {transaction:?}"));
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use crate::calls::PlannedCall;
+use crate::cli_config::GasReportFormat;
+use crate::l1_fee::receipt_l1_fee;
+use crate::price_feed::{format_usd, usd};
+use crate::units::format_eth;
+use ethers::types::{TransactionReceipt, U256};
+use eyre::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// Gas used by the deployments and post-deploy calls of a run, by the name
+// each contract is deployed under
+#[derive(Default, Debug)]
+pub struct GasReport {
+    contracts: BTreeMap<String, ContractGas>,
+}
+
+#[derive(Default, Debug)]
+struct ContractGas {
+    contract_name: String,
+    deployment_gas: Option<u64>,
+    // Size of the deployed runtime bytecode
+    deployment_size: usize,
+    // Gas used by every call, by method
+    calls: BTreeMap<String, Vec<u64>>,
+    // Fees paid for the deployment and the calls, in wei
+    cost: U256,
+    // Part of `cost` paid for L1 data on OP-stack chains
+    l1_fee: U256,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ContractSummary<'a> {
+    contract_name: &'a str,
+    deployment_gas: Option<u64>,
+    deployment_size: usize,
+    functions: BTreeMap<&'a str, FunctionSummary>,
+    cost: U256,
+    #[serde(skip_serializing_if = "U256::is_zero")]
+    l1_fee: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost_usd: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct FunctionSummary {
+    calls: usize,
+    min: u64,
+    avg: u64,
+    max: u64,
+}
+
+impl GasReport {
+    pub fn record_deployment(
+        &mut self,
+        name: &str,
+        contract_name: &str,
+        receipt: &TransactionReceipt,
+        deployment_size: usize,
+    ) {
+        let contract = self.contracts.entry(name.to_owned()).or_default();
+        contract.contract_name = contract_name.to_owned();
+        contract.deployment_gas = receipt.gas_used.map(|gas| gas.as_u64());
+        contract.deployment_size = deployment_size;
+        contract.cost += fee(receipt);
+        contract.l1_fee += receipt_l1_fee(receipt);
+    }
+
+    // Record the receipts `execute_calls` returned for the calls of a contract
+    pub fn record_calls(
+        &mut self,
+        name: &str,
+        calls: &[PlannedCall],
+        receipts: &[Option<TransactionReceipt>],
+    ) {
+        let contract = self.contracts.entry(name.to_owned()).or_default();
+        for (call, receipt) in calls.iter().zip(receipts) {
+            // Calls of view methods send no transaction
+            if let Some(gas) = receipt.as_ref().and_then(|receipt| receipt.gas_used) {
+                contract
+                    .calls
+                    .entry(call.method.clone())
+                    .or_default()
+                    .push(gas.as_u64());
+            }
+            if let Some(receipt) = receipt {
+                contract.cost += fee(receipt);
+                contract.l1_fee += receipt_l1_fee(receipt);
+            }
+        }
+    }
+
+    // Costs are also shown in USD when the native token price is given
+    pub fn print(&self, format: GasReportFormat, usd_price: Option<f64>) -> Result<()> {
+        match format {
+            GasReportFormat::Text => {
+                for (name, contract) in &self.contracts {
+                    println!("{}", "=".repeat(80));
+                    println!("GAS REPORT {name} ({})", contract.contract_name);
+                    println!(
+                        "deployment gas: {}, deployed size: {} bytes",
+                        contract
+                            .deployment_gas
+                            .map_or("-".to_owned(), |gas| gas.to_string()),
+                        contract.deployment_size
+                    );
+                    match usd_price {
+                        Some(price) => println!(
+                            "cost: {} (~{})",
+                            format_eth(contract.cost),
+                            format_usd(contract.cost, price)
+                        ),
+                        None => println!("cost: {}", format_eth(contract.cost)),
+                    }
+                    if !contract.l1_fee.is_zero() {
+                        println!("of which L1 data fee: {}", format_eth(contract.l1_fee));
+                    }
+                    println!(
+                        "{:<32} {:>8} {:>12} {:>12} {:>12}",
+                        "FUNCTION", "CALLS", "MIN", "AVG", "MAX"
+                    );
+                    for (method, summary) in contract.summaries() {
+                        println!(
+                            "{:<32} {:>8} {:>12} {:>12} {:>12}",
+                            method, summary.calls, summary.min, summary.avg, summary.max
+                        );
+                    }
+                }
+            }
+            GasReportFormat::Json => {
+                let report = self
+                    .contracts
+                    .iter()
+                    .map(|(name, contract)| {
+                        let summary = ContractSummary {
+                            contract_name: &contract.contract_name,
+                            deployment_gas: contract.deployment_gas,
+                            deployment_size: contract.deployment_size,
+                            functions: contract.summaries(),
+                            cost: contract.cost,
+                            l1_fee: contract.l1_fee,
+                            cost_usd: usd_price.map(|price| usd(contract.cost, price)),
+                        };
+                        (name.as_str(), summary)
+                    })
+                    .collect::<BTreeMap<_, _>>();
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Fee paid for a transaction, in wei, with the L1 data fee of OP-stack chains
+fn fee(receipt: &TransactionReceipt) -> U256 {
+    receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or_default()
+        + receipt_l1_fee(receipt)
+}
+
+impl ContractGas {
+    fn summaries(&self) -> BTreeMap<&str, FunctionSummary> {
+        self.calls
+            .iter()
+            .map(|(method, gas)| {
+                let summary = FunctionSummary {
+                    calls: gas.len(),
+                    min: gas.iter().copied().min().unwrap_or_default(),
+                    avg: gas.iter().sum::<u64>() / gas.len().max(1) as u64,
+                    max: gas.iter().copied().max().unwrap_or_default(),
+                };
+                (method.as_str(), summary)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,197 @@
+use crate::chains::ChainInfo;
+use crate::manifest::Deployment;
+use ethers::types::{Address, H256};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// Longest a webhook may take to answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+// Variables holding signer credentials or secrets, not passed to hook commands
+// along with those ending in `_API_KEY`
+const SECRET_VARIABLES: [&str; 5] = [
+    "PRIVATE_KEY",
+    "MNEMONIC",
+    "KEYSTORE_PASSWORD",
+    "GAS_ORACLE_KEY",
+    "SECRETS_PASSPHRASE",
+];
+
+// Shell commands and webhooks run around a deployment, from the `hooks`
+// section of `solidity-deploy.toml` or of a plan, e.g.
+//
+// [hooks]
+// pre_deploy = ["./scripts/check-release-branch.sh"]
+// post_deploy = ["https://hooks.example.com/deployed", "./scripts/publish-addresses.sh"]
+// on_failure = ["https://hooks.example.com/deploy-failed"]
+//
+// An `http://` or `https://` hook is POSTed the run as JSON, any other one is
+// run with `sh -c` and gets it in `DEPLOY_*` environment variables.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    // Run before the first transaction, a failing one aborts the deployment
+    #[serde(default)]
+    pub pre_deploy: Vec<String>,
+    #[serde(default)]
+    pub post_deploy: Vec<String>,
+    // Run when the deployment fails, their own failures are only reported
+    #[serde(default)]
+    pub on_failure: Vec<String>,
+}
+
+impl Hooks {
+    // The hooks of both, those of `self` first
+    pub fn merged(&self, other: &Hooks) -> Hooks {
+        let join =
+            |a: &[String], b: &[String]| -> Vec<String> { a.iter().chain(b).cloned().collect() };
+        Hooks {
+            pre_deploy: join(&self.pre_deploy, &other.pre_deploy),
+            post_deploy: join(&self.post_deploy, &other.post_deploy),
+            on_failure: join(&self.on_failure, &other.on_failure),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    PreDeploy,
+    PostDeploy,
+    OnFailure,
+}
+
+// What a hook is told about the run
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HookContext<'a> {
+    pub event: HookEvent,
+    pub run: &'a str,
+    pub chain: &'a str,
+    pub chain_id: u64,
+    pub sender: Address,
+    pub deployments: Vec<DeployedContract<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployedContract<'a> {
+    pub name: &'a str,
+    pub contract_name: &'a str,
+    pub address: Address,
+    pub transaction_hash: H256,
+}
+
+impl<'a> HookContext<'a> {
+    pub fn new(event: HookEvent, run: &'a str, chain: &'a ChainInfo, sender: Address) -> Self {
+        HookContext {
+            event,
+            run,
+            chain: &chain.name,
+            chain_id: chain.chain_id,
+            sender,
+            deployments: vec![],
+            error: None,
+        }
+    }
+
+    pub fn with_deployments(mut self, deployments: &'a [Deployment]) -> Self {
+        self.deployments = deployments
+            .iter()
+            .map(|deployment| DeployedContract {
+                name: &deployment.name,
+                contract_name: &deployment.contract_name,
+                address: deployment.address,
+                transaction_hash: deployment.transaction_hash,
+            })
+            .collect();
+        self
+    }
+
+    pub fn with_error(mut self, error: &eyre::Report) -> Self {
+        self.error = Some(format!("{error:#}"));
+        self
+    }
+}
+
+// Run the hooks of the context's event one after another, stopping at the
+// first that fails
+pub async fn run_hooks(hooks: &Hooks, context: &HookContext<'_>) -> Result<()> {
+    let commands = match context.event {
+        HookEvent::PreDeploy => &hooks.pre_deploy,
+        HookEvent::PostDeploy => &hooks.post_deploy,
+        HookEvent::OnFailure => &hooks.on_failure,
+    };
+    for hook in commands {
+        println!("HOOK {}: {hook}", event_name(context.event));
+        match hook.starts_with("http://") || hook.starts_with("https://") {
+            true => webhook(hook, context).await?,
+            false => command(hook, context).await?,
+        }
+    }
+    Ok(())
+}
+
+fn event_name(event: HookEvent) -> &'static str {
+    match event {
+        HookEvent::PreDeploy => "pre_deploy",
+        HookEvent::PostDeploy => "post_deploy",
+        HookEvent::OnFailure => "on_failure",
+    }
+}
+
+async fn webhook(url: &str, context: &HookContext<'_>) -> Result<()> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(context)
+        .send()
+        .await
+        .map_err(|e| eyre!("Webhook {url} failed: {e}"))?;
+    if !response.status().is_success() {
+        return Err(eyre!(
+            "Webhook {url} answered {}: {}",
+            response.status(),
+            response.text().await.unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+// The command sees the run in `DEPLOY_EVENT`, `DEPLOY_RUN`, `DEPLOY_CHAIN`,
+// `DEPLOY_CHAIN_ID`, `DEPLOY_SENDER`, `DEPLOY_ERROR` on failure and, with the
+// deployed addresses, as JSON in `DEPLOY_CONTEXT`. The signer credentials and
+// API keys of the environment are kept from it.
+async fn command(hook: &str, context: &HookContext<'_>) -> Result<()> {
+    let mut command = tokio::process::Command::new("sh");
+    for (name, _) in std::env::vars_os() {
+        let secret = name
+            .to_str()
+            .is_some_and(|name| SECRET_VARIABLES.contains(&name) || name.ends_with("_API_KEY"));
+        if secret {
+            command.env_remove(name);
+        }
+    }
+    command
+        .arg("-c")
+        .arg(hook)
+        .env("DEPLOY_EVENT", event_name(context.event))
+        .env("DEPLOY_RUN", context.run)
+        .env("DEPLOY_CHAIN", context.chain)
+        .env("DEPLOY_CHAIN_ID", context.chain_id.to_string())
+        .env("DEPLOY_SENDER", format!("{:?}", context.sender))
+        .env("DEPLOY_CONTEXT", serde_json::to_string(context)?);
+    if let Some(error) = &context.error {
+        command.env("DEPLOY_ERROR", error);
+    }
+    let status = command
+        .status()
+        .await
+        .map_err(|e| eyre!("Failed to run hook {hook:?}: {e}"))?;
+    if !status.success() {
+        return Err(eyre!("Hook {hook:?} exited with {status}"));
+    }
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use crate::cli_config::InitConfig;
+use crate::compiler::contracts_dir;
+use eyre::{eyre, Result};
+use std::path::Path;
+
+const SAMPLE_CONTRACT: &str = include_str!("../contracts/MessageStorage.sol");
+
+const PROJECT_CONFIG: &str = r#"# Network profiles, selected with `--network <name>`. Every setting is the
+# default of the CLI flag of the same name. `${VAR}` is replaced with an
+# environment variable, `${VAR:-default}` falls back to a default.
+
+[networks.local]
+rpc_url = "http://127.0.0.1:8545"
+chain_id = 31337
+
+[networks.sepolia]
+rpc_url = "${SEPOLIA_RPC_URL:-https://ethereum-sepolia-rpc.publicnode.com}"
+chain_id = 11155111
+confirmations = 2
+"#;
+
+const ENV_EXAMPLE: &str = r#"# Environment variables the CLI flags fall back to. Copied to `.env`, they
+# are loaded on every run, or pass another file with `--env-file`
+# Credentials of the deploying account, a mnemonic or a private key
+MNEMONIC=
+PRIVATE_KEY=
+# API key to verify contracts with `--verify`
+ETHERSCAN_API_KEY=
+# RPC endpoint of the sepolia profile, a public one when empty
+SEPOLIA_RPC_URL=
+"#;
+
+// Entry point of the `init` subcommand: lay out a project that deploys as is
+// with `deploy --contract-name MessageStorage`
+pub fn init(config: InitConfig) -> Result<()> {
+    let files = [
+        (contracts_dir().join("MessageStorage.sol"), SAMPLE_CONTRACT),
+        (
+            Path::new("solidity-deploy.toml").to_path_buf(),
+            PROJECT_CONFIG,
+        ),
+        (Path::new(".env.example").to_path_buf(), ENV_EXAMPLE),
+    ];
+
+    // Nothing is written unless every file can be
+    let existing = files
+        .iter()
+        .map(|(path, _)| config.path.join(path))
+        .filter(|path| path.exists())
+        .collect::<Vec<_>>();
+    if !existing.is_empty() && !config.force {
+        return Err(eyre!(
+            "{existing:?} already exist(s), pass --force to overwrite"
+        ));
+    }
+
+    for (path, content) in files {
+        let path = config.path.join(path);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, content).map_err(|e| eyre!("Failed to write {path:?}: {e}"))?;
+        println!("Created {}", path.display());
+    }
+    println!(
+        "Deploy the sample contract to a local chain with `deploy --contract-name MessageStorage`"
+    );
+    Ok(())
+}
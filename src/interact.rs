@@ -0,0 +1,283 @@
+use crate::abi_args::{encode_call, format_token, parse_tokens, print_named_tokens};
+use crate::audit;
+use crate::cli_config::{CallConfig, FeeConfig, SendConfig};
+use crate::contract_abi::{find_function, function_signature, load_abi, load_abi_file};
+use crate::error::DeployError;
+use crate::fees::resolve_fees;
+use crate::l1_fee::receipt_l1_fee;
+use crate::lock::DeployLock;
+use crate::nonce::check_nonce;
+use crate::output::{self, Event};
+use crate::registry::{
+    aliases, parse_reference, resolve_alias, resolve_aliases, resolve_deployment, DEPLOYER_ALIAS,
+};
+use crate::revert::revert_error;
+use crate::rpc::{self, RpcTransport};
+use crate::signer::{ensure_funded, wallet_from_config};
+use crate::units::format_eth;
+use ethers::abi::{Abi, Function, RawLog, Token};
+use ethers::middleware::SignerMiddleware;
+use ethers::signers::Signer;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Log, TransactionReceipt, TransactionRequest, U256};
+use ethers_providers::Middleware;
+use eyre::{eyre, ContextCompat, Result};
+use std::path::Path;
+
+// Perform a read-only `eth_call` against an arbitrary contract method
+pub async fn call(config: CallConfig) -> Result<()> {
+    let provider = rpc::connect(&config.rpc_url)?;
+    let (address, abi) = resolve_contract(
+        &provider,
+        config.address,
+        config.abi.as_deref(),
+        config.contract_name.as_deref(),
+    )
+    .await?;
+    let function = find_function(&abi, &config.method, config.args.len())?;
+    // The signer is only needed, and a keystore only unlocked, for `@deployer`
+    let deployer = if refers_to_deployer(&config.args) {
+        wallet_from_config(&config.signer)?.map(|wallet| wallet.address())
+    } else {
+        None
+    };
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let args = resolve_args(&config.args, chain_id, deployer)?;
+    let tokens = parse_tokens(&function.inputs, &args)?;
+
+    let decoded = call_method(&provider, &abi, address, function, &tokens).await?;
+    println!("RETURNS:");
+    print_named_tokens(&function.outputs, &decoded);
+    Ok(())
+}
+
+// Sign and submit a transaction to an arbitrary contract method, then wait for
+// confirmations and print the receipt together with the decoded logs
+pub async fn send(config: SendConfig) -> Result<()> {
+    let provider = rpc::connect_with_ws(&config.rpc_url, config.ws_url.as_deref()).await?;
+    let (address, abi) = resolve_contract(
+        &provider,
+        config.address,
+        config.abi.as_deref(),
+        config.contract_name.as_deref(),
+    )
+    .await?;
+    let function = find_function(&abi, &config.method, config.args.len())?;
+
+    let chain_id = provider.get_chainid().await?;
+    let wallet = wallet_from_config(&config.signer)?
+        .context("--private-key, --keystore or --mnemonic is required to send transactions")?
+        .with_chain_id(chain_id.as_u64());
+    // Sends use the nonces of the signer as deployments do
+    let _lock = DeployLock::acquire(chain_id.as_u64(), "send", config.force_unlock)?;
+    let args = resolve_args(&config.args, chain_id.as_u64(), Some(wallet.address()))?;
+    let tokens = parse_tokens(&function.inputs, &args)?;
+    println!("Sender: {:?}", wallet.address());
+    ensure_funded(&provider, wallet.address()).await?;
+    check_nonce(&provider, wallet.address(), config.nonce).await?;
+    let client = SignerMiddleware::new(provider, wallet);
+
+    let receipt = send_method(
+        &client,
+        &abi,
+        address,
+        function,
+        &tokens,
+        config.value,
+        &config.fees,
+        config.confirmations,
+        config.nonce,
+    )
+    .await?;
+    print_receipt(&receipt);
+    print_logs(&abi, &receipt.logs);
+    Ok(())
+}
+
+// Replace `@Name` in method arguments with the address of the deployment of
+// the chain recorded under that name, and `@deployer` with the signer
+fn resolve_args(args: &[String], chain_id: u64, deployer: Option<Address>) -> Result<Vec<String>> {
+    args.iter()
+        .map(|arg| resolve_aliases(arg, |name| resolve_alias(name, chain_id, deployer)))
+        .collect()
+}
+
+fn refers_to_deployer(args: &[String]) -> bool {
+    args.iter()
+        .flat_map(|arg| aliases(arg))
+        .any(|name| name == DEPLOYER_ALIAS)
+}
+
+// `eth_call` a method with already parsed arguments and decode what it returns.
+// Custom errors of the ABI are decoded when the call reverts.
+pub async fn call_method<M: Middleware>(
+    provider: &M,
+    abi: &Abi,
+    address: Address,
+    function: &Function,
+    tokens: &[Token],
+) -> Result<Vec<Token>>
+where
+    M::Error: 'static,
+{
+    let tx = TransactionRequest::new()
+        .to(address)
+        .data(encode_call(function, tokens)?);
+
+    println!(
+        "CALL {} on {:?} with ({})",
+        function_signature(function),
+        address,
+        join_tokens(tokens)
+    );
+    let output = provider
+        .call(&tx.into(), None)
+        .await
+        .map_err(|e| revert_error(e, Some(abi), &format!("Call {}", function.name)))?;
+    Ok(function.decode_output(&output)?)
+}
+
+// Send a transaction calling a method with already parsed arguments and wait
+// for its receipt
+#[allow(clippy::too_many_arguments)]
+pub async fn send_method<M: Middleware<Provider = RpcTransport>>(
+    client: &M,
+    abi: &Abi,
+    address: Address,
+    function: &Function,
+    tokens: &[Token],
+    value: Option<U256>,
+    fee_config: &FeeConfig,
+    confirmations: usize,
+    nonce: Option<U256>,
+) -> Result<TransactionReceipt>
+where
+    M::Error: 'static,
+{
+    let mut tx = TransactionRequest::new()
+        .to(address)
+        .data(encode_call(function, tokens)?);
+    if let Some(value) = value {
+        tx = tx.value(value);
+    }
+    if let Some(nonce) = nonce {
+        tx = tx.nonce(nonce);
+    }
+    let mut tx = TypedTransaction::Legacy(tx);
+    let fees = resolve_fees(client, fee_config).await?;
+    fees.apply(&mut tx);
+    println!("Fees: {fees}");
+
+    println!(
+        "SEND {} on {:?} with ({})",
+        function_signature(function),
+        address,
+        join_tokens(tokens)
+    );
+    let pending_tx = client
+        .send_transaction(tx.clone(), None)
+        .await
+        .map_err(|e| revert_error(e, Some(abi), &format!("Transaction {}", function.name)))?;
+    let action = format!("send {}", function_signature(function));
+    audit::record(client, &action, &tx, pending_tx.tx_hash()).await;
+    let transaction = pending_tx.tx_hash();
+    println!("Transaction hash: {transaction:?}");
+    Ok(rpc::confirm(client.provider(), pending_tx, confirmations)
+        .await?
+        .ok_or(DeployError::Dropped { transaction })?)
+}
+
+fn join_tokens(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .map(format_token)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn print_receipt(receipt: &TransactionReceipt) {
+    output::emit(Event::tx_confirmed(receipt));
+    println!("RECEIPT:");
+    println!("  transaction hash: {:?}", receipt.transaction_hash);
+    println!("  block number: {:?}", receipt.block_number);
+    println!(
+        "  status: {}",
+        match receipt.status.map(|status| status.as_u64()) {
+            Some(1) => "success",
+            Some(_) => "reverted",
+            None => "unknown",
+        }
+    );
+    println!("  gas used: {:?}", receipt.gas_used);
+    let l1_fee = receipt_l1_fee(receipt);
+    if !l1_fee.is_zero() {
+        println!("  L1 data fee: {}", format_eth(l1_fee));
+    }
+}
+
+// Decode logs with the events of the given ABI, falling back to raw topics
+pub fn print_logs(abi: &Abi, logs: &[Log]) {
+    println!("LOGS: {}", logs.len());
+    for log in logs {
+        print_log(abi, log);
+    }
+}
+
+pub fn print_log(abi: &Abi, log: &Log) {
+    let event = log
+        .topics
+        .first()
+        .and_then(|topic0| abi.events().find(|event| event.signature() == *topic0));
+    let decoded = event.and_then(|event| {
+        let raw = RawLog {
+            topics: log.topics.clone(),
+            data: log.data.to_vec(),
+        };
+        event.parse_log(raw).ok().map(|parsed| (event, parsed))
+    });
+    match decoded {
+        Some((event, parsed)) => {
+            println!("  {} emitted by {:?}", event.name, log.address);
+            for param in parsed.params {
+                println!("    {} = {}", param.name, format_token(&param.value));
+            }
+        }
+        None => println!(
+            "  unknown event emitted by {:?}, topics: {:?}, data: {}",
+            log.address, log.topics, log.data
+        ),
+    }
+}
+
+// Use the given address and ABI, or resolve both from the deployment manifest
+// when no address is given. The contract may be named `Name@network`.
+pub async fn resolve_contract<M: Middleware>(
+    provider: &M,
+    address: Option<Address>,
+    abi_path: Option<&Path>,
+    contract_name: Option<&str>,
+) -> Result<(Address, Abi)>
+where
+    M::Error: 'static,
+{
+    match (address, contract_name) {
+        (Some(address), _) => {
+            let contract_name = contract_name.map(|name| parse_reference(name).0);
+            Ok((address, load_abi(abi_path, contract_name).await?))
+        }
+        (None, Some(contract_name)) => {
+            let chain_id = provider.get_chainid().await?.as_u64();
+            let deployment = resolve_deployment(contract_name, Some(chain_id))?;
+            println!(
+                "Resolved {contract_name} on chain {chain_id} to {:?}",
+                deployment.address
+            );
+            let abi = match abi_path {
+                Some(path) => load_abi_file(path)?,
+                None => deployment.abi,
+            };
+            Ok((deployment.address, abi))
+        }
+        (None, None) => Err(eyre!("Either --address or --contract-name is required")),
+    }
+}
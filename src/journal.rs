@@ -0,0 +1,141 @@
+use crate::manifest::DEPLOYMENTS_FOLDER;
+use ethers::types::{TransactionReceipt, H256};
+use ethers_providers::{Middleware, PendingTransaction};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// Progress of a multi-step deployment, persisted after every transaction so
+// an interrupted run can be resumed with `deploy --resume`
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Journal {
+    steps: Vec<JournalStep>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalStep {
+    pub name: String,
+    pub transaction_hash: H256,
+    pub confirmed: bool,
+}
+
+pub fn journal_path(chain_id: u64, contract_name: &str) -> PathBuf {
+    PathBuf::from(DEPLOYMENTS_FOLDER)
+        .join(chain_id.to_string())
+        .join(format!("{contract_name}.journal.json"))
+}
+
+impl Journal {
+    // Load the journal to resume from, or start a fresh one
+    pub fn open(path: PathBuf, resume: bool) -> Result<Journal> {
+        if !resume || !path.exists() {
+            if resume {
+                println!("No journal at {}, starting from scratch", path.display());
+            }
+            return Ok(Journal {
+                steps: vec![],
+                path,
+            });
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let mut journal: Journal = serde_json::from_str(&content)
+            .map_err(|e| eyre!("Failed to parse deployment journal {path:?}: {e}"))?;
+        println!(
+            "Resuming from {} with {} recorded step(s)",
+            path.display(),
+            journal.steps.len()
+        );
+        journal.path = path;
+        Ok(journal)
+    }
+
+    // Receipt of a step recorded by a previous run, if its transaction is
+    // confirmed on-chain. Transactions still pending are waited for; steps
+    // whose transaction the node no longer knows are sent again.
+    pub async fn completed<M: Middleware>(
+        &self,
+        client: &M,
+        name: &str,
+    ) -> Result<Option<TransactionReceipt>>
+    where
+        M::Error: 'static,
+    {
+        let Some(step) = self.steps.iter().find(|step| step.name == name) else {
+            return Ok(None);
+        };
+
+        let receipt = match client
+            .get_transaction_receipt(step.transaction_hash)
+            .await?
+        {
+            Some(receipt) => Some(receipt),
+            None if client
+                .get_transaction(step.transaction_hash)
+                .await?
+                .is_some() =>
+            {
+                println!(
+                    "Waiting for pending transaction {:?} of step {name}",
+                    step.transaction_hash
+                );
+                PendingTransaction::new(step.transaction_hash, client.provider()).await?
+            }
+            None => None,
+        };
+
+        match receipt {
+            Some(receipt) if receipt.status.is_some_and(|status| status.is_zero()) => Err(eyre!(
+                "Step {name} reverted in transaction {:?}",
+                step.transaction_hash
+            )),
+            Some(receipt) => {
+                println!(
+                    "Skipping step {name}, confirmed in {:?}",
+                    receipt.transaction_hash
+                );
+                Ok(Some(receipt))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn record_sent(&mut self, name: &str, transaction_hash: H256) -> Result<()> {
+        self.steps.retain(|step| step.name != name);
+        self.steps.push(JournalStep {
+            name: name.to_owned(),
+            transaction_hash,
+            confirmed: false,
+        });
+        self.save()
+    }
+
+    pub fn record_confirmed(&mut self, name: &str) -> Result<()> {
+        if let Some(step) = self.steps.iter_mut().find(|step| step.name == name) {
+            step.confirmed = true;
+        }
+        self.save()
+    }
+
+    // Remove the journal once every step went through. The journal can then
+    // record the steps of another run, e.g. a redeploy in watch mode.
+    pub fn finish(&mut self) -> Result<()> {
+        self.steps.clear();
+        if self.path.exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| eyre!("Failed to write deployment journal {:?}: {e}", self.path))
+    }
+}
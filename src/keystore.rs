@@ -0,0 +1,26 @@
+use ethers::signers::LocalWallet;
+use eyre::{eyre, Result};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+// Decrypt a geth-style encrypted JSON keystore. The password is taken from
+// the given value or prompted for without echo, and wiped from memory on drop.
+pub fn load_keystore(path: &Path, password: Option<&str>) -> Result<LocalWallet> {
+    if !path.exists() {
+        return Err(eyre!("Keystore {path:?} does not exist!"));
+    }
+
+    let password = match password {
+        Some(password) => Zeroizing::new(password.to_owned()),
+        None => read_password(&format!("Password for keystore {}: ", path.display()))?,
+    };
+    LocalWallet::decrypt_keystore(path, password.as_bytes())
+        .map_err(|e| eyre!("Failed to decrypt keystore {path:?}: {e}"))
+}
+
+// Prompt on the terminal without echoing the typed characters
+pub fn read_password(prompt: &str) -> Result<Zeroizing<String>> {
+    let password =
+        rpassword::prompt_password(prompt).map_err(|e| eyre!("Failed to read password: {e}"))?;
+    Ok(Zeroizing::new(password))
+}
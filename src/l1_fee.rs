@@ -0,0 +1,48 @@
+use ethers::abi::parse_abi;
+use ethers::contract::BaseContract;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, TransactionReceipt, TransactionRequest, H160, U256};
+use ethers_providers::Middleware;
+use eyre::Result;
+
+// GasPriceOracle predeploy of OP-stack chains (Optimism, Base, ...), pricing
+// the L1 data fee their transactions pay on top of the L2 gas
+const GAS_PRICE_ORACLE: Address = H160([
+    0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x0f,
+]);
+
+// Whether the chain is an OP-stack rollup charging an L1 data fee, told by
+// the oracle predeploy being there. Also true on Anvil forks of one.
+pub async fn is_op_stack<M: Middleware>(client: &M) -> Result<bool>
+where
+    M::Error: 'static,
+{
+    Ok(!client.get_code(GAS_PRICE_ORACLE, None).await?.is_empty())
+}
+
+// L1 data fee of `tx` in wei, as priced by the oracle from its serialized
+// size. The oracle accounts for the signature the unsigned `tx` lacks.
+pub async fn l1_fee<M: Middleware>(client: &M, tx: &TypedTransaction) -> Result<U256>
+where
+    M::Error: 'static,
+{
+    let oracle = BaseContract::from(parse_abi(&[
+        "function getL1Fee(bytes _data) view returns (uint256)",
+    ])?);
+    let call: TypedTransaction = TransactionRequest::new()
+        .to(GAS_PRICE_ORACLE)
+        .data(oracle.encode("getL1Fee", (tx.rlp(),))?)
+        .into();
+    Ok(oracle.decode_output("getL1Fee", client.call(&call, None).await?)?)
+}
+
+// L1 data fee an OP-stack node reports in the receipt of a transaction, zero
+// on other chains
+pub fn receipt_l1_fee(receipt: &TransactionReceipt) -> U256 {
+    receipt
+        .other
+        .get_deserialized::<U256>("l1Fee")
+        .and_then(|fee| fee.ok())
+        .unwrap_or_default()
+}
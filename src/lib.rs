@@ -0,0 +1,109 @@
+//! Compile, deploy and manage Solidity contracts from Rust.
+//!
+//! The `deploy` binary is a command line front end to this crate, other
+//! programs can use the same building blocks:
+//!
+//! - [`compiler`] compiles a project with solc and checks the bytecode sizes
+//! - [`signer`] builds the wallet from a private key, keystore or mnemonic
+//! - [`deployer`] sends deployments and records them
+//! - [`manifest`] reads and writes the deployment manifests in `deployments/`
+//!
+//! ```no_run
+//! use solidity_deploy_rs::cli_config::CompilerConfig;
+//! use solidity_deploy_rs::{compiler, deployer};
+//! use std::path::Path;
+//!
+//! # async fn example() -> eyre::Result<()> {
+//! let output = compiler::compile(Path::new("contracts/"), None, &CompilerConfig::default()).await?;
+//! let (abi, bytecode) = deployer::find_contract(&output, "MessageStorage")?;
+//! # Ok(())
+//! # }
+//! ```
+
+// Human-oriented output, suppressed in `--json` mode so that stdout only
+// carries the JSON events. Defined before the modules to shadow the std macro.
+macro_rules! println {
+    ($($arg:tt)*) => {
+        if !$crate::output::json() {
+            std::println!($($arg)*);
+        }
+    };
+}
+
+pub mod abi_args;
+pub mod abi_codec;
+pub mod abi_diff;
+pub mod abi_export;
+pub mod accounts;
+pub mod artifacts;
+pub mod audit;
+pub mod bindgen;
+pub mod calls;
+pub mod chains;
+pub mod clean;
+pub mod cli_config;
+pub mod compiler;
+pub mod console;
+pub mod contract_abi;
+pub mod create2;
+pub mod decode;
+pub mod deployer;
+pub mod ens;
+pub mod env;
+pub mod error;
+pub mod estimate;
+pub mod events;
+pub mod fees;
+pub mod flatten;
+pub mod fund;
+pub mod gas_report;
+pub mod hooks;
+pub mod init;
+pub mod interact;
+pub mod journal;
+pub mod keystore;
+pub mod l1_fee;
+pub mod lock;
+pub mod logs;
+pub mod manifest;
+pub mod multicall;
+pub mod multichain;
+pub mod network;
+pub mod node_info;
+pub mod nonce;
+pub mod offline;
+pub mod optimize_report;
+pub mod output;
+pub mod permissions;
+pub mod plan;
+pub mod predict;
+pub mod price_feed;
+pub mod progress;
+pub mod prompt;
+pub mod proxy;
+pub mod registry;
+pub mod remappings;
+pub mod revert;
+pub mod rpc;
+pub mod safe;
+pub mod secrets;
+pub mod selectors;
+pub mod signer;
+pub mod simulate;
+pub mod smart_account;
+pub mod snapshot;
+pub mod standard_json;
+pub mod steps;
+pub mod storage;
+pub mod storage_layout;
+pub mod timelock;
+pub mod trace;
+pub mod tx;
+pub mod tx_store;
+pub mod typed_data;
+pub mod units;
+pub mod upgrade;
+pub mod verify;
+pub mod verify_bytecode;
+pub mod watch;
+pub mod zksync;
@@ -0,0 +1,150 @@
+use crate::manifest::DEPLOYMENTS_FOLDER;
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+// Run holding the lock of a network, the content of its lock file
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LockOwner {
+    pid: u32,
+    hostname: String,
+    // Unix timestamp in seconds
+    acquired_at: u64,
+    // Subcommand of the run, e.g. `deploy`. Not its flags, which may hold
+    // secrets such as `--private-key` and end up in the errors of other runs.
+    command: String,
+}
+
+impl LockOwner {
+    fn current(command: &str) -> Result<Self> {
+        Ok(LockOwner {
+            pid: std::process::id(),
+            hostname: hostname(),
+            acquired_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            command: command.to_owned(),
+        })
+    }
+
+    // Locks of older versions hold the whole command line, of which only the
+    // first word is shown
+    fn command(&self) -> &str {
+        self.command.split_whitespace().next().unwrap_or("unknown")
+    }
+
+    // Left behind by a run that died without releasing it. Only a run of the
+    // same host can be checked, those of other hosts are assumed alive.
+    fn is_stale(&self) -> bool {
+        self.hostname == hostname() && !process_running(self.pid)
+    }
+}
+
+pub fn lock_path(chain_id: u64) -> PathBuf {
+    PathBuf::from(DEPLOYMENTS_FOLDER)
+        .join(chain_id.to_string())
+        .join("deploy.lock")
+}
+
+// Lock of a network held while a run broadcasts to it, so two runs cannot
+// race the same nonces. Released when dropped.
+pub struct DeployLock {
+    path: PathBuf,
+}
+
+impl DeployLock {
+    // Take the lock of the chain for `command`, the subcommand shown to runs
+    // waiting for it, replacing a stale one, or any one with `force`
+    pub fn acquire(chain_id: u64, command: &str, force: bool) -> Result<Self> {
+        let path = lock_path(chain_id);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let owner = serde_json::to_string_pretty(&LockOwner::current(command)?)?;
+        // A second attempt once a stale lock is removed, in case another run
+        // took it in between
+        for _ in 0..2 {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    file.write_all(owner.as_bytes())?;
+                    return Ok(DeployLock { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let holder = std::fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|content| serde_json::from_str::<LockOwner>(&content).ok());
+                    let description = match &holder {
+                        Some(holder) => format!(
+                            "PID {} on {} since {}: {}",
+                            holder.pid,
+                            holder.hostname,
+                            holder.acquired_at,
+                            holder.command()
+                        ),
+                        None => "an unreadable lock".to_owned(),
+                    };
+                    if force {
+                        println!("Taking over the lock of chain {chain_id} held by {description}");
+                    } else if holder.as_ref().is_some_and(LockOwner::is_stale) {
+                        println!(
+                            "Removing the stale lock of chain {chain_id} left by {description}"
+                        );
+                    } else {
+                        return Err(eyre!(
+                            "Chain {chain_id} is locked by another deployment, {description}. \
+                             Wait for it to finish, or remove {} or pass --force-unlock if it died",
+                            path.display()
+                        ));
+                    }
+                    match std::fs::remove_file(&path) {
+                        Ok(()) => {}
+                        Err(e) if e.kind() == ErrorKind::NotFound => {}
+                        Err(e) => return Err(eyre!("Failed to remove {}: {e}", path.display())),
+                    }
+                }
+                Err(e) => return Err(eyre!("Failed to create {}: {e}", path.display())),
+            }
+        }
+        Err(eyre!(
+            "Chain {chain_id} was locked again by another deployment while taking over its lock"
+        ))
+    }
+}
+
+impl Drop for DeployLock {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!("Failed to release the lock {}: {e}", self.path.display());
+        }
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+#[cfg(unix)]
+fn process_running(pid: u32) -> bool {
+    // Signal 0 only checks that the process exists
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_or(true, |status| status.success())
+}
+
+#[cfg(not(unix))]
+fn process_running(_pid: u32) -> bool {
+    true
+}
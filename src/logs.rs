@@ -0,0 +1,121 @@
+use crate::abi_args::format_token;
+use crate::cli_config::{LogsConfig, LogsFormat};
+use crate::contract_abi::{event_signature, find_event, load_abi};
+use crate::registry::{parse_reference, resolve_deployment};
+use crate::rpc;
+use ethers::abi::{Event, RawLog};
+use ethers::types::{BlockNumber, Filter, Log};
+use ethers_providers::Middleware;
+use eyre::{eyre, Result};
+
+// Entry point of the `logs` subcommand: fetch the past logs of one event of a
+// deployed contract and print them decoded, as a table or as JSON
+pub async fn logs(config: LogsConfig) -> Result<()> {
+    let provider = rpc::connect(&config.rpc_url)?;
+    let (address, abi, deployed_at) = match config.address {
+        Some(address) => (
+            address,
+            load_abi(None, Some(parse_reference(&config.contract_name).0)).await?,
+            None,
+        ),
+        None => {
+            let chain_id = provider.get_chainid().await?.as_u64();
+            let deployment = resolve_deployment(&config.contract_name, Some(chain_id))?;
+            (deployment.address, deployment.abi, deployment.block_number)
+        }
+    };
+    let event = find_event(&abi, &config.event)?;
+
+    // Nothing can be logged before the contract was deployed
+    let from_block = config.from_block.unwrap_or(match deployed_at {
+        Some(block) => BlockNumber::Number(block.into()),
+        None => BlockNumber::Earliest,
+    });
+    let filter = Filter::new()
+        .address(address)
+        .topic0(event.signature())
+        .from_block(from_block)
+        .to_block(config.to_block);
+    let logs = provider.get_logs(&filter).await?;
+
+    match config.format {
+        LogsFormat::Table => print_table(event, &logs)?,
+        LogsFormat::Json => {
+            let json = to_json(event, &logs)?;
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+    }
+    Ok(())
+}
+
+fn print_table(event: &Event, logs: &[Log]) -> Result<()> {
+    println!("{}", "=".repeat(80));
+    println!("{}: {} log(s)", event_signature(event), logs.len());
+    println!("{:<10} {:<66} {:>6}  ARGS", "BLOCK", "TRANSACTION", "INDEX");
+    for log in logs {
+        let args = decode(event, log)?
+            .into_iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!(
+            "{:<10} {:<66} {:>6}  {args}",
+            log.block_number
+                .map_or("pending".to_owned(), |n| n.to_string()),
+            log.transaction_hash
+                .map_or("-".to_owned(), |hash| format!("{hash:?}")),
+            log.log_index
+                .map_or("-".to_owned(), |index| index.to_string()),
+        );
+    }
+    Ok(())
+}
+
+fn to_json(event: &Event, logs: &[Log]) -> Result<serde_json::Value> {
+    let logs = logs
+        .iter()
+        .map(|log| {
+            let args = decode(event, log)?
+                .into_iter()
+                .map(|(name, value)| (name, serde_json::Value::String(value)))
+                .collect::<serde_json::Map<_, _>>();
+            Ok(serde_json::json!({
+                "event": event.name,
+                "address": log.address,
+                "blockNumber": log.block_number.map(|number| number.as_u64()),
+                "transactionHash": log.transaction_hash,
+                "logIndex": log.log_index,
+                "args": args,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(serde_json::Value::Array(logs))
+}
+
+// Decoded arguments of a log, by name, in the literal form `call` accepts.
+// Unnamed arguments are named by their position.
+fn decode(event: &Event, log: &Log) -> Result<Vec<(String, String)>> {
+    let raw = RawLog {
+        topics: log.topics.clone(),
+        data: log.data.to_vec(),
+    };
+    let parsed = event.parse_log(raw).map_err(|e| {
+        eyre!(
+            "Failed to decode {} log in transaction {:?}: {e}",
+            event.name,
+            log.transaction_hash
+        )
+    })?;
+    Ok(parsed
+        .params
+        .into_iter()
+        .enumerate()
+        .map(|(index, param)| {
+            let name = match param.name.is_empty() {
+                true => index.to_string(),
+                false => param.name,
+            };
+            (name, format_token(&param.value))
+        })
+        .collect())
+}
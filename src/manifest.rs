@@ -0,0 +1,122 @@
+use crate::proxy::ProxyKind;
+use ethers::abi::Abi;
+use ethers::types::{Address, Bytes, H256};
+use ethers_solc::artifacts::{Settings, StorageLayout};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Root directory of the per-network deployment manifests
+pub const DEPLOYMENTS_FOLDER: &str = "deployments/";
+
+/// Record of a deployed contract, stored at `deployments/<chain-id>/<name>.json`.
+/// The name is the contract name unless a deployment plan gave it another one.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Deployment {
+    pub name: String,
+    pub contract_name: String,
+    pub chain_id: u64,
+    pub address: Address,
+    pub transaction_hash: H256,
+    pub block_number: Option<u64>,
+    pub deployer: Address,
+    pub abi: Abi,
+    // Constructor arguments as given on the command line and ABI encoded
+    pub constructor_args: Vec<String>,
+    pub encoded_constructor_args: Bytes,
+    pub compiler: CompilerInfo,
+    // CREATE2 salt, deploying the same init code with it gives the same
+    // address on any chain with the deterministic deployment proxy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<H256>,
+    // Set when `address` is a proxy in front of the deployed contract
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyInfo>,
+    // Checked against the next implementation when upgrading a proxy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_layout: Option<StorageLayout>,
+    // Libraries linked into the bytecode, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub libraries: BTreeMap<String, Address>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CompilerInfo {
+    // Full solc version, e.g. `0.8.29+commit.ab55807c`
+    pub version: String,
+    pub settings: Settings,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyInfo {
+    pub kind: ProxyKind,
+    pub implementation: Address,
+    // Name the implementation's own manifest is recorded under
+    pub implementation_name: String,
+    // Admin allowed to upgrade a transparent proxy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin: Option<Address>,
+}
+
+/// Path of the manifest of a deployment, `deployments/<chain-id>/<name>.json`
+pub fn manifest_path(chain_id: u64, name: &str) -> PathBuf {
+    PathBuf::from(DEPLOYMENTS_FOLDER)
+        .join(chain_id.to_string())
+        .join(format!("{name}.json"))
+}
+
+impl Deployment {
+    /// Write (or overwrite) the manifest of this deployment
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = manifest_path(self.chain_id, &self.name);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| eyre!("Failed to write deployment manifest {path:?}: {e}"))?;
+        Ok(path)
+    }
+
+    /// Every deployment recorded for any chain, ordered by chain and name
+    pub fn list_all() -> Result<Vec<Deployment>> {
+        let mut deployments = vec![];
+        let root = PathBuf::from(DEPLOYMENTS_FOLDER);
+        if !root.is_dir() {
+            return Ok(deployments);
+        }
+        for chain_dir in std::fs::read_dir(&root)? {
+            let chain_dir = chain_dir?.path();
+            if !chain_dir.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(&chain_dir)? {
+                let path = entry?.path();
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                // Journals share the folder with the manifests
+                if !file_name.ends_with(".json") || file_name.ends_with(".journal.json") {
+                    continue;
+                }
+                let content = std::fs::read_to_string(&path)?;
+                let deployment: Deployment = serde_json::from_str(&content)
+                    .map_err(|e| eyre!("Failed to parse deployment manifest {path:?}: {e}"))?;
+                deployments.push(deployment);
+            }
+        }
+        deployments.sort_by(|a, b| (a.chain_id, &a.name).cmp(&(b.chain_id, &b.name)));
+        Ok(deployments)
+    }
+
+    /// Read the manifest of a contract deployed on the given chain
+    pub fn load(chain_id: u64, name: &str) -> Result<Deployment> {
+        let path = manifest_path(chain_id, name);
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            eyre!("No deployment of {name} recorded for chain {chain_id} ({path:?}): {e}")
+        })?;
+        serde_json::from_str(&content)
+            .map_err(|e| eyre!("Failed to parse deployment manifest {path:?}: {e}"))
+    }
+}
@@ -0,0 +1,112 @@
+use ethers::abi::{parse_abi, Token};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionRequest};
+use ethers_providers::{Middleware, MiddlewareError};
+use eyre::{eyre, ContextCompat, Result};
+
+// Multicall3, deployed at the same address on most chains
+const MULTICALL3: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+// Read-only calls batched into a single `aggregate3` call of Multicall3, so
+// many getters cost one round trip. Chains without Multicall3, e.g. a fresh
+// Anvil, get one `eth_call` per read instead. Multicall3 is the caller of the
+// batched reads, methods depending on `msg.sender` see its address.
+pub struct BatchReader<'a, M> {
+    client: &'a M,
+    reads: Vec<(Address, Bytes)>,
+}
+
+impl<'a, M: Middleware> BatchReader<'a, M>
+where
+    M::Error: 'static,
+{
+    pub fn new(client: &'a M) -> Self {
+        BatchReader {
+            client,
+            reads: Vec::new(),
+        }
+    }
+
+    // Queue a call of `target` and return its index in the results of `read`
+    pub fn add(&mut self, target: Address, calldata: Bytes) -> usize {
+        self.reads.push((target, calldata));
+        self.reads.len() - 1
+    }
+
+    // Run the queued reads. Every read returns its output, or its revert data
+    // when it reverted; a failing read does not fail the others.
+    pub async fn read(&self) -> Result<Vec<Result<Bytes, Bytes>>> {
+        let multicall: Address = MULTICALL3.parse()?;
+        if self.reads.len() < 2 || self.client.get_code(multicall, None).await?.is_empty() {
+            return self.read_one_by_one().await;
+        }
+
+        let abi = parse_abi(&[
+            "function aggregate3((address,bool,bytes)[]) payable returns ((bool,bytes)[])",
+        ])?;
+        let aggregate3 = abi.function("aggregate3")?;
+        let calls = self
+            .reads
+            .iter()
+            .map(|(target, calldata)| {
+                Token::Tuple(vec![
+                    Token::Address(*target),
+                    Token::Bool(true),
+                    Token::Bytes(calldata.to_vec()),
+                ])
+            })
+            .collect();
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(multicall)
+            .data(aggregate3.encode_input(&[Token::Array(calls)])?)
+            .into();
+        let output = self
+            .client
+            .call(&tx, None)
+            .await
+            .map_err(|e| eyre!("Multicall3 batch of {} reads failed: {e}", self.reads.len()))?;
+
+        let results = aggregate3
+            .decode_output(&output)?
+            .into_iter()
+            .next()
+            .and_then(Token::into_array)
+            .context("Multicall3 returned no results")?;
+        if results.len() != self.reads.len() {
+            return Err(eyre!(
+                "Multicall3 returned {} results for {} reads",
+                results.len(),
+                self.reads.len()
+            ));
+        }
+        results
+            .into_iter()
+            .map(|result| match result.into_tuple().as_deref() {
+                Some([Token::Bool(true), Token::Bytes(data)]) => Ok(Ok(data.clone().into())),
+                Some([Token::Bool(false), Token::Bytes(data)]) => Ok(Err(data.clone().into())),
+                _ => Err(eyre!("Multicall3 returned a malformed result")),
+            })
+            .collect()
+    }
+
+    async fn read_one_by_one(&self) -> Result<Vec<Result<Bytes, Bytes>>> {
+        let mut results = Vec::with_capacity(self.reads.len());
+        for (target, calldata) in &self.reads {
+            let tx: TypedTransaction = TransactionRequest::new()
+                .to(*target)
+                .data(calldata.clone())
+                .into();
+            match self.client.call(&tx, None).await {
+                Ok(output) => results.push(Ok(output)),
+                // Reverts without data still come back as an error response
+                Err(e) => match e.as_error_response() {
+                    Some(response) => {
+                        results.push(Err(response.as_revert_data().unwrap_or_default()))
+                    }
+                    None => return Err(eyre!("Read of {target:?} failed: {e}")),
+                },
+            }
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,107 @@
+use crate::cli_config::DeployConfig;
+use crate::deployer::deploy_to_chain;
+use crate::manifest::{manifest_path, Deployment};
+use crate::network::deploy_config_for;
+use eyre::{eyre, Result};
+use futures::future::join_all;
+use std::time::{Duration, Instant};
+use tracing::{info_span, Instrument};
+
+// Outcome of the deployment to one network of `--networks`
+struct NetworkResult {
+    network: String,
+    elapsed: Duration,
+    result: Result<Vec<Deployment>>,
+}
+
+// Entry point of `deploy --networks`: deploy the contract or plan of `config`
+// to every network, one after the other or all at once with `--parallel`,
+// then report on all of them. A network that fails does not stop the
+// others, the run fails once they are all done.
+pub async fn deploy_to_networks(config: DeployConfig) -> Result<()> {
+    // Every profile is read up front so a typo fails before anything is sent
+    let configs = config
+        .networks
+        .iter()
+        .map(|network| Ok((network.clone(), deploy_config_for(network, &config)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let results = if config.parallel {
+        join_all(
+            configs
+                .into_iter()
+                .map(|(network, config)| deploy_network(network, config)),
+        )
+        .await
+    } else {
+        let mut results = vec![];
+        for (network, config) in configs {
+            results.push(deploy_network(network, config).await);
+        }
+        results
+    };
+
+    print_report(&results);
+    let failed = results
+        .iter()
+        .filter(|result| result.result.is_err())
+        .map(|result| result.network.as_str())
+        .collect::<Vec<_>>();
+    if !failed.is_empty() {
+        return Err(eyre!(
+            "Deployment failed on {} of {} networks: {}",
+            failed.len(),
+            results.len(),
+            failed.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+async fn deploy_network(network: String, config: DeployConfig) -> NetworkResult {
+    println!("{}", "=".repeat(80));
+    println!("NETWORK {network}");
+    let started = Instant::now();
+    let result = deploy_to_chain(config)
+        .instrument(info_span!("network", network = network.as_str()))
+        .await;
+    NetworkResult {
+        network,
+        elapsed: started.elapsed(),
+        result,
+    }
+}
+
+fn print_report(results: &[NetworkResult]) {
+    println!("{}", "=".repeat(80));
+    println!(
+        "{:<24} {:>10} {:>10}  {}",
+        "NETWORK", "STATUS", "TIME", "DEPLOYMENTS"
+    );
+    for result in results {
+        let elapsed = format!("{}s", result.elapsed.as_secs());
+        match &result.result {
+            Ok(deployments) => {
+                println!(
+                    "{:<24} {:>10} {:>10}  {}",
+                    result.network,
+                    "deployed",
+                    elapsed,
+                    deployments.len()
+                );
+                for deployment in deployments {
+                    println!(
+                        "  {} at {:?}, manifest {}",
+                        deployment.name,
+                        deployment.address,
+                        manifest_path(deployment.chain_id, &deployment.name).display()
+                    );
+                }
+            }
+            Err(e) => {
+                println!("{:<24} {:>10} {:>10}  -", result.network, "failed", elapsed);
+                println!("  {e:#}");
+            }
+        }
+    }
+}
@@ -0,0 +1,544 @@
+use crate::chains::{self, CustomChain};
+use crate::cli_config::{
+    flag_before_parsing, Command, Config, DeployConfig, GasOracleKind, SignerConfig, TxCommand,
+};
+use crate::env::interpolate;
+use crate::hooks::Hooks;
+use crate::prompt;
+use crate::rpc;
+use crate::secrets::{parse_secret, resolve_secret, SECRET_PREFIX};
+use ethers_providers::Middleware;
+use eyre::{eyre, ContextCompat, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// Project configuration with the named network profiles, in the working directory
+const CONFIG_FILE: &str = "solidity-deploy.toml";
+// Variables of the flags that decrypt `secret:` values themselves
+const SECRET_VARIABLES: [&str; 7] = [
+    "RPC_URL",
+    "WS_URL",
+    "ETHERSCAN_API_KEY",
+    "GAS_ORACLE_KEY",
+    "MNEMONIC",
+    "PRIVATE_KEY",
+    "KEYSTORE_PASSWORD",
+];
+
+// `solidity-deploy.toml` with the project's settings, network profiles and
+// the chains added to the built-in registry. `${VAR}` placeholders are taken
+// from the environment or `.env`, e.g.
+//
+// contracts_dir = "src/"
+//
+// [networks.sepolia]
+// rpc_url = "https://sepolia.infura.io/v3/${INFURA_KEY}"
+// ws_url = "wss://sepolia.infura.io/ws/v3/${INFURA_KEY}"
+// chain_id = 11155111
+// etherscan_api_key = "${ETHERSCAN_API_KEY}"
+// mnemonic = "secret:deployer"
+// confirmations = 2
+// rpc_retries = 8
+// gas_oracle = "fee-history"
+//
+// [profiles.prod]
+// network = "mainnet"
+// keystore = "keys/prod.json"
+// confirmations = 3
+// verify = true
+// strict = true
+//
+// [hooks]
+// on_failure = ["https://hooks.example.com/deploy-failed"]
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    // Default of `--contracts-dir`
+    contracts_dir: Option<PathBuf>,
+    #[serde(default)]
+    networks: BTreeMap<String, NetworkProfile>,
+    #[serde(default)]
+    profiles: BTreeMap<String, EnvironmentProfile>,
+    #[serde(default)]
+    pub chains: BTreeMap<String, CustomChain>,
+    #[serde(default)]
+    pub hooks: Hooks,
+}
+
+// Settings of one network. Each one is the default of the CLI flag and
+// environment variable of the same name.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct NetworkProfile {
+    rpc_url: Option<String>,
+    ws_url: Option<String>,
+    chain_id: Option<u64>,
+    etherscan_api_key: Option<String>,
+    confirmations: Option<usize>,
+    rpc_retries: Option<u32>,
+    rpc_retry_backoff_ms: Option<u64>,
+    rpc_retry_budget: Option<u32>,
+    gas_oracle: Option<String>,
+    fee_percentile: Option<f64>,
+    gas_oracle_key: Option<String>,
+    safe: Option<String>,
+    safe_service_url: Option<String>,
+    mnemonic: Option<String>,
+    derivation_path: Option<String>,
+    account_index: Option<u32>,
+    private_key: Option<String>,
+    keystore: Option<PathBuf>,
+    keystore_password: Option<String>,
+}
+
+// Settings of an environment such as `staging` or `prod`, selected with
+// `--profile`. They pick a network profile and win over its settings.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct EnvironmentProfile {
+    network: Option<String>,
+    confirmations: Option<usize>,
+    verify: Option<bool>,
+    etherscan_api_key: Option<String>,
+    mnemonic: Option<String>,
+    derivation_path: Option<String>,
+    account_index: Option<u32>,
+    private_key: Option<String>,
+    keystore: Option<PathBuf>,
+    keystore_password: Option<String>,
+    // Whatever the run sends needs the profile name typed in, deployments
+    // must be verified and nothing may be forced
+    #[serde(default)]
+    strict: bool,
+}
+
+impl EnvironmentProfile {
+    fn has_signer(&self) -> bool {
+        self.mnemonic.is_some() || self.private_key.is_some() || self.keystore.is_some()
+    }
+}
+
+// Apply the environment profile selected with `--profile` or
+// `DEPLOY_PROFILE`, then the network profile selected with `--network`,
+// `NETWORK` or the environment profile, by setting the environment variables
+// the CLI flags fall back to. Flags given on the command line and variables
+// already set take precedence over the profiles, the environment profile
+// over the network profile. `secret:<name>` values are set as they are, for
+// the flags to decrypt them from the secrets store.
+pub fn apply_network_profile() -> Result<()> {
+    let environment = match selected_profile() {
+        Some(name) => Some(load_environment_profile(&name)?),
+        None => None,
+    };
+    // Its signer replaces that of the network profile as a whole
+    let mut skip_signer = false;
+    if let Some(environment) = environment {
+        skip_signer = environment.has_signer();
+        let variables = [
+            ("NETWORK", environment.network),
+            (
+                "CONFIRMATIONS",
+                environment.confirmations.map(|n| n.to_string()),
+            ),
+            (
+                "VERIFY",
+                environment.verify.map(|verify| verify.to_string()),
+            ),
+            ("ETHERSCAN_API_KEY", environment.etherscan_api_key),
+            ("MNEMONIC", environment.mnemonic),
+            ("DERIVATION_PATH", environment.derivation_path),
+            (
+                "ACCOUNT_INDEX",
+                environment.account_index.map(|n| n.to_string()),
+            ),
+            ("PRIVATE_KEY", environment.private_key),
+            (
+                "KEYSTORE",
+                environment.keystore.map(|path| path.display().to_string()),
+            ),
+            ("KEYSTORE_PASSWORD", environment.keystore_password),
+        ];
+        set_defaults(variables)?;
+    }
+
+    let Some(network) = selected_network() else {
+        return Ok(());
+    };
+    let mut profile = load_profile(&network)?;
+    if skip_signer {
+        profile.mnemonic = None;
+        profile.derivation_path = None;
+        profile.account_index = None;
+        profile.private_key = None;
+        profile.keystore = None;
+        profile.keystore_password = None;
+    }
+
+    let variables = [
+        ("RPC_URL", profile.rpc_url),
+        ("WS_URL", profile.ws_url),
+        ("CHAIN", profile.chain_id.map(|id| id.to_string())),
+        ("ETHERSCAN_API_KEY", profile.etherscan_api_key),
+        (
+            "CONFIRMATIONS",
+            profile.confirmations.map(|n| n.to_string()),
+        ),
+        ("RPC_RETRIES", profile.rpc_retries.map(|n| n.to_string())),
+        (
+            "RPC_RETRY_BACKOFF_MS",
+            profile.rpc_retry_backoff_ms.map(|ms| ms.to_string()),
+        ),
+        (
+            "RPC_RETRY_BUDGET",
+            profile.rpc_retry_budget.map(|n| n.to_string()),
+        ),
+        ("GAS_ORACLE", profile.gas_oracle),
+        (
+            "FEE_PERCENTILE",
+            profile.fee_percentile.map(|p| p.to_string()),
+        ),
+        ("GAS_ORACLE_KEY", profile.gas_oracle_key),
+        ("SAFE", profile.safe),
+        ("SAFE_SERVICE_URL", profile.safe_service_url),
+        ("MNEMONIC", profile.mnemonic),
+        ("DERIVATION_PATH", profile.derivation_path),
+        (
+            "ACCOUNT_INDEX",
+            profile.account_index.map(|n| n.to_string()),
+        ),
+        ("PRIVATE_KEY", profile.private_key),
+        (
+            "KEYSTORE",
+            profile.keystore.map(|path| path.display().to_string()),
+        ),
+        ("KEYSTORE_PASSWORD", profile.keystore_password),
+    ];
+    set_defaults(variables)
+}
+
+// Ask for the network profile to deploy to when an interactive `deploy`
+// names neither a network nor an endpoint, and none of the options of the
+// local Anvil chain either, which stays the default. The chosen one is
+// applied as `--network` is, and `true` returned for the command line to be
+// parsed again with its settings.
+pub fn ask_network(config: &Config) -> Result<bool> {
+    let Command::Deploy(deploy) = &config.command else {
+        return Ok(false);
+    };
+    let anvil = &deploy.anvil;
+    let local = anvil.port.is_some()
+        || anvil.accounts.is_some()
+        || anvil.balance.is_some()
+        || anvil.hardfork.is_some()
+        || anvil.keep_alive
+        || anvil.impersonate.is_some()
+        || anvil.attach.is_some()
+        || anvil.fork_url.is_some();
+    // `--json` is checked here as the output mode is not set yet
+    if config.network.is_some()
+        || deploy.rpc_url.is_some()
+        || !deploy.networks.is_empty()
+        || local
+        || config.json
+        || !prompt::interactive()
+    {
+        return Ok(false);
+    }
+    let networks = network_names()?;
+    if networks.is_empty() {
+        return Ok(false);
+    }
+    let Some(network) = prompt::network(&networks)? else {
+        return Ok(false);
+    };
+    std::env::set_var("NETWORK", network);
+    apply_network_profile()?;
+    Ok(true)
+}
+
+// Set the environment variables that are not set yet. Only the variables of
+// flags parsed with `parse_secret` may hold `secret:` references, the others
+// would be taken as they are.
+fn set_defaults<const N: usize>(variables: [(&str, Option<String>); N]) -> Result<()> {
+    for (name, value) in variables {
+        if let Some(value) = value {
+            if value.starts_with(SECRET_PREFIX) && !SECRET_VARIABLES.contains(&name) {
+                return Err(eyre!(
+                    "{} cannot be a secret in {CONFIG_FILE}, only {} can",
+                    name.to_lowercase(),
+                    SECRET_VARIABLES.map(str::to_lowercase).join(", ")
+                ));
+            }
+            if std::env::var_os(name).is_none() {
+                std::env::set_var(name, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Enforce a strict environment profile on the parsed command line: the
+// network is that of the profile, deployments are verified, nothing is
+// forced, and the profile name has to be typed in at a terminal before
+// anything is sent
+pub async fn check_environment_profile(config: &Config) -> Result<()> {
+    let Some(name) = &config.profile else {
+        return Ok(());
+    };
+    let profile = load_environment_profile(name)?;
+    if !profile.strict {
+        return Ok(());
+    }
+    let networks = match &config.command {
+        Command::Deploy(deploy) => deploy.networks.iter().chain(&config.network).collect(),
+        _ => config.network.iter().collect::<Vec<_>>(),
+    };
+    if let Some(expected) = &profile.network {
+        if let Some(network) = networks.into_iter().find(|network| *network != expected) {
+            return Err(eyre!(
+                "Profile {name} is strict and deploys to {expected}, not {network}"
+            ));
+        }
+    }
+
+    // The endpoint is that of the sending commands, `None` for `deploy
+    // --networks`, whose networks are checked by name above
+    let (sends, forced, endpoint) = match &config.command {
+        Command::Deploy(deploy) => {
+            let self_sent = !deploy.dry_run
+                && deploy.safe.safe.is_none()
+                && deploy.smart_account.smart_account.is_none();
+            if self_sent && !deploy.verify {
+                return Err(eyre!(
+                    "Profile {name} is strict, its deployments must be verified with --verify"
+                ));
+            }
+            (
+                !deploy.dry_run,
+                deploy.force_unlock.then_some("--force-unlock"),
+                deploy
+                    .networks
+                    .is_empty()
+                    .then_some(deploy.rpc_url.as_deref()),
+            )
+        }
+        Command::Upgrade(upgrade) => (
+            true,
+            match (upgrade.force, upgrade.force_unlock) {
+                (true, _) => Some("--force"),
+                (false, true) => Some("--force-unlock"),
+                (false, false) => None,
+            },
+            Some(Some(upgrade.rpc_url.as_str())),
+        ),
+        Command::Tx(tx) => match &tx.command {
+            TxCommand::Bump(replace) | TxCommand::Cancel(replace) => {
+                (true, None, Some(Some(replace.rpc_url.as_str())))
+            }
+            _ => (false, None, None),
+        },
+        Command::Send(send) => (true, None, Some(Some(send.rpc_url.as_str()))),
+        Command::Console(console) => (true, None, Some(Some(console.rpc_url.as_str()))),
+        Command::Broadcast(broadcast) => (true, None, Some(Some(broadcast.rpc_url.as_str()))),
+        Command::Fund(fund) => (true, None, Some(Some(fund.rpc_url.as_str()))),
+        _ => (false, None, None),
+    };
+    if let Some(flag) = forced {
+        return Err(eyre!("Profile {name} is strict, {flag} is not allowed"));
+    }
+    if !sends {
+        return Ok(());
+    }
+    if let (Some(network), Some(rpc_url)) = (&profile.network, endpoint) {
+        check_profile_endpoint(name, network, rpc_url).await?;
+    }
+    // `--json` is checked here as the output mode is not set yet
+    if config.json || !prompt::interactive() {
+        return Err(eyre!(
+            "Profile {name} is strict, its name must be typed in at a terminal to send \
+             transactions, which a --json or CI run cannot do"
+        ));
+    }
+    if prompt::ask(&format!("Profile {name} is strict, type its name to go on"))? != name {
+        return Err(eyre!("Aborted, the profile name was not typed in"));
+    }
+    Ok(())
+}
+
+// Check that a run of a strict profile sends to its network whatever the
+// flags say: the RPC URL is that of the network profile, and the node is on
+// its chain. The URLs are not printed, as they often hold API keys.
+async fn check_profile_endpoint(name: &str, network: &str, rpc_url: Option<&str>) -> Result<()> {
+    let profile = load_profile(network)?;
+    let Some(rpc_url) = rpc_url else {
+        return Err(eyre!(
+            "Profile {name} is strict and deploys to {network}, not to a local node"
+        ));
+    };
+    if profile.rpc_url.is_none() && profile.chain_id.is_none() {
+        return Err(eyre!(
+            "Profile {name} is strict, its network {network} needs an rpc_url or chain_id in \
+             {CONFIG_FILE} to check the RPC endpoint against"
+        ));
+    }
+    if let Some(expected) = profile.rpc_url {
+        if parse_secret(&expected).map_err(|e| eyre!(e))? != rpc_url {
+            return Err(eyre!(
+                "Profile {name} is strict and deploys to {network}, whose rpc_url in \
+                 {CONFIG_FILE} is not the RPC URL given"
+            ));
+        }
+    }
+    if let Some(expected) = profile.chain_id {
+        let chain_id = rpc::connect(rpc_url)?.get_chainid().await?.as_u64();
+        if chain_id != expected {
+            return Err(eyre!(
+                "Profile {name} is strict and deploys to {network} on chain {expected}, \
+                 but the RPC endpoint is on chain {chain_id}"
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Deploy settings of one network of `deploy --networks`: those of its
+// profile, and the flags for what the profile leaves out. Unlike with
+// `--network`, the profile wins over the flags, which every network of the
+// run shares. The RPC retry settings stay those of the flags.
+pub fn deploy_config_for(network: &str, config: &DeployConfig) -> Result<DeployConfig> {
+    let profile = load_profile(network)?;
+    let mut secrets = None;
+    let mut secret = |value: Option<String>| {
+        value
+            .map(|value| resolve_secret(value, &mut secrets))
+            .transpose()
+    };
+
+    let mut config = config.clone();
+    config.networks = vec![];
+    config.rpc_url = Some(
+        secret(profile.rpc_url)?
+            .with_context(|| format!("Network {network} has no rpc_url in {CONFIG_FILE}"))?,
+    );
+    config.ws_url = secret(profile.ws_url)?;
+    if let Some(chain_id) = profile.chain_id {
+        config.chain = Some(chains::chain_info(chain_id)?);
+    }
+    if let Some(api_key) = secret(profile.etherscan_api_key)? {
+        config.etherscan_api_key = Some(api_key);
+    }
+    if let Some(confirmations) = profile.confirmations {
+        config.confirmations = confirmations;
+    }
+    if let Some(gas_oracle) = profile.gas_oracle {
+        config.fees.gas_oracle = <GasOracleKind as clap::ValueEnum>::from_str(&gas_oracle, true)
+            .map_err(|e| eyre!("Invalid gas_oracle of network {network}: {e}"))?;
+    }
+    if let Some(fee_percentile) = profile.fee_percentile {
+        config.fees.fee_percentile = fee_percentile;
+    }
+    if let Some(gas_oracle_key) = secret(profile.gas_oracle_key)? {
+        config.fees.gas_oracle_key = Some(gas_oracle_key);
+    }
+    if let Some(safe) = profile.safe {
+        config.safe.safe = Some(
+            safe.parse()
+                .map_err(|e| eyre!("Invalid safe of network {network}: {e}"))?,
+        );
+    }
+    if let Some(safe_service_url) = profile.safe_service_url {
+        config.safe.safe_service_url = Some(safe_service_url);
+    }
+    // A signer of the profile replaces that of the flags as a whole, so a
+    // private key given as a flag does not win over the profile's mnemonic
+    if profile.mnemonic.is_some() || profile.private_key.is_some() || profile.keystore.is_some() {
+        config.signer = SignerConfig {
+            mnemonic: secret(profile.mnemonic)?,
+            derivation_path: profile.derivation_path,
+            account_index: profile.account_index.unwrap_or_default(),
+            private_key: secret(profile.private_key)?,
+            keystore: profile.keystore,
+            keystore_password: secret(profile.keystore_password)?,
+        };
+    }
+    Ok(config)
+}
+
+// Apply the settings of `solidity-deploy.toml` outside of network profiles,
+// which are defaults of the CLI flags just like the profile's
+pub fn apply_project_settings() -> Result<()> {
+    let config = load_project_config()?;
+    if let Some(contracts_dir) = config.contracts_dir {
+        if std::env::var_os("CONTRACTS_DIR").is_none() {
+            std::env::set_var("CONTRACTS_DIR", contracts_dir);
+        }
+    }
+    Ok(())
+}
+
+// Network named on the command line, which is parsed before clap so the
+// profile can provide the defaults of required flags such as `--rpc-url`
+fn selected_network() -> Option<String> {
+    flag_before_parsing("--network").or_else(|| std::env::var("NETWORK").ok())
+}
+
+// Environment profile named on the command line, parsed before clap too
+fn selected_profile() -> Option<String> {
+    flag_before_parsing("--profile").or_else(|| std::env::var("DEPLOY_PROFILE").ok())
+}
+
+// Read `solidity-deploy.toml`, which is optional unless a network is selected
+pub fn load_project_config() -> Result<ProjectConfig> {
+    let path = Path::new(CONFIG_FILE);
+    if !path.exists() {
+        return Ok(ProjectConfig::default());
+    }
+    let content = interpolate(&std::fs::read_to_string(path)?, path)?;
+    toml::from_str(&content).map_err(|e| eyre!("Invalid {CONFIG_FILE}: {}", e.to_string()))
+}
+
+// Names of the network profiles of the project
+pub fn network_names() -> Result<Vec<String>> {
+    Ok(load_project_config()?.networks.into_keys().collect())
+}
+
+// Chain id of the network profile of that name, if the project has one
+pub fn profile_chain_id(network: &str) -> Result<Option<u64>> {
+    Ok(load_project_config()?
+        .networks
+        .get(network)
+        .and_then(|profile| profile.chain_id))
+}
+
+fn load_environment_profile(name: &str) -> Result<EnvironmentProfile> {
+    if !Path::new(CONFIG_FILE).exists() {
+        return Err(eyre!("--profile {name} needs {CONFIG_FILE}"));
+    }
+    let mut config = load_project_config()?;
+    let known = config
+        .profiles
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    config.profiles.remove(name).with_context(|| {
+        format!("Profile {name} not found in {CONFIG_FILE}, known profiles: {known}")
+    })
+}
+
+fn load_profile(network: &str) -> Result<NetworkProfile> {
+    if !Path::new(CONFIG_FILE).exists() {
+        return Err(eyre!("--network {network} needs {CONFIG_FILE}"));
+    }
+    let mut config = load_project_config()?;
+    let known = config
+        .networks
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    config.networks.remove(network).with_context(|| {
+        format!("Network {network} not found in {CONFIG_FILE}, known networks: {known}")
+    })
+}
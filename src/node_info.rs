@@ -0,0 +1,166 @@
+use crate::chains::{self, ChainInfo};
+use crate::cli_config::{ChainInfoConfig, FeeConfig};
+use crate::rpc::{self, RpcProvider};
+use crate::units::format_gwei;
+use ethers::types::{BlockNumber, H256, U256};
+use ethers_providers::{Middleware, ProviderError, RpcError};
+use eyre::{eyre, ContextCompat, Result};
+
+// JSON-RPC error code of a method the node does not have
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// What a node tells about its chain and supports
+struct NodeInfo {
+    chain_id: u64,
+    client_version: Option<String>,
+    block_number: u64,
+    block_timestamp: U256,
+    // None on chains without EIP-1559
+    base_fee: Option<U256>,
+    gas_price: U256,
+    // `eth_maxPriorityFeePerGas`, which not every node has
+    priority_fee: Option<U256>,
+    debug_trace: bool,
+    parity_trace: bool,
+    // Outcome of connecting to `--ws-url`, None without one
+    websocket: Option<Result<(), String>>,
+}
+
+impl NodeInfo {
+    async fn query(provider: &RpcProvider, ws_url: Option<&str>) -> Result<Self> {
+        let chain_id = provider.get_chainid().await?.as_u64();
+        let block = provider
+            .get_block(BlockNumber::Latest)
+            .await?
+            .context("Node returned no latest block")?;
+        // A hash no node knows: nodes with the method answer it is not found,
+        // nodes without it that the method is
+        let unknown = [H256::zero()];
+        let websocket = match ws_url {
+            Some(ws_url) => Some(check_websocket(ws_url, chain_id).await),
+            None => None,
+        };
+        Ok(NodeInfo {
+            chain_id,
+            client_version: provider.client_version().await.ok(),
+            block_number: block.number.map_or(0, |number| number.as_u64()),
+            block_timestamp: block.timestamp,
+            base_fee: block.base_fee_per_gas,
+            gas_price: provider.get_gas_price().await?,
+            priority_fee: provider
+                .request::<_, U256>("eth_maxPriorityFeePerGas", ())
+                .await
+                .ok(),
+            debug_trace: supports(provider, "debug_traceTransaction", unknown).await,
+            parity_trace: supports(provider, "trace_transaction", unknown).await,
+            websocket,
+        })
+    }
+
+    fn print(&self, chain: &ChainInfo) {
+        let yes_no = |supported: bool| if supported { "yes" } else { "no" };
+        println!("{}", "=".repeat(80));
+        println!("CHAIN {} ({})", chain.name, self.chain_id);
+        println!(
+            "client:          {}",
+            self.client_version.as_deref().unwrap_or("unknown")
+        );
+        println!(
+            "latest block:    {} at {}",
+            self.block_number, self.block_timestamp
+        );
+        println!(
+            "base fee:        {}",
+            self.base_fee.map_or("-".to_owned(), format_gwei)
+        );
+        println!("gas price:       {}", format_gwei(self.gas_price));
+        println!(
+            "priority fee:    {}",
+            self.priority_fee.map_or("-".to_owned(), format_gwei)
+        );
+        println!("EIP-1559:        {}", yes_no(self.base_fee.is_some()));
+        println!(
+            "tracing:         debug_traceTransaction {}, trace_transaction {}",
+            yes_no(self.debug_trace),
+            yes_no(self.parity_trace)
+        );
+        match &self.websocket {
+            Some(Ok(())) => println!("websocket:       yes"),
+            Some(Err(e)) => println!("websocket:       no, {e}"),
+            None => println!("websocket:       not checked, pass --ws-url"),
+        }
+    }
+}
+
+// Entry point of the `chain-info` subcommand
+pub async fn chain_info(config: ChainInfoConfig) -> Result<()> {
+    let provider = rpc::connect(&config.rpc_url)?;
+    let info = NodeInfo::query(&provider, config.ws_url.as_deref()).await?;
+    info.print(&chains::chain_info(info.chain_id)?);
+    Ok(())
+}
+
+// Pre-flight check of `deploy --preflight`: print what `chain-info` reports
+// and fail before anything is compiled or sent when the node cannot serve the
+// deployment, e.g. EIP-1559 fees on a chain without a base fee
+pub async fn preflight(
+    provider: &RpcProvider,
+    ws_url: Option<&str>,
+    chain: &ChainInfo,
+    fees: &FeeConfig,
+) -> Result<()> {
+    let info = NodeInfo::query(provider, ws_url).await?;
+    info.print(chain);
+    if let Some(Err(e)) = &info.websocket {
+        return Err(eyre!("Pre-flight check failed, websocket endpoint: {e}"));
+    }
+    let legacy = fees.legacy || !chain.eip1559;
+    if !legacy && info.base_fee.is_none() {
+        return Err(eyre!(
+            "Pre-flight check failed, the node reports no base fee, pass --legacy"
+        ));
+    }
+    Ok(())
+}
+
+async fn check_websocket(ws_url: &str, chain_id: u64) -> Result<(), String> {
+    let provider = rpc::connect_ws(ws_url).await.map_err(|e| e.to_string())?;
+    let ws_chain_id = provider
+        .get_chainid()
+        .await
+        .map_err(|e| e.to_string())?
+        .as_u64();
+    if ws_chain_id != chain_id {
+        return Err(format!("it serves chain {ws_chain_id}, not {chain_id}"));
+    }
+    Ok(())
+}
+
+// Whether the node has an RPC method, judged from its answer to a call of it
+async fn supports(provider: &RpcProvider, method: &str, params: [H256; 1]) -> bool {
+    match provider
+        .request::<_, serde_json::Value>(method, params)
+        .await
+    {
+        Ok(_) => true,
+        Err(e) => !method_missing(&e),
+    }
+}
+
+fn method_missing(error: &ProviderError) -> bool {
+    let Some(response) = error.as_error_response() else {
+        // Not even an error response, e.g. a provider rejecting the method
+        return true;
+    };
+    let message = response.message.to_lowercase();
+    response.code == METHOD_NOT_FOUND
+        || (message.contains("method")
+            && [
+                "not found",
+                "not supported",
+                "does not exist",
+                "not available",
+            ]
+            .iter()
+            .any(|reason| message.contains(reason)))
+}
@@ -0,0 +1,63 @@
+use ethers::types::{Address, BlockNumber, U256};
+use ethers_providers::Middleware;
+use eyre::{eyre, Result};
+
+// Pre-flight check of the nonces of the sending account, since a transaction
+// stuck behind others only shows up as a deployment that never confirms.
+// Warns about pending transactions and, when the node exposes its txpool,
+// queued ones waiting for a nonce gap to be filled. An explicit nonce must
+// not be used already and is warned about when it leaves a gap itself.
+pub async fn check_nonce<M: Middleware>(
+    provider: &M,
+    address: Address,
+    nonce: Option<U256>,
+) -> Result<()>
+where
+    M::Error: 'static,
+{
+    let mined = provider
+        .get_transaction_count(address, Some(BlockNumber::Latest.into()))
+        .await?;
+    let pending = provider
+        .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+        .await?;
+    println!("nonce: {mined} mined, {pending} including pending transactions");
+    if pending > mined {
+        println!(
+            "WARNING: {address:?} has {} pending transaction(s), new ones are mined after them",
+            pending - mined
+        );
+    }
+    // Not every node exposes its txpool, the check is skipped on those
+    if let Ok(txpool) = provider.txpool_content().await {
+        let queued = txpool
+            .queued
+            .get(&address)
+            .map(|queued| queued.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        if !queued.is_empty() {
+            println!(
+                "WARNING: {address:?} has transaction(s) with nonce {} queued behind a nonce gap at {pending}",
+                queued.join(", ")
+            );
+        }
+    }
+
+    match nonce {
+        Some(nonce) if nonce < mined => Err(eyre!(
+            "Nonce {nonce} of {address:?} is already used, the next free one is {pending}"
+        )),
+        Some(nonce) if nonce > pending => {
+            println!(
+                "WARNING: nonce {nonce} leaves a gap, the transactions wait until nonces {pending} to {} are used",
+                nonce - 1
+            );
+            Ok(())
+        }
+        Some(nonce) if nonce < pending => {
+            println!("WARNING: nonce {nonce} replaces a pending transaction");
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
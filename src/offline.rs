@@ -0,0 +1,214 @@
+use crate::artifacts::ContractSource;
+use crate::audit;
+use crate::cli_config::{BroadcastConfig, SignConfig};
+use crate::compiler::{build_project, compile, contracts_path};
+use crate::deployer::{deploy_code, encode_constructor_args, find_contract};
+use crate::error::DeployError;
+use crate::interact::print_receipt;
+use crate::lock::DeployLock;
+use crate::manifest::{CompilerInfo, Deployment};
+use crate::revert::revert_error;
+use crate::rpc;
+use crate::signer::wallet_from_config;
+use ethers::abi::Abi;
+use ethers::signers::Signer;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{
+    Address, BlockNumber, Bytes, Eip1559TransactionRequest, TransactionRequest, H256, U256,
+};
+use ethers::utils::{get_contract_address, keccak256, rlp};
+use ethers_providers::Middleware;
+use ethers_solc::artifacts::StorageLayout;
+use eyre::{eyre, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// Deployment transaction signed by `sign`, with what `broadcast` needs to
+// record the deployment once it is mined
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedDeployment {
+    pub contract_name: String,
+    pub chain_id: u64,
+    pub from: Address,
+    pub nonce: U256,
+    // Address of the contract once deployed, from the sender and nonce
+    pub address: Address,
+    pub transaction_hash: H256,
+    pub raw_transaction: Bytes,
+    pub abi: Abi,
+    pub constructor_args: Vec<String>,
+    pub encoded_constructor_args: Bytes,
+    pub compiler: CompilerInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_layout: Option<StorageLayout>,
+}
+
+// Entry point of the `sign` subcommand: build and sign a deployment without
+// any node, the nonce, gas and fees being given on the command line
+pub async fn sign(config: SignConfig) -> Result<()> {
+    let wallet = wallet_from_config(&config.signer)?
+        .context("--private-key, --keystore or --mnemonic is required to sign")?
+        .with_chain_id(config.chain.chain_id);
+    let project = compile(contracts_path(), None, &config.compiler).await?;
+    let (abi, bytecode) = find_contract(&project, &config.contract_name)?;
+    let constructor_args =
+        encode_constructor_args(&abi, &config.contract_name, &config.constructor_args)?;
+    let data = deploy_code(&abi, &bytecode, &constructor_args)?;
+
+    let mut tx: TypedTransaction = match (
+        config.gas_price,
+        config.max_fee_per_gas,
+        config.max_priority_fee_per_gas,
+    ) {
+        (Some(gas_price), _, _) => TransactionRequest::new().gas_price(gas_price).into(),
+        (None, Some(max_fee), Some(priority_fee)) => Eip1559TransactionRequest::new()
+            .max_fee_per_gas(max_fee)
+            .max_priority_fee_per_gas(priority_fee)
+            .into(),
+        _ => {
+            return Err(eyre!(
+            "Either --gas-price or --max-fee-per-gas and --max-priority-fee-per-gas are required"
+        ))
+        }
+    };
+    tx.set_from(wallet.address())
+        .set_chain_id(config.chain.chain_id)
+        .set_nonce(config.nonce)
+        .set_gas(config.gas_limit)
+        .set_value(config.value.unwrap_or_default())
+        .set_data(data);
+    let signature = wallet.sign_transaction(&tx).await?;
+    let raw_transaction = tx.rlp_signed(&signature);
+
+    let settings = build_project(contracts_path(), None, &config.compiler)?
+        .solc_config
+        .settings;
+    let contracts = ContractSource::Compiled(project);
+    let signed = SignedDeployment {
+        contract_name: config.contract_name.clone(),
+        chain_id: config.chain.chain_id,
+        from: wallet.address(),
+        nonce: config.nonce,
+        address: get_contract_address(wallet.address(), config.nonce),
+        transaction_hash: H256(keccak256(&raw_transaction)),
+        raw_transaction,
+        abi,
+        constructor_args: config.constructor_args.clone(),
+        encoded_constructor_args: ethers::abi::encode(&constructor_args).into(),
+        compiler: contracts.compiler(&config.contract_name, &settings)?,
+        storage_layout: contracts.storage_layout(&config.contract_name),
+    };
+
+    match (&config.out, config.raw) {
+        (Some(out), _) => {
+            if let Some(dir) = out.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(out, serde_json::to_string_pretty(&signed)?)?;
+            println!(
+                "Deployment of {} on {} signed by {:?} with nonce {}",
+                signed.contract_name, config.chain.name, signed.from, signed.nonce
+            );
+            println!("Contract address once mined: {:?}", signed.address);
+            println!("Signed transaction written to {}", out.display());
+        }
+        (None, true) => println!("{}", signed.raw_transaction),
+        (None, false) => println!("{}", serde_json::to_string_pretty(&signed)?),
+    }
+    Ok(())
+}
+
+// Entry point of the `broadcast` subcommand: send a transaction signed by
+// `sign` and, when it came with its contract, record the deployment
+pub async fn broadcast(config: BroadcastConfig) -> Result<()> {
+    let (raw_transaction, signed) = read_signed(&config.file)?;
+    let (mut tx, signature) = TypedTransaction::decode_signed(&rlp::Rlp::new(&raw_transaction))
+        .map_err(|e| {
+            eyre!(
+                "Invalid signed transaction in {}: {e}",
+                config.file.display()
+            )
+        })?;
+    let from = signature.recover(tx.sighash())?;
+    tx.set_from(from);
+
+    let provider = rpc::connect(&config.rpc_url)?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+    if let Some(signed_for) = tx
+        .chain_id()
+        .filter(|signed_for| signed_for.as_u64() != chain_id)
+    {
+        return Err(eyre!(
+            "The transaction is signed for chain {signed_for} but the RPC endpoint is on chain {chain_id}"
+        ));
+    }
+    let _lock = DeployLock::acquire(chain_id, "broadcast", config.force_unlock)?;
+    // A used nonce would fail, a later one would wait for the transactions
+    // before it forever
+    let nonce = provider
+        .get_transaction_count(from, Some(BlockNumber::Pending.into()))
+        .await?;
+    if tx.nonce() != Some(&nonce) {
+        return Err(eyre!(
+            "The transaction has nonce {} but the next nonce of {from:?} is {nonce}, sign it again",
+            tx.nonce().copied().unwrap_or_default()
+        ));
+    }
+
+    let what = match &signed {
+        Some(signed) => format!("Deployment of {}", signed.contract_name),
+        None => "Broadcast".to_owned(),
+    };
+    let pending_tx = provider
+        .send_raw_transaction(raw_transaction)
+        .await
+        .map_err(|e| revert_error(e, signed.as_ref().map(|signed| &signed.abi), &what))?;
+    let transaction = pending_tx.tx_hash();
+    audit::record(&provider, "broadcast", &tx, transaction).await;
+    println!("Transaction hash: {transaction:?}");
+    let receipt = rpc::confirm(&provider, pending_tx, config.confirmations)
+        .await?
+        .ok_or(DeployError::Dropped { transaction })?;
+    print_receipt(&receipt);
+    if receipt.status.is_some_and(|status| status.is_zero()) {
+        return Err(eyre!("{what} reverted in transaction {transaction:?}"));
+    }
+
+    if let Some(signed) = signed {
+        let deployment = Deployment {
+            name: signed.contract_name.clone(),
+            contract_name: signed.contract_name,
+            chain_id,
+            address: receipt.contract_address.unwrap_or(signed.address),
+            transaction_hash: receipt.transaction_hash,
+            block_number: receipt.block_number.map(|number| number.as_u64()),
+            deployer: from,
+            abi: signed.abi,
+            constructor_args: signed.constructor_args,
+            encoded_constructor_args: signed.encoded_constructor_args,
+            compiler: signed.compiler,
+            salt: None,
+            proxy: None,
+            storage_layout: signed.storage_layout,
+            libraries: BTreeMap::new(),
+        };
+        println!("Contract deployed at {:?}", deployment.address);
+        println!("Deployment manifest: {}", deployment.save()?.display());
+    }
+    Ok(())
+}
+
+// Raw transaction of a file written by `sign`, or by `sign --raw`, which
+// has no deployment to record
+fn read_signed(path: &Path) -> Result<(Bytes, Option<SignedDeployment>)> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| eyre!("Failed to read {}: {e}", path.display()))?;
+    if let Ok(raw_transaction) = content.trim().parse::<Bytes>() {
+        return Ok((raw_transaction, None));
+    }
+    let signed: SignedDeployment = serde_json::from_str(&content)
+        .map_err(|e| eyre!("Invalid signed transaction in {}: {e}", path.display()))?;
+    Ok((signed.raw_transaction.clone(), Some(signed)))
+}
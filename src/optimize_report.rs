@@ -0,0 +1,219 @@
+use crate::calls::{load_calls, PlannedCall};
+use crate::cli_config::{CompilerConfig, OptimizeReportConfig};
+use crate::compiler::{compile, contracts_path};
+use crate::deployer::{encode_constructor_args, find_contract};
+use crate::revert::revert_error;
+use ethers::abi::Token;
+use ethers::contract::ContractFactory;
+use ethers::middleware::SignerMiddleware;
+use ethers::prelude::{LocalWallet, Signer};
+use ethers::types::BlockNumber;
+use ethers::utils::Anvil;
+use ethers_providers::{Http, Middleware, Provider};
+use eyre::{eyre, ContextCompat, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Optimizer settings one build of the report is compiled with
+#[derive(Debug, Clone, Copy)]
+struct Variant {
+    // None with the optimizer off
+    runs: Option<usize>,
+    via_ir: bool,
+}
+
+impl Variant {
+    fn label(&self) -> String {
+        let optimizer = match self.runs {
+            Some(runs) => format!("runs {runs}"),
+            None => "no optimizer".to_owned(),
+        };
+        match self.via_ir {
+            true => format!("{optimizer}, via IR"),
+            false => optimizer,
+        }
+    }
+
+    fn compiler(&self, base: &CompilerConfig) -> CompilerConfig {
+        CompilerConfig {
+            optimize: self.runs.is_some(),
+            optimizer_runs: self.runs,
+            via_ir: self.via_ir,
+            // Builds of the report neither read nor replace the cached ones
+            no_cache: true,
+            ..base.clone()
+        }
+    }
+}
+
+// Sizes and gas of the contract built with one variant
+struct VariantResult {
+    variant: Variant,
+    init_size: usize,
+    runtime_size: usize,
+    deployment_gas: u64,
+    // Gas used by every call, by method
+    calls: BTreeMap<String, Vec<u64>>,
+}
+
+// Entry point of `optimize-report`: compile the contract with every optimizer
+// runs value, with and without the IR pipeline, deploy each build to a
+// scratch Anvil chain and compare their bytecode sizes and gas
+pub async fn optimize_report(config: OptimizeReportConfig) -> Result<()> {
+    let calls = match &config.calls {
+        Some(path) => load_calls(path)?,
+        None => vec![],
+    };
+    let mut variants = vec![Variant {
+        runs: None,
+        via_ir: false,
+    }];
+    for via_ir in [false, true] {
+        if via_ir && config.no_via_ir {
+            continue;
+        }
+        variants.extend(config.runs.iter().map(|&runs| Variant {
+            runs: Some(runs),
+            via_ir,
+        }));
+    }
+
+    let anvil = Anvil::new().spawn();
+    let wallet: LocalWallet = anvil.keys()[0].clone().into();
+    let provider =
+        Provider::<Http>::try_from(anvil.endpoint())?.interval(Duration::from_millis(10));
+    let client = Arc::new(SignerMiddleware::new(
+        provider,
+        wallet.with_chain_id(anvil.chain_id()),
+    ));
+
+    let mut results = vec![];
+    for variant in variants {
+        println!("Building {} with {}", config.contract_name, variant.label());
+        let result = measure(&config, variant, &calls, client.clone()).await?;
+        results.push(result);
+    }
+    print_report(&config.contract_name, &results);
+    Ok(())
+}
+
+async fn measure<M: Middleware + 'static>(
+    config: &OptimizeReportConfig,
+    variant: Variant,
+    calls: &[PlannedCall],
+    client: Arc<M>,
+) -> Result<VariantResult> {
+    let project = compile(contracts_path(), None, &variant.compiler(&config.compiler)).await?;
+    let (abi, bytecode) = find_contract(&project, &config.contract_name)?;
+    let constructor_args: Vec<Token> =
+        encode_constructor_args(&abi, &config.contract_name, &config.constructor_args)?;
+    let init_size = bytecode.len();
+
+    let what = format!(
+        "Deployment of {} with {}",
+        config.contract_name,
+        variant.label()
+    );
+    let (contract, receipt) = ContractFactory::new(abi.clone(), bytecode, client.clone())
+        .deploy_tokens(constructor_args)?
+        .send_with_receipt()
+        .await
+        .map_err(|e| revert_error(e, Some(&abi), &what))?;
+    let address = contract.address();
+    let runtime_size = client
+        .get_code(address, Some(BlockNumber::Latest.into()))
+        .await
+        .map_err(|e| eyre!("Failed to read the code of {address:?}: {e}"))?
+        .len();
+
+    // Every build gets the same calls, on its own deployment
+    let mut gas = BTreeMap::<String, Vec<u64>>::new();
+    for call in calls {
+        let tx = call.to_transaction(&abi, address)?;
+        let receipt = client
+            .send_transaction(tx, None)
+            .await
+            .map_err(|e| revert_error(e, Some(&abi), &format!("Call {}", call.method)))?
+            .await?
+            .context("Call dropped from the mempool")?;
+        if let Some(used) = receipt.gas_used {
+            gas.entry(call.method.clone())
+                .or_default()
+                .push(used.as_u64());
+        }
+    }
+
+    Ok(VariantResult {
+        variant,
+        init_size,
+        runtime_size,
+        deployment_gas: receipt.gas_used.map_or(0, |gas| gas.as_u64()),
+        calls: gas,
+    })
+}
+
+fn print_report(contract_name: &str, results: &[VariantResult]) {
+    println!("{}", "=".repeat(80));
+    println!("OPTIMIZER REPORT {contract_name}");
+    println!(
+        "{:<24} {:>12} {:>12} {:>14}",
+        "SETTINGS", "INIT (bytes)", "RUNTIME", "DEPLOY GAS"
+    );
+    let smallest = results.iter().map(|result| result.runtime_size).min();
+    let cheapest = results.iter().map(|result| result.deployment_gas).min();
+    for result in results {
+        let mut marks = vec![];
+        if Some(result.runtime_size) == smallest {
+            marks.push("smallest");
+        }
+        if Some(result.deployment_gas) == cheapest {
+            marks.push("cheapest deployment");
+        }
+        println!(
+            "{:<24} {:>12} {:>12} {:>14}  {}",
+            result.variant.label(),
+            result.init_size,
+            result.runtime_size,
+            result.deployment_gas,
+            marks.join(", ")
+        );
+    }
+
+    let methods = results
+        .iter()
+        .flat_map(|result| result.calls.keys())
+        .collect::<BTreeSet<_>>();
+    if methods.is_empty() {
+        return;
+    }
+    // Average gas of each function per build, the cheapest build marked
+    println!("{}", "=".repeat(80));
+    println!("{:<24} {:<32} {:>12}", "SETTINGS", "FUNCTION", "AVG GAS");
+    for method in methods {
+        let averages = results
+            .iter()
+            .map(|result| {
+                result
+                    .calls
+                    .get(method)
+                    .filter(|gas| !gas.is_empty())
+                    .map(|gas| gas.iter().sum::<u64>() / gas.len() as u64)
+            })
+            .collect::<Vec<_>>();
+        let cheapest = averages.iter().flatten().min().copied();
+        for (result, average) in results.iter().zip(averages) {
+            println!(
+                "{:<24} {:<32} {:>12}{}",
+                result.variant.label(),
+                method,
+                average.map_or("-".to_owned(), |gas| gas.to_string()),
+                if average.is_some() && average == cheapest {
+                    "  cheapest"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+}
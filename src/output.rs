@@ -0,0 +1,73 @@
+use ethers::types::{Address, TransactionReceipt, H256, U256};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Whether `--json` was given, set once the command line is parsed
+static JSON: AtomicBool = AtomicBool::new(false);
+
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+// In `--json` mode the human-oriented output is suppressed and stdout only
+// carries the events of the run
+pub fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+// Progress of a run, printed as one JSON object per line in `--json` mode,
+// e.g. `{"event":"deployed","name":"Greeter",...}`
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    Compiled {
+        contracts: Vec<CompiledContract>,
+    },
+    Deploying {
+        name: &'a str,
+        contract_name: &'a str,
+    },
+    Deployed {
+        name: &'a str,
+        contract_name: &'a str,
+        chain_id: u64,
+        address: Address,
+        transaction_hash: H256,
+        block_number: Option<u64>,
+    },
+    TxConfirmed {
+        transaction_hash: H256,
+        block_number: Option<u64>,
+        gas_used: Option<U256>,
+        success: bool,
+    },
+}
+
+#[derive(Serialize, Debug)]
+pub struct CompiledContract {
+    pub name: String,
+    pub init_size: usize,
+    pub runtime_size: usize,
+}
+
+impl Event<'_> {
+    pub fn tx_confirmed(receipt: &TransactionReceipt) -> Self {
+        Event::TxConfirmed {
+            transaction_hash: receipt.transaction_hash,
+            block_number: receipt.block_number.map(|number| number.as_u64()),
+            gas_used: receipt.gas_used,
+            success: !receipt.status.is_some_and(|status| status.is_zero()),
+        }
+    }
+}
+
+// Print an event on stdout, only in `--json` mode
+pub fn emit(event: Event) {
+    if !json() {
+        return;
+    }
+    match serde_json::to_string(&event) {
+        Ok(line) => std::println!("{line}"),
+        Err(e) => eprintln!("Failed to serialize {event:?}: {e}"),
+    }
+}
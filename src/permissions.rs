@@ -0,0 +1,315 @@
+use crate::calls::{execute_calls, PlannedCall};
+use crate::deployer::DeployContext;
+use crate::multicall::BatchReader;
+use ethers::abi::{parse_abi, Abi, Token};
+use ethers::types::{Address, Bytes, H256, U256};
+use ethers::utils::keccak256;
+use eyre::{eyre, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// Roles and ownership a plan contract ends up with, from the `permissions`
+// section of a plan:
+//
+// [permissions.Token]
+// owner = "@Timelock"
+// grant = { MINTER_ROLE = ["@Minter"] }
+// renounce = ["DEFAULT_ADMIN_ROLE"]
+//
+// Roles are granted first, then ownership is transferred and the deployer
+// renounces its roles last, so it keeps the rights the other steps need.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Permissions {
+    // New owner of an Ownable contract
+    #[serde(default)]
+    pub owner: Option<String>,
+    // Accounts each AccessControl role is granted to, roles given by name,
+    // e.g. `MINTER_ROLE`, or as a bytes32 hash
+    #[serde(default)]
+    pub grant: BTreeMap<String, Vec<String>>,
+    // Roles the deployer gives up
+    #[serde(default)]
+    pub renounce: Vec<String>,
+}
+
+impl Permissions {
+    // Values that may refer to other contracts of the plan
+    pub fn accounts(&self) -> impl Iterator<Item = &String> {
+        self.owner.iter().chain(self.grant.values().flatten())
+    }
+
+    // The permissions with every account passed through `resolve`
+    pub fn resolve(&self, resolve: impl Fn(&str) -> Result<String>) -> Result<Self> {
+        Ok(Permissions {
+            owner: self.owner.as_deref().map(&resolve).transpose()?,
+            grant: self
+                .grant
+                .iter()
+                .map(|(role, accounts)| {
+                    let accounts = accounts
+                        .iter()
+                        .map(|account| resolve(account))
+                        .collect::<Result<Vec<_>>>()?;
+                    Ok((role.clone(), accounts))
+                })
+                .collect::<Result<_>>()?,
+            renounce: self.renounce.clone(),
+        })
+    }
+
+    // Roles the permissions mention, in the order they are reported
+    fn roles(&self) -> Vec<&str> {
+        let mut roles: Vec<&str> = self.grant.keys().map(String::as_str).collect();
+        for role in &self.renounce {
+            if !roles.contains(&role.as_str()) {
+                roles.push(role);
+            }
+        }
+        roles
+    }
+}
+
+fn permissions_abi() -> Result<Abi> {
+    Ok(parse_abi(&[
+        "function owner() view returns (address)",
+        "function pendingOwner() view returns (address)",
+        "function transferOwnership(address)",
+        "function hasRole(bytes32,address) view returns (bool)",
+        "function grantRole(bytes32,address)",
+        "function renounceRole(bytes32,address)",
+        "function getRoleMemberCount(bytes32) view returns (uint256)",
+        "function getRoleMember(bytes32,uint256) view returns (address)",
+    ])?)
+}
+
+// Role id of a role name, as OpenZeppelin's AccessControl derives it
+fn role_id(role: &str) -> Result<H256> {
+    if role.starts_with("0x") {
+        return role
+            .parse()
+            .map_err(|e| eyre!("Invalid role {role:?}: {e}"));
+    }
+    match role {
+        "DEFAULT_ADMIN_ROLE" => Ok(H256::zero()),
+        _ => Ok(keccak256(role.as_bytes()).into()),
+    }
+}
+
+fn parse_account(account: &str) -> Result<Address> {
+    account
+        .parse()
+        .map_err(|e| eyre!("Invalid account {account:?} in permissions: {e}"))
+}
+
+fn permission_call(method: &str, args: Vec<String>) -> PlannedCall {
+    PlannedCall {
+        method: method.to_owned(),
+        args,
+        value: None,
+        expect: None,
+        timelock: None,
+    }
+}
+
+// Apply the permissions of the contract deployed as `name` at `address`, check
+// they hold on-chain and report who ends up with the roles
+pub async fn apply_permissions(
+    context: &mut DeployContext,
+    name: &str,
+    address: Address,
+    permissions: &Permissions,
+) -> Result<()> {
+    println!("{}", "=".repeat(80));
+    println!("PERMISSIONS {name}");
+    let abi = permissions_abi()?;
+    let deployer = context.sender();
+
+    // Full signatures keep the journal steps apart from post-deploy calls
+    let mut calls = vec![];
+    for (role, accounts) in &permissions.grant {
+        let id = role_id(role)?;
+        for account in accounts {
+            println!("GRANT {role} to {account}");
+            calls.push(permission_call(
+                "grantRole(bytes32,address)",
+                vec![format!("{id:?}"), account.clone()],
+            ));
+        }
+    }
+    if let Some(owner) = &permissions.owner {
+        println!("TRANSFER ownership to {owner}");
+        calls.push(permission_call(
+            "transferOwnership(address)",
+            vec![owner.clone()],
+        ));
+    }
+    for role in &permissions.renounce {
+        println!("RENOUNCE {role} of the deployer {deployer:?}");
+        calls.push(permission_call(
+            "renounceRole(bytes32,address)",
+            vec![format!("{:?}", role_id(role)?), format!("{deployer:?}")],
+        ));
+    }
+    execute_calls(
+        context.client.as_ref(),
+        &abi,
+        address,
+        &calls,
+        context.from,
+        Some(&context.fees),
+        Some(&mut context.journal),
+        context.confirmations,
+        context.nonce.as_mut(),
+        context.fee_bump.as_ref(),
+    )
+    .await?;
+
+    verify_and_report(context, &abi, address, permissions, deployer).await
+}
+
+async fn verify_and_report(
+    context: &DeployContext,
+    abi: &Abi,
+    address: Address,
+    permissions: &Permissions,
+    deployer: Address,
+) -> Result<()> {
+    let client = context.client.as_ref();
+    let encode = |method: &str, tokens: &[Token]| -> Result<Bytes> {
+        Ok(abi.function(method)?.encode_input(tokens)?.into())
+    };
+    let decode = |method: &str, output: &Result<Bytes, Bytes>| -> Option<Token> {
+        let output = output.as_ref().ok()?;
+        abi.function(method)
+            .ok()?
+            .decode_output(output)
+            .ok()?
+            .into_iter()
+            .next()
+    };
+    let role_token =
+        |role: &str| -> Result<Token> { Ok(Token::FixedBytes(role_id(role)?.as_bytes().to_vec())) };
+
+    // Accounts whose roles are checked: the grantees and the deployer
+    let mut accounts: Vec<Address> = permissions
+        .grant
+        .values()
+        .flatten()
+        .map(|account| parse_account(account))
+        .collect::<Result<_>>()?;
+    accounts.push(deployer);
+    accounts.sort();
+    accounts.dedup();
+
+    let roles = permissions.roles();
+    let mut reader = BatchReader::new(client);
+    let owner_read = reader.add(address, encode("owner", &[])?);
+    let pending_owner_read = reader.add(address, encode("pendingOwner", &[])?);
+    let mut count_reads = BTreeMap::new();
+    let mut holder_reads = BTreeMap::new();
+    for role in &roles {
+        count_reads.insert(
+            *role,
+            reader.add(address, encode("getRoleMemberCount", &[role_token(role)?])?),
+        );
+        for account in &accounts {
+            let read = reader.add(
+                address,
+                encode("hasRole", &[role_token(role)?, Token::Address(*account)])?,
+            );
+            holder_reads.insert((*role, *account), read);
+        }
+    }
+    let results = reader.read().await?;
+    let has_role = |role: &str, account: Address| {
+        holder_reads
+            .get(&(role, account))
+            .and_then(|read| decode("hasRole", &results[*read]))
+            .and_then(Token::into_bool)
+            .unwrap_or(false)
+    };
+
+    let mut problems = vec![];
+    let owner = decode("owner", &results[owner_read]).and_then(Token::into_address);
+    let pending_owner =
+        decode("pendingOwner", &results[pending_owner_read]).and_then(Token::into_address);
+    if let Some(expected) = &permissions.owner {
+        let expected = parse_account(expected)?;
+        if pending_owner == Some(expected) && owner != Some(expected) {
+            println!("Ownership is pending acceptance by {expected:?}");
+        } else if owner != Some(expected) {
+            problems.push(format!("owner is {owner:?}, expected {expected:?}"));
+        }
+    }
+    for (role, grantees) in &permissions.grant {
+        for grantee in grantees {
+            if !has_role(role, parse_account(grantee)?) {
+                problems.push(format!("{grantee} does not hold {role}"));
+            }
+        }
+    }
+    for role in &permissions.renounce {
+        if has_role(role, deployer) {
+            problems.push(format!("the deployer {deployer:?} still holds {role}"));
+        }
+    }
+
+    // Enumerable AccessControl lists every holder, otherwise only the
+    // accounts of the permissions can be checked
+    let mut members_reader = BatchReader::new(client);
+    let mut member_reads: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (role, read) in &count_reads {
+        if let Some(count) =
+            decode("getRoleMemberCount", &results[*read]).and_then(Token::into_uint)
+        {
+            let reads = member_reads.entry(*role).or_default();
+            for index in 0..count.min(U256::from(u8::MAX)).as_u64() {
+                reads.push(members_reader.add(
+                    address,
+                    encode(
+                        "getRoleMember",
+                        &[role_token(role)?, Token::Uint(U256::from(index))],
+                    )?,
+                ));
+            }
+        }
+    }
+    let member_results = members_reader.read().await?;
+
+    println!("{}", "-".repeat(80));
+    if let Some(owner) = owner {
+        println!("owner: {owner:?}");
+    }
+    if let Some(pending_owner) = pending_owner.filter(|pending| !pending.is_zero()) {
+        println!("pending owner: {pending_owner:?}");
+    }
+    for role in &roles {
+        let holders: Vec<String> = match member_reads.get(role) {
+            Some(reads) => reads
+                .iter()
+                .filter_map(|read| decode("getRoleMember", &member_results[*read]))
+                .filter_map(Token::into_address)
+                .map(|member| format!("{member:?}"))
+                .collect(),
+            None => accounts
+                .iter()
+                .filter(|account| has_role(role, **account))
+                .map(|account| format!("{account:?} (of the checked accounts)"))
+                .collect(),
+        };
+        let holders = match holders.is_empty() {
+            true => "none".to_owned(),
+            false => holders.join(", "),
+        };
+        println!("{role}: {holders}");
+    }
+
+    if !problems.is_empty() {
+        return Err(eyre!(
+            "Permissions of {address:?} do not hold: {}",
+            problems.join("; ")
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,372 @@
+use crate::calls::PlannedCall;
+use crate::cli_config::parse_amount;
+use crate::deployer::DeployContext;
+use crate::env::interpolate;
+use crate::fund::top_up;
+use crate::hooks::Hooks;
+use crate::manifest::Deployment;
+use crate::permissions::{apply_permissions, Permissions};
+use crate::registry::{aliases, resolve_alias, resolve_aliases};
+use crate::steps::PlannedStep;
+use ethers::types::Address;
+use eyre::{eyre, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+// A declarative multi-contract deployment, read from `deploy.toml`,
+// `deploy.yaml` or `deploy.json`:
+//
+// [[fund]]
+// account = "@deployer"
+// amount = "1ether"
+//
+// [[contracts]]
+// name = "Storage"
+// contract = "MessageStorage"
+//
+// [[contracts]]
+// name = "Registry"
+// args = ["@Storage"]
+// calls = [{ method = "register", args = ["@Storage", "main"] }]
+//
+// [[contracts]]
+// name = "Vault"
+// calls = [{ method = "setFee", args = ["30"], timelock = "@Timelock" }]
+//
+// [permissions.Vault]
+// owner = "@Timelock"
+//
+// [[steps]]
+// kind = "call"
+// target = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+// method = "register(address,string)"
+// args = ["@Vault", "vault"]
+//
+// [hooks]
+// post_deploy = ["./scripts/publish-addresses.sh"]
+//
+// `@<name>` in constructor or call arguments, or in the timelock of a call,
+// refers to the address of another contract of the plan, which is then
+// deployed first. A name outside the plan refers to a contract already
+// deployed on the chain, read from its manifest, and `@deployer` to the
+// account sending the transactions. Accounts of `fund` are topped up to their
+// amount before anything is deployed. Custom steps run once every contract is
+// deployed, then the permissions are applied.
+// `${VAR}` anywhere in the plan is replaced with an environment variable.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Plan {
+    pub contracts: Vec<PlannedContract>,
+    // Ownership and roles of plan contracts, by contract name
+    #[serde(default)]
+    pub permissions: BTreeMap<String, Permissions>,
+    // Run after those of `solidity-deploy.toml`
+    #[serde(default)]
+    pub hooks: Hooks,
+    // Custom steps run once every contract is deployed
+    #[serde(default)]
+    pub steps: Vec<PlannedStep>,
+    // Accounts topped up before anything is deployed
+    #[serde(default)]
+    pub fund: Vec<Funding>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Funding {
+    // Address or `@<name>`, e.g. `@deployer`
+    pub account: String,
+    // Balance the account needs at least, e.g. `1ether`
+    pub amount: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlannedContract {
+    // Name the deployment is recorded under and referred to by
+    pub name: String,
+    // Solidity contract to deploy, defaults to `name`
+    #[serde(default)]
+    pub contract: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub calls: Vec<PlannedCall>,
+}
+
+impl PlannedContract {
+    pub fn contract_name(&self) -> &str {
+        self.contract.as_deref().unwrap_or(&self.name)
+    }
+
+    // Names this contract refers to, in the plan or not
+    fn references(&self) -> BTreeSet<String> {
+        self.args
+            .iter()
+            .chain(
+                self.calls
+                    .iter()
+                    .flat_map(|call| call.args.iter().chain(call.timelock.iter())),
+            )
+            .flat_map(|value| aliases(value))
+            .filter(|name| *name != self.name)
+            .collect()
+    }
+}
+
+// Read a plan, picking the format from the file extension
+pub fn load_plan(path: &Path) -> Result<Plan> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| eyre!("Failed to read deployment plan {path:?}: {e}"))?;
+    let content = interpolate(&content, path)?;
+    let plan: Plan = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(|e| eyre!(e.to_string()))?,
+        Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+        Some("json") => serde_json::from_str(&content)?,
+        _ => {
+            return Err(eyre!(
+                "Unsupported deployment plan format {path:?}, use toml, yaml or json"
+            ))
+        }
+    };
+    plan.ordered()?;
+    Ok(plan)
+}
+
+// Name of the plan, used for its journal
+pub fn plan_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "plan".to_owned())
+}
+
+impl Plan {
+    // Order the contracts so every one comes after the contracts it refers to
+    pub fn ordered(&self) -> Result<Vec<&PlannedContract>> {
+        let mut by_name = HashMap::new();
+        for contract in &self.contracts {
+            if by_name.insert(contract.name.as_str(), contract).is_some() {
+                return Err(eyre!(
+                    "Contract {} appears twice in the plan",
+                    contract.name
+                ));
+            }
+        }
+
+        for name in self.permissions.keys() {
+            if !by_name.contains_key(name.as_str()) {
+                return Err(eyre!("Permissions of {name} which is not part of the plan"));
+            }
+        }
+
+        // Only plan contracts order the deployment, other names are resolved
+        // up front by `external_aliases`
+        let mut pending: Vec<(&PlannedContract, BTreeSet<String>)> = self
+            .contracts
+            .iter()
+            .map(|contract| {
+                let mut dependencies = contract.references();
+                dependencies.retain(|name| by_name.contains_key(name.as_str()));
+                (contract, dependencies)
+            })
+            .collect();
+
+        let mut ordered: Vec<&PlannedContract> = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let ready = pending.iter().position(|(_, dependencies)| {
+                dependencies
+                    .iter()
+                    .all(|name| ordered.iter().any(|done| done.name == *name))
+            });
+            match ready {
+                Some(index) => ordered.push(pending.remove(index).0),
+                None => {
+                    let names = pending
+                        .iter()
+                        .map(|(contract, _)| contract.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    return Err(eyre!("Circular references between contracts: {names}"));
+                }
+            }
+        }
+        Ok(ordered)
+    }
+
+    // Names referred to anywhere in the plan that are not plan contracts:
+    // `@deployer` and contracts deployed by earlier runs
+    pub fn external_aliases(&self) -> BTreeSet<String> {
+        let planned: BTreeSet<&str> = self
+            .contracts
+            .iter()
+            .map(|contract| contract.name.as_str())
+            .collect();
+        self.contracts
+            .iter()
+            .flat_map(|contract| contract.references())
+            .chain(
+                self.permissions
+                    .values()
+                    .flat_map(|permissions| permissions.accounts())
+                    .flat_map(|value| aliases(value)),
+            )
+            .chain(
+                self.steps
+                    .iter()
+                    .flat_map(|step| aliases(&Value::Object(step.args.clone()).to_string())),
+            )
+            .chain(
+                self.fund
+                    .iter()
+                    .flat_map(|funding| aliases(&funding.account)),
+            )
+            .filter(|name| !planned.contains(name.as_str()))
+            .collect()
+    }
+}
+
+// Deploy every contract of the plan in dependency order, substituting
+// `@<name>` references with the addresses deployed so far, then run the
+// custom steps and apply the permissions
+pub async fn execute_plan(context: &mut DeployContext, plan: &Plan) -> Result<Vec<Deployment>> {
+    let ordered = plan.ordered()?;
+    println!(
+        "PLAN: {}",
+        ordered
+            .iter()
+            .map(|contract| contract.name.as_str())
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    );
+
+    // Unknown step kinds and invalid arguments fail before anything is sent
+    let steps = plan
+        .steps
+        .iter()
+        .map(|step| {
+            let runner = context.steps.get(&step.kind)?;
+            runner.validate(&step.args)?;
+            Ok((step, runner))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let funding = plan
+        .fund
+        .iter()
+        .map(|funding| {
+            let amount = parse_amount(&funding.amount)
+                .map_err(|e| eyre!("Invalid amount to fund {}: {e}", funding.account))?;
+            Ok((funding, amount))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Aliases outside the plan fail before anything is sent too
+    let deployer = context.from.unwrap_or_else(|| context.client.address());
+    let mut addresses = plan
+        .external_aliases()
+        .into_iter()
+        .map(|name| {
+            let address = resolve_alias(&name, context.chain_id, Some(deployer))?;
+            Ok((name, address))
+        })
+        .collect::<Result<HashMap<String, Address>>>()?;
+
+    for (funding, amount) in funding {
+        let account = resolve_references(&funding.account, &addresses)?;
+        let account: Address = account
+            .parse()
+            .map_err(|e| eyre!("Invalid account to fund {account:?}: {e}"))?;
+        println!("FUND {account:?}");
+        top_up(
+            context.client.as_ref(),
+            context.from,
+            account,
+            amount,
+            &context.fees,
+            context.confirmations,
+        )
+        .await?;
+    }
+
+    let mut deployments = Vec::with_capacity(ordered.len());
+    for contract in ordered {
+        let args = contract
+            .args
+            .iter()
+            .map(|value| resolve_references(value, &addresses))
+            .collect::<Result<Vec<_>>>()?;
+        let calls = contract
+            .calls
+            .iter()
+            .map(|call| {
+                let mut call = call.clone();
+                call.args = call
+                    .args
+                    .iter()
+                    .map(|value| resolve_references(value, &addresses))
+                    .collect::<Result<Vec<_>>>()?;
+                call.timelock = call
+                    .timelock
+                    .as_deref()
+                    .map(|value| resolve_references(value, &addresses))
+                    .transpose()?;
+                Ok(call)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let deployment = context
+            .deploy_contract(&contract.name, contract.contract_name(), &args, &calls)
+            .await?;
+        addresses.insert(contract.name.clone(), deployment.address);
+        deployments.push(deployment);
+    }
+
+    for (index, (step, runner)) in steps.iter().enumerate() {
+        let args: Map<String, Value> = step
+            .args
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), resolve_value_references(value, &addresses)?)))
+            .collect::<Result<_>>()?;
+        println!("{}", "=".repeat(80));
+        println!("STEP #{index} {}", step.kind);
+        runner
+            .run(context, &format!("step #{index} {}", step.kind), &args)
+            .await?;
+    }
+
+    for deployment in &deployments {
+        if let Some(permissions) = plan.permissions.get(&deployment.name) {
+            let permissions = permissions.resolve(|value| resolve_references(value, &addresses))?;
+            apply_permissions(context, &deployment.name, deployment.address, &permissions).await?;
+        }
+    }
+    Ok(deployments)
+}
+
+fn resolve_references(value: &str, addresses: &HashMap<String, Address>) -> Result<String> {
+    resolve_aliases(value, |name| {
+        addresses
+            .get(name)
+            .copied()
+            .ok_or_else(|| eyre!("@{name} is not deployed yet"))
+    })
+}
+
+// `@<name>` references resolved in every string of a step argument
+fn resolve_value_references(value: &Value, addresses: &HashMap<String, Address>) -> Result<Value> {
+    Ok(match value {
+        Value::String(value) => Value::String(resolve_references(value, addresses)?),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| resolve_value_references(item, addresses))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), resolve_value_references(value, addresses)?)))
+                .collect::<Result<_>>()?,
+        ),
+        value => value.clone(),
+    })
+}
@@ -0,0 +1,139 @@
+use crate::cli_config::{MineSaltConfig, PredictAddressConfig};
+use crate::compiler::{compile, contracts_path};
+use crate::create2::{create2_address, init_code_hash, CREATE2_FACTORY};
+use crate::deployer::{deploy_code, encode_constructor_args, find_contract};
+use ethers::types::{Address, H256, U256};
+use ethers::utils::get_contract_address;
+use eyre::{eyre, ContextCompat, Result};
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+// Entry point of the `predict-address` subcommand. CREATE addresses follow
+// from the deployer and its nonce, CREATE2 addresses from the factory, the
+// salt and the hash of the init code.
+pub async fn predict_address(config: PredictAddressConfig) -> Result<()> {
+    let Some(salt) = config.salt else {
+        let deployer = config
+            .deployer
+            .context("--deployer is required for CREATE")?;
+        let nonce = config.nonce.context("--nonce is required for CREATE")?;
+        println!("Address: {:?}", get_contract_address(deployer, nonce));
+        return Ok(());
+    };
+
+    let init_code = match (config.init_code, &config.contract_name) {
+        (Some(init_code), _) => init_code,
+        (None, Some(contract_name)) => {
+            let project = compile(contracts_path(), None, &config.compiler).await?;
+            let (abi, bytecode) = find_contract(&project, contract_name)?;
+            let constructor_args =
+                encode_constructor_args(&abi, contract_name, &config.constructor_args)?;
+            deploy_code(&abi, &bytecode, &constructor_args)?
+        }
+        (None, None) => {
+            return Err(eyre!(
+                "--init-code or --contract-name is required to predict a CREATE2 address"
+            ))
+        }
+    };
+    let deployer = config.deployer.unwrap_or(CREATE2_FACTORY);
+    let init_code_hash = init_code_hash(&init_code);
+    println!("Deployer: {deployer:?}");
+    println!("Init code hash: {init_code_hash:?}");
+    println!(
+        "Address: {:?}",
+        create2_address(deployer, salt, init_code_hash)
+    );
+    Ok(())
+}
+
+// Entry point of the `mine-salt` subcommand: try salts on every core until
+// the CREATE2 address of the init code matches the prefix and suffix, then
+// print the `deploy --create2` arguments deploying it there
+pub async fn mine_salt(config: MineSaltConfig) -> Result<()> {
+    let init_code = match (&config.init_code, &config.contract_name) {
+        (Some(init_code), _) => init_code.clone(),
+        (None, Some(contract_name)) => {
+            let project = compile(contracts_path(), None, &config.compiler).await?;
+            let (abi, bytecode) = find_contract(&project, contract_name)?;
+            let constructor_args =
+                encode_constructor_args(&abi, contract_name, &config.constructor_args)?;
+            deploy_code(&abi, &bytecode, &constructor_args)?
+        }
+        (None, None) => return Err(eyre!("--init-code or --contract-name is required")),
+    };
+    let prefix = config.prefix.clone().unwrap_or_default();
+    let suffix = config.suffix.clone().unwrap_or_default();
+    if prefix.len() + suffix.len() > 40 {
+        return Err(eyre!(
+            "--prefix and --suffix ask for more than the 40 hex digits of an address"
+        ));
+    }
+    let deployer = config.deployer.unwrap_or(CREATE2_FACTORY);
+    let init_code_hash = init_code_hash(&init_code);
+    // Each extra digit multiplies the expected number of attempts by 16
+    let expected = 16f64.powi((prefix.len() + suffix.len()) as i32);
+    println!("Deployer: {deployer:?}");
+    println!("Init code hash: {init_code_hash:?}");
+    println!("Searching, about {expected:.0} attempts expected");
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(config.threads.unwrap_or(0))
+        .build()?;
+    let attempts = AtomicU64::new(0);
+    let started = Instant::now();
+    let found = pool.install(|| {
+        (0..u64::MAX).into_par_iter().find_map_any(|index| {
+            attempts.fetch_add(1, Ordering::Relaxed);
+            let mut salt = [0u8; 32];
+            U256::from(index).to_big_endian(&mut salt);
+            let salt = H256(salt);
+            let address = create2_address(deployer, salt, init_code_hash);
+            matches_pattern(address, &prefix, &suffix).then_some((salt, address))
+        })
+    });
+    let (salt, address) = found.context("No salt found")?;
+    let elapsed = started.elapsed().as_secs_f64();
+    println!(
+        "Found after {} attempts in {elapsed:.1}s",
+        attempts.load(Ordering::Relaxed)
+    );
+    println!("Salt: {salt:?}");
+    println!("Address: {address:?}");
+    match (&config.contract_name, config.deployer) {
+        (Some(contract_name), None) => {
+            let mut command =
+                format!("deploy --contract-name {contract_name} --create2 --salt {salt:?}");
+            if !config.constructor_args.is_empty() {
+                command.push_str(" --constructor-args");
+                for arg in &config.constructor_args {
+                    command.push_str(&format!(" {arg:?}"));
+                }
+            }
+            println!("Deploy it there with: {command}");
+        }
+        _ => println!("Deploy it there with --salt {salt:?}"),
+    }
+    Ok(())
+}
+
+// Whether the hex digits of `address` start with `prefix` and end with
+// `suffix`, given one nibble per digit
+fn matches_pattern(address: Address, prefix: &[u8], suffix: &[u8]) -> bool {
+    let nibble = |index: usize| {
+        let byte = address.as_bytes()[index / 2];
+        match index % 2 {
+            0 => byte >> 4,
+            _ => byte & 0x0f,
+        }
+    };
+    prefix
+        .iter()
+        .enumerate()
+        .all(|(index, digit)| nibble(index) == *digit)
+        && suffix
+            .iter()
+            .enumerate()
+            .all(|(index, digit)| nibble(40 - suffix.len() + index) == *digit)
+}
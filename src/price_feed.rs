@@ -0,0 +1,103 @@
+use crate::cli_config::{PriceFeed, PriceFeedConfig};
+use ethers::abi::parse_abi;
+use ethers::contract::BaseContract;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionRequest, I256, U256};
+use ethers::utils::format_ether;
+use ethers_providers::Middleware;
+use eyre::{eyre, Result};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::warn;
+
+// Chainlink ETH/USD aggregator on mainnet
+const MAINNET_ETH_USD: &str = "0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419";
+// Longest the Coingecko API may take to answer
+const COINGECKO_TIMEOUT: Duration = Duration::from_secs(10);
+
+// USD price of the native token from the feed of `--price-feed`, `None`
+// without one. Costs in USD are only informative, so a feed that fails is
+// reported without failing the run.
+pub async fn usd_price<M: Middleware>(client: &M, config: &PriceFeedConfig) -> Option<f64>
+where
+    M::Error: 'static,
+{
+    let result = match config.price_feed? {
+        PriceFeed::Coingecko => coingecko_price(&config.coingecko_id).await,
+        PriceFeed::Chainlink => chainlink_price(client, config.price_feed_address).await,
+    };
+    match result {
+        Ok(price) => {
+            println!("Native token price: ${price:.2}");
+            Some(price)
+        }
+        Err(e) => {
+            warn!("Failed to get the USD price, costs are shown without it: {e}");
+            None
+        }
+    }
+}
+
+// Approximate value in USD of an amount of wei
+pub fn usd(wei: U256, price: f64) -> f64 {
+    format_ether(wei).parse::<f64>().unwrap_or_default() * price
+}
+
+// e.g. `$12.34` for an amount of wei
+pub fn format_usd(wei: U256, price: f64) -> String {
+    format!("${:.2}", usd(wei, price))
+}
+
+async fn coingecko_price(id: &str) -> Result<f64> {
+    let url = format!("https://api.coingecko.com/api/v3/simple/price?ids={id}&vs_currencies=usd");
+    let prices: HashMap<String, HashMap<String, f64>> = reqwest::Client::new()
+        .get(&url)
+        .timeout(COINGECKO_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    prices
+        .get(id)
+        .and_then(|price| price.get("usd"))
+        .copied()
+        .ok_or_else(|| eyre!("Coingecko has no USD price of {id:?}"))
+}
+
+// Latest answer of a Chainlink aggregator, scaled by its decimals
+async fn chainlink_price<M: Middleware>(client: &M, aggregator: Option<Address>) -> Result<f64>
+where
+    M::Error: 'static,
+{
+    let aggregator = match aggregator {
+        Some(aggregator) => aggregator,
+        None if client.get_chainid().await? == U256::one() => MAINNET_ETH_USD.parse()?,
+        None => {
+            return Err(eyre!(
+                "--price-feed-address is required for Chainlink outside of mainnet"
+            ))
+        }
+    };
+    let contract = BaseContract::from(parse_abi(&[
+        "function decimals() external view returns (uint8)",
+        "function latestRoundData() external view returns (uint80, int256, uint256, uint256, uint80)",
+    ])?);
+    let call = |data: Bytes| -> TypedTransaction {
+        TransactionRequest::new().to(aggregator).data(data).into()
+    };
+
+    let output = client
+        .call(&call(contract.encode("decimals", ())?), None)
+        .await?;
+    let decimals: u8 = contract.decode_output("decimals", output)?;
+    let output = client
+        .call(&call(contract.encode("latestRoundData", ())?), None)
+        .await?;
+    let (_, answer, _, _, _): (U256, I256, U256, U256, U256) =
+        contract.decode_output("latestRoundData", output)?;
+    if answer <= I256::zero() {
+        return Err(eyre!("Aggregator {aggregator:?} answered {answer}"));
+    }
+    Ok(answer.to_string().parse::<f64>()? / 10f64.powi(decimals.into()))
+}
@@ -0,0 +1,112 @@
+use crate::output;
+use ethers::types::{BlockNumber, H256};
+use ethers_providers::Middleware;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::future::Future;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+// Blocks the block time is averaged over
+const BLOCK_TIME_SAMPLE: u64 = 20;
+// How often the confirmations of a transaction are polled for the display
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Whether progress is displayed, set once the command line is parsed
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// Display progress on stderr when it is a terminal, unless `--json` or `-q`
+// was given. Must be called after `output::set_json`.
+pub fn init(quiet: bool) {
+    let enabled = !quiet && !output::json() && std::io::stderr().is_terminal();
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// Spinner on stderr with a message, cleared when dropped. Does nothing when
+// progress is not displayed.
+pub struct Progress(Option<ProgressBar>);
+
+impl Progress {
+    pub fn spinner(message: impl Into<String>) -> Self {
+        if !enabled() {
+            return Progress(None);
+        }
+        let bar = ProgressBar::new_spinner()
+            .with_style(ProgressStyle::with_template("{spinner} {msg} [{elapsed}]").unwrap())
+            .with_message(message.into());
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Progress(Some(bar))
+    }
+
+    pub fn set_message(&self, message: impl Into<String>) {
+        if let Some(bar) = &self.0 {
+            bar.set_message(message.into());
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(bar) = &self.0 {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+// Average time between the latest blocks, `None` on chains mining on demand
+// such as Anvil
+pub async fn block_time<M: Middleware>(client: &M) -> Option<Duration> {
+    let latest = client.get_block(BlockNumber::Latest).await.ok()??;
+    let number = latest.number?.as_u64();
+    let sample = BLOCK_TIME_SAMPLE.min(number);
+    if sample == 0 {
+        return None;
+    }
+    let earlier = client.get_block(number - sample).await.ok()??;
+    let elapsed = latest.timestamp.checked_sub(earlier.timestamp)?.as_u64();
+    (elapsed > 0).then(|| Duration::from_secs_f64(elapsed as f64 / sample as f64))
+}
+
+// Await `wait`, waiting for `confirmations` confirmations of the transaction
+// `hash`, showing how many it has and an estimate of the time left
+pub async fn track_confirmations<M: Middleware, T>(
+    client: &M,
+    hash: H256,
+    confirmations: usize,
+    wait: impl Future<Output = T>,
+) -> T {
+    if !enabled() {
+        return wait.await;
+    }
+    let progress = Progress::spinner(format!("Waiting for {hash:?} to be mined"));
+    let block_time = block_time(client).await;
+    let mut ticker = tokio::time::interval(POLL_INTERVAL);
+    tokio::pin!(wait);
+    loop {
+        tokio::select! {
+            result = &mut wait => return result,
+            _ = ticker.tick() => {
+                let confirmed = match client.get_transaction_receipt(hash).await {
+                    Ok(Some(receipt)) => match (receipt.block_number, client.get_block_number().await) {
+                        (Some(mined), Ok(latest)) => (latest.as_u64() + 1).saturating_sub(mined.as_u64()),
+                        _ => 0,
+                    },
+                    _ => 0,
+                };
+                let remaining = (confirmations as u64).saturating_sub(confirmed);
+                let estimate = match block_time {
+                    Some(block_time) => format!(", about {}s left", (block_time * remaining as u32).as_secs()),
+                    None => String::new(),
+                };
+                progress.set_message(format!(
+                    "Waiting for {hash:?}: {}/{confirmations} confirmation(s){estimate}",
+                    confirmed.min(confirmations as u64)
+                ));
+            }
+        }
+    }
+}
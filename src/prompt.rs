@@ -0,0 +1,68 @@
+use crate::abi_args::describe_params;
+use crate::output;
+use ethers::abi::token::{LenientTokenizer, Tokenizer};
+use ethers::abi::Param;
+use eyre::{eyre, Result};
+use std::io::{IsTerminal, Write};
+
+// Whether missing inputs may be asked for: someone is at the terminal, and
+// the run is neither a `--json` one nor a CI job, which never wait on stdin
+pub fn interactive() -> bool {
+    !output::json()
+        && std::env::var_os("CI").is_none()
+        && std::io::stdin().is_terminal()
+        && std::io::stdout().is_terminal()
+}
+
+// Ask a question on the terminal and return the trimmed answer
+pub fn ask(question: &str) -> Result<String> {
+    print!("{question}: ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer)? == 0 {
+        return Err(eyre!("No answer to {question:?}, stdin is closed"));
+    }
+    Ok(answer.trim().to_owned())
+}
+
+// Ask for every constructor argument of a contract, asking again for those
+// that do not parse as their ABI type
+pub fn constructor_args(contract_name: &str, inputs: &[Param]) -> Result<Vec<String>> {
+    println!(
+        "Constructor arguments of {contract_name} {}",
+        describe_params(inputs)
+    );
+    let mut values = vec![];
+    for (index, param) in inputs.iter().enumerate() {
+        let name = match param.name.is_empty() {
+            true => format!("argument {}", index + 1),
+            false => param.name.clone(),
+        };
+        loop {
+            let value = ask(&format!("{name} ({})", param.kind))?;
+            match LenientTokenizer::tokenize(&param.kind, &value) {
+                Ok(_) => {
+                    values.push(value);
+                    break;
+                }
+                Err(e) => println!("Invalid {}: {e}", param.kind),
+            }
+        }
+    }
+    Ok(values)
+}
+
+// Ask which network profile to deploy to, `None` for a local Anvil chain
+pub fn network(networks: &[String]) -> Result<Option<String>> {
+    println!("Networks: {}", networks.join(", "));
+    loop {
+        let network = ask("Network to deploy to, empty for a local Anvil chain")?;
+        if network.is_empty() {
+            return Ok(None);
+        }
+        if networks.contains(&network) {
+            return Ok(Some(network));
+        }
+        println!("Unknown network {network}");
+    }
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+// Minimal ERC-1967 proxy for UUPS implementations, which carry the upgrade
+// logic (`upgradeToAndCall`) themselves.
+contract ERC1967Proxy {
+    // bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)
+    bytes32 internal constant IMPLEMENTATION_SLOT =
+        0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc;
+
+    event Upgraded(address indexed implementation);
+
+    constructor(address implementation, bytes memory data) payable {
+        require(implementation.code.length > 0, "ERC1967: implementation is not a contract");
+        assembly {
+            sstore(IMPLEMENTATION_SLOT, implementation)
+        }
+        emit Upgraded(implementation);
+
+        if (data.length > 0) {
+            (bool success, bytes memory result) = implementation.delegatecall(data);
+            if (!success) {
+                assembly {
+                    revert(add(result, 32), mload(result))
+                }
+            }
+        }
+    }
+
+    fallback() external payable {
+        _delegate();
+    }
+
+    receive() external payable {
+        _delegate();
+    }
+
+    function _delegate() internal {
+        assembly {
+            let implementation := sload(IMPLEMENTATION_SLOT)
+            calldatacopy(0, 0, calldatasize())
+            let result := delegatecall(gas(), implementation, 0, calldatasize(), 0, 0)
+            returndatacopy(0, 0, returndatasize())
+            switch result
+            case 0 {
+                revert(0, returndatasize())
+            }
+            default {
+                return(0, returndatasize())
+            }
+        }
+    }
+}
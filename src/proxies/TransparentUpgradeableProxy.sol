@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+// Minimal transparent ERC-1967 proxy. The admin can only call
+// `upgradeToAndCall(address,bytes)`, every other caller is forwarded to the
+// implementation, so admin functions never clash with implementation ones.
+contract TransparentUpgradeableProxy {
+    // bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)
+    bytes32 internal constant IMPLEMENTATION_SLOT =
+        0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc;
+    // bytes32(uint256(keccak256("eip1967.proxy.admin")) - 1)
+    bytes32 internal constant ADMIN_SLOT =
+        0xb53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103;
+
+    event Upgraded(address indexed implementation);
+    event AdminChanged(address previousAdmin, address newAdmin);
+
+    constructor(address implementation, address admin, bytes memory data) payable {
+        assembly {
+            sstore(ADMIN_SLOT, admin)
+        }
+        emit AdminChanged(address(0), admin);
+        _upgradeToAndCall(implementation, data);
+    }
+
+    fallback() external payable {
+        address admin;
+        assembly {
+            admin := sload(ADMIN_SLOT)
+        }
+        if (msg.sender != admin) {
+            _delegate();
+        } else if (msg.sig == bytes4(keccak256("upgradeToAndCall(address,bytes)"))) {
+            (address implementation, bytes memory data) = abi.decode(msg.data[4:], (address, bytes));
+            _upgradeToAndCall(implementation, data);
+        } else {
+            revert("TransparentUpgradeableProxy: admin cannot fallback to proxy target");
+        }
+    }
+
+    receive() external payable {
+        _delegate();
+    }
+
+    function _upgradeToAndCall(address implementation, bytes memory data) internal {
+        require(implementation.code.length > 0, "ERC1967: implementation is not a contract");
+        assembly {
+            sstore(IMPLEMENTATION_SLOT, implementation)
+        }
+        emit Upgraded(implementation);
+
+        if (data.length > 0) {
+            (bool success, bytes memory result) = implementation.delegatecall(data);
+            if (!success) {
+                assembly {
+                    revert(add(result, 32), mload(result))
+                }
+            }
+        }
+    }
+
+    function _delegate() internal {
+        assembly {
+            let implementation := sload(IMPLEMENTATION_SLOT)
+            calldatacopy(0, 0, calldatasize())
+            let result := delegatecall(gas(), implementation, 0, calldatasize(), 0, 0)
+            returndatacopy(0, 0, returndatasize())
+            switch result
+            case 0 {
+                revert(0, returndatasize())
+            }
+            default {
+                return(0, returndatasize())
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::abi_args::parse_tokens;
+use crate::calls::PlannedCall;
+use crate::contract_abi::find_function;
+use ethers::abi::{parse_abi, Abi, Token};
+use ethers::types::{Address, Bytes};
+use ethers_solc::artifacts::{Source, Sources};
+use ethers_solc::{CompilerInput, Solc};
+use eyre::{eyre, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+// Proxy contracts shipped with the binary, compiled on demand
+const ERC1967_PROXY_SOURCE: &str = include_str!("proxies/ERC1967Proxy.sol");
+const TRANSPARENT_PROXY_SOURCE: &str = include_str!("proxies/TransparentUpgradeableProxy.sol");
+
+#[derive(clap::ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyKind {
+    // Upgrades go through the proxy, restricted to its admin
+    Transparent,
+    // Upgrades go through `upgradeToAndCall` of the implementation itself
+    Uups,
+}
+
+impl ProxyKind {
+    fn contract(&self) -> (&'static str, &'static str) {
+        match self {
+            ProxyKind::Transparent => ("TransparentUpgradeableProxy", TRANSPARENT_PROXY_SOURCE),
+            ProxyKind::Uups => ("ERC1967Proxy", ERC1967_PROXY_SOURCE),
+        }
+    }
+}
+
+impl fmt::Display for ProxyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyKind::Transparent => write!(f, "transparent"),
+            ProxyKind::Uups => write!(f, "uups"),
+        }
+    }
+}
+
+// Compile the embedded proxy contract and return its ABI and creation bytecode
+pub fn compile_proxy(kind: ProxyKind) -> Result<(Abi, Bytes)> {
+    let (name, source) = kind.contract();
+    let source = Source::new(source);
+    let version = Solc::detect_version(&source)?;
+    let solc = Solc::find_or_install_svm_version(version.to_string())?;
+
+    let sources = Sources::from([(PathBuf::from(format!("{name}.sol")), source)]);
+    let input = CompilerInput::with_sources(sources)
+        .into_iter()
+        .next()
+        .context("No Solidity input for the proxy contract")?;
+    let output = solc.compile_exact(&input)?;
+    if output.has_error() {
+        return Err(eyre!("Compiling {name} failed: {:?}", output.errors));
+    }
+
+    let contract = output
+        .find(name)
+        .with_context(|| format!("{name} not found in compiler output"))?;
+    let abi = contract
+        .abi
+        .context("Missing abi from proxy contract")?
+        .clone();
+    let bytecode = contract
+        .bin
+        .and_then(|bin| bin.as_bytes())
+        .context("Missing bytecode from proxy contract")?
+        .clone();
+    Ok((abi, bytecode))
+}
+
+// Constructor arguments of the proxy: the implementation, the admin of a
+// transparent proxy and the initializer call data
+pub fn proxy_constructor_args(
+    kind: ProxyKind,
+    implementation: Address,
+    admin: Option<Address>,
+    data: &Bytes,
+) -> Vec<Token> {
+    let mut args = vec![Token::Address(implementation)];
+    if kind == ProxyKind::Transparent {
+        args.push(Token::Address(admin.unwrap_or_default()));
+    }
+    args.push(Token::Bytes(data.to_vec()));
+    args
+}
+
+// Call data of an initializer, given as a name or full signature
+pub fn encode_initializer(abi: &Abi, initializer: &str, values: &[String]) -> Result<Bytes> {
+    let function = find_function(abi, initializer, values.len())?;
+    let tokens = parse_tokens(&function.inputs, values)?;
+    Ok(function.encode_input(&tokens)?.into())
+}
+
+// Call upgrading a proxy to `implementation`, sent to the proxy address with
+// the returned ABI. UUPS implementations predating `upgradeToAndCall` are
+// upgraded with `upgradeTo` when there is nothing to call.
+pub fn upgrade_call(
+    kind: ProxyKind,
+    current_abi: &Abi,
+    implementation: Address,
+    data: &Bytes,
+) -> Result<(Abi, PlannedCall)> {
+    let abi = parse_abi(&[
+        "function upgradeToAndCall(address,bytes) payable",
+        "function upgradeTo(address)",
+    ])?;
+    let legacy_uups = kind == ProxyKind::Uups
+        && data.is_empty()
+        && current_abi.function("upgradeToAndCall").is_err()
+        && current_abi.function("upgradeTo").is_ok();
+    let call = if legacy_uups {
+        PlannedCall {
+            method: "upgradeTo".to_owned(),
+            args: vec![format!("{implementation:?}")],
+            value: None,
+            expect: None,
+            timelock: None,
+        }
+    } else {
+        PlannedCall {
+            method: "upgradeToAndCall".to_owned(),
+            args: vec![format!("{implementation:?}"), data.to_string()],
+            value: None,
+            expect: None,
+            timelock: None,
+        }
+    };
+    Ok((abi, call))
+}
@@ -0,0 +1,217 @@
+use crate::chains::{chain_info, resolve_chain};
+use crate::cli_config::{RegistryCommand, RegistryConfig};
+use crate::manifest::{manifest_path, Deployment};
+use crate::network::profile_chain_id;
+use ethers::types::Address;
+use eyre::{eyre, ContextCompat, Result};
+
+// Entry point of the `registry` subcommand, maintaining the deployment
+// manifests of every network
+pub fn registry(config: RegistryConfig) -> Result<()> {
+    match config.command {
+        RegistryCommand::List(config) => {
+            let chain_id = config.only.as_deref().map(network_chain_id).transpose()?;
+            let deployments = Deployment::list_all()?
+                .into_iter()
+                .filter(|deployment| chain_id.map_or(true, |id| deployment.chain_id == id))
+                .collect::<Vec<_>>();
+            println!("{}", "=".repeat(80));
+            println!(
+                "{:<24} {:<24} {:<24} ADDRESS",
+                "NETWORK", "NAME", "CONTRACT"
+            );
+            for deployment in &deployments {
+                let contract = match &deployment.proxy {
+                    Some(proxy) => format!("{} ({} proxy)", deployment.contract_name, proxy.kind),
+                    None => deployment.contract_name.clone(),
+                };
+                println!(
+                    "{:<24} {:<24} {:<24} {:?}",
+                    chain_info(deployment.chain_id)?.name,
+                    deployment.name,
+                    contract,
+                    deployment.address
+                );
+            }
+            println!("{} deployment(s)", deployments.len());
+        }
+        RegistryCommand::Show(config) => {
+            let deployment = resolve_deployment(&config.reference, None)?;
+            println!("{}", "=".repeat(80));
+            println!("{}", config.reference);
+            println!("contract: {}", deployment.contract_name);
+            println!(
+                "chain: {} ({})",
+                chain_info(deployment.chain_id)?.name,
+                deployment.chain_id
+            );
+            println!("address: {:?}", deployment.address);
+            println!("transaction: {:?}", deployment.transaction_hash);
+            if let Some(block) = deployment.block_number {
+                println!("block: {block}");
+            }
+            println!("deployer: {:?}", deployment.deployer);
+            println!("compiler: {}", deployment.compiler.version);
+            if !deployment.constructor_args.is_empty() {
+                println!(
+                    "constructor args: {}",
+                    deployment.constructor_args.join(" ")
+                );
+            }
+            if let Some(salt) = deployment.salt {
+                println!("salt: {salt:?}");
+            }
+            if let Some(proxy) = &deployment.proxy {
+                println!(
+                    "proxy: {} of {} at {:?}",
+                    proxy.kind, proxy.implementation_name, proxy.implementation
+                );
+                if let Some(admin) = proxy.admin {
+                    println!("proxy admin: {admin:?}");
+                }
+            }
+            for (library, address) in &deployment.libraries {
+                println!("library {library}: {address:?}");
+            }
+            println!(
+                "functions: {}, events: {}",
+                deployment.abi.functions().count(),
+                deployment.abi.events().count()
+            );
+        }
+        RegistryCommand::Rm(config) => {
+            let deployment = resolve_deployment(&config.reference, None)?;
+            // A proxy's manifest points at the one of its implementation
+            let users = Deployment::list_all()?
+                .into_iter()
+                .filter(|other| other.chain_id == deployment.chain_id)
+                .filter(|other| {
+                    other
+                        .proxy
+                        .as_ref()
+                        .is_some_and(|proxy| proxy.implementation_name == deployment.name)
+                })
+                .map(|other| other.name)
+                .collect::<Vec<_>>();
+            if !users.is_empty() && !config.force {
+                return Err(eyre!(
+                    "{} is the implementation of {}, use --force to remove it anyway",
+                    config.reference,
+                    users.join(", ")
+                ));
+            }
+            let path = manifest_path(deployment.chain_id, &deployment.name);
+            std::fs::remove_file(&path)
+                .map_err(|e| eyre!("Failed to remove {}: {e}", path.display()))?;
+            println!("Removed {} ({})", config.reference, path.display());
+        }
+    }
+    Ok(())
+}
+
+// Split a `Name@network` reference into the deployment name and the network
+pub fn parse_reference(reference: &str) -> (&str, Option<&str>) {
+    match reference.rsplit_once('@') {
+        Some((name, network)) => (name, Some(network)),
+        None => (reference, None),
+    }
+}
+
+// Chain id of a network profile of `solidity-deploy.toml`, a chain name or a
+// chain id
+pub fn network_chain_id(network: &str) -> Result<u64> {
+    match profile_chain_id(network)? {
+        Some(chain_id) => Ok(chain_id),
+        None => Ok(resolve_chain(network)?.chain_id),
+    }
+}
+
+// Deployment referred to as `Name@network`, or as `Name` on the chain the
+// command is connected to. A network other than the connected chain is
+// rejected, its address would mean another contract or none at all.
+pub fn resolve_deployment(reference: &str, connected: Option<u64>) -> Result<Deployment> {
+    let (name, network) = parse_reference(reference);
+    let chain_id = match (network, connected) {
+        (Some(network), connected) => {
+            let chain_id = network_chain_id(network)?;
+            if let Some(connected) = connected.filter(|connected| *connected != chain_id) {
+                return Err(eyre!(
+                    "{reference} is on chain {chain_id} but the RPC endpoint is on chain {connected}, select it with --network or --rpc-url"
+                ));
+            }
+            chain_id
+        }
+        (None, Some(connected)) => connected,
+        (None, None) => {
+            return Err(eyre!(
+                "{reference} needs a network, e.g. {reference}@sepolia"
+            ))
+        }
+    };
+    Deployment::load(chain_id, name)
+}
+
+// Alias of the account sending the transactions
+pub const DEPLOYER_ALIAS: &str = "deployer";
+
+// Span of every `@<name>` alias of a value, e.g. `[@Token,@Vault]`. An `@`
+// only starts an alias at the start of the value or of a list item, so
+// strings such as `me@example.com` are left alone.
+fn alias_spans(value: &str) -> Vec<(usize, usize)> {
+    let mut spans = vec![];
+    let mut rest_start = 0;
+    while let Some(offset) = value[rest_start..].find('@') {
+        let start = rest_start + offset;
+        let name_len = value[start + 1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(value.len() - start - 1);
+        let standalone = value[..start]
+            .chars()
+            .last()
+            .map_or(true, |c| matches!(c, '[' | '(' | ',' | ' ' | '"'));
+        if standalone && name_len > 0 {
+            spans.push((start, start + 1 + name_len));
+        }
+        rest_start = start + 1 + name_len;
+    }
+    spans
+}
+
+// Names of the `@<name>` aliases of a value
+pub fn aliases(value: &str) -> Vec<String> {
+    alias_spans(value)
+        .into_iter()
+        .map(|(start, end)| value[start + 1..end].to_owned())
+        .collect()
+}
+
+// Replace the `@<name>` aliases of a value with the addresses `resolve`
+// gives for their names
+pub fn resolve_aliases(
+    value: &str,
+    mut resolve: impl FnMut(&str) -> Result<Address>,
+) -> Result<String> {
+    let mut resolved = String::with_capacity(value.len());
+    let mut copied = 0;
+    for (start, end) in alias_spans(value) {
+        resolved.push_str(&value[copied..start]);
+        resolved.push_str(&format!("{:?}", resolve(&value[start + 1..end])?));
+        copied = end;
+    }
+    resolved.push_str(&value[copied..]);
+    Ok(resolved)
+}
+
+// Address an alias stands for on a chain: `@deployer` is the sending
+// account, when there is one, any other name the deployment recorded under
+// it on the chain
+pub fn resolve_alias(name: &str, chain_id: u64, deployer: Option<Address>) -> Result<Address> {
+    if name == DEPLOYER_ALIAS {
+        return deployer.with_context(|| {
+            format!("@{DEPLOYER_ALIAS} needs a signer, e.g. --private-key or --mnemonic")
+        });
+    }
+    Deployment::load(chain_id, name)
+        .map(|deployment| deployment.address)
+        .map_err(|e| eyre!("@{name} is not a deployment of chain {chain_id}: {e}"))
+}
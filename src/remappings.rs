@@ -0,0 +1,75 @@
+use ethers_solc::remappings::Remapping;
+use eyre::{eyre, Result};
+use std::path::Path;
+
+// Import remappings of a project, so `import "@openzeppelin/contracts/..."`
+// resolves. In order of precedence they come from `remappings.txt`, Foundry
+// style dependencies in `lib/` and npm packages in `node_modules/`.
+pub fn detect_remappings(base: &Path) -> Result<Vec<Remapping>> {
+    let mut remappings = vec![];
+
+    let remappings_file = base.join("remappings.txt");
+    if remappings_file.exists() {
+        let content = std::fs::read_to_string(&remappings_file)?;
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut remapping: Remapping = line
+                .parse()
+                .map_err(|e| eyre!("Invalid remapping {line:?} in {remappings_file:?}: {e}"))?;
+            if Path::new(&remapping.path).is_relative() {
+                remapping.path = base.join(&remapping.path).to_string_lossy().into_owned();
+            }
+            remappings.push(remapping);
+        }
+    }
+
+    let lib_dir = base.join("lib");
+    if lib_dir.is_dir() {
+        remappings.extend(Remapping::find_many(&lib_dir));
+    }
+
+    let node_modules = base.join("node_modules");
+    if node_modules.is_dir() {
+        for package in npm_packages(&node_modules)? {
+            remappings.push(Remapping {
+                context: None,
+                name: format!("{package}/"),
+                path: format!("{}/", node_modules.join(&package).display()),
+            });
+        }
+    }
+
+    // Earlier remappings win over later ones with the same prefix
+    let mut seen = std::collections::HashSet::new();
+    remappings.retain(|remapping| seen.insert((remapping.context.clone(), remapping.name.clone())));
+    Ok(remappings)
+}
+
+// Package names in `node_modules`, including scoped ones like `@openzeppelin/contracts`
+fn npm_packages(node_modules: &Path) -> Result<Vec<String>> {
+    let mut packages = vec![];
+    for entry in std::fs::read_dir(node_modules)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        if name.starts_with('@') {
+            for scoped in std::fs::read_dir(entry.path())? {
+                let scoped = scoped?;
+                if scoped.file_type()?.is_dir() {
+                    packages.push(format!("{name}/{}", scoped.file_name().to_string_lossy()));
+                }
+            }
+        } else {
+            packages.push(name);
+        }
+    }
+    packages.sort();
+    Ok(packages)
+}
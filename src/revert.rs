@@ -0,0 +1,92 @@
+use crate::abi_args::format_token;
+use crate::error::DeployError;
+use ethers::abi::{Abi, ParamType, Token};
+use ethers::types::U256;
+use ethers::utils::hex;
+use ethers_providers::MiddlewareError;
+
+// Selector of the `Error(string)` thrown by `require` and `revert("...")`
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+// Selector of the `Panic(uint256)` thrown by failed assertions, overflows, ...
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+// Human-readable reason for the data a call reverted with: the message of an
+// `Error(string)`, the meaning of a `Panic(uint256)` code or a custom error
+// of the given ABI with its arguments. Unknown data is printed as hex.
+pub fn decode_revert(data: &[u8], abi: Option<&Abi>) -> String {
+    if data.len() < 4 {
+        return match data.is_empty() {
+            true => "no reason".to_owned(),
+            false => format!("unknown error 0x{}", hex::encode(data)),
+        };
+    }
+    let (selector, args) = data.split_at(4);
+
+    if selector == ERROR_SELECTOR {
+        if let Ok(tokens) = ethers::abi::decode(&[ParamType::String], args) {
+            if let [Token::String(message)] = tokens.as_slice() {
+                return format!("{message:?}");
+            }
+        }
+    }
+    if selector == PANIC_SELECTOR {
+        if let Ok(tokens) = ethers::abi::decode(&[ParamType::Uint(256)], args) {
+            if let [Token::Uint(code)] = tokens.as_slice() {
+                return match code.bits() <= 8 {
+                    true => format!("panic 0x{:02x} ({})", code.low_u64(), panic_reason(*code)),
+                    false => format!("panic {code}"),
+                };
+            }
+        }
+    }
+    for error in abi.into_iter().flat_map(|abi| abi.errors()) {
+        if error.signature()[..4] != *selector {
+            continue;
+        }
+        if let Ok(tokens) = error.decode(args) {
+            let args = error
+                .inputs
+                .iter()
+                .zip(&tokens)
+                .map(|(param, token)| match param.name.is_empty() {
+                    true => format_token(token),
+                    false => format!("{}: {}", param.name, format_token(token)),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            return format!("{}({args})", error.name);
+        }
+    }
+    format!("unknown error 0x{}", hex::encode(data))
+}
+
+// Decoded revert reason of a failed node request, if the node returned the
+// data the call reverted with
+pub fn revert_reason<E: MiddlewareError>(error: &E, abi: Option<&Abi>) -> Option<String> {
+    let data = error.as_error_response()?.as_revert_data()?;
+    Some(decode_revert(&data, abi))
+}
+
+// Error for a failed node request, of the `DeployError` kind of the failure
+// and naming the revert reason when there is one, e.g. `Call withdraw failed:
+// execution reverted: InsufficientBalance(available: 0, required: 5)`
+pub fn revert_error<E: MiddlewareError>(error: E, abi: Option<&Abi>, what: &str) -> eyre::Report {
+    eyre::Report::new(DeployError::from_middleware(&error, abi)).wrap_err(format!("{what} failed"))
+}
+
+// Meaning of the codes the compiler panics with
+fn panic_reason(code: U256) -> &'static str {
+    match code.low_u64() {
+        0x00 => "generic compiler inserted panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "incorrectly encoded storage byte array",
+        0x31 => "pop on an empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "too much memory allocated",
+        0x51 => "call to an uninitialized function",
+        _ => "unknown panic code",
+    }
+}
@@ -0,0 +1,305 @@
+use crate::cli_config::RetryConfig;
+use crate::tx_store;
+use async_trait::async_trait;
+use ethers::core::rand::{thread_rng, Rng};
+use ethers::types::{TransactionReceipt, U256};
+use ethers_providers::{
+    Http, HttpClientError, JsonRpcClient, JsonRpcError, Middleware, PendingTransaction, Provider,
+    ProviderError, PubsubClient, RpcError, StreamExt, Ws, WsClientError,
+};
+use eyre::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::warn;
+
+// Longest wait between two attempts of a request
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+// Methods broadcasting a transaction. A request of them that timed out or
+// failed at a gateway may have reached the node already: sent again it fails
+// with `already known` or `nonce too low`, and the hash of the transaction
+// that was broadcast is lost.
+const SEND_METHODS: [&str; 2] = ["eth_sendRawTransaction", "eth_sendTransaction"];
+
+// Retry settings of the run, set once the command line is parsed
+static RETRY_CONFIG: OnceLock<RetryConfig> = OnceLock::new();
+
+// Retries left for the whole run, shared by every connection
+static RETRY_BUDGET: AtomicU32 = AtomicU32::new(0);
+
+pub type RpcProvider = Provider<RpcTransport>;
+
+pub fn init_retry(config: RetryConfig) {
+    RETRY_BUDGET.store(config.rpc_retry_budget, Ordering::SeqCst);
+    let _ = RETRY_CONFIG.set(config);
+}
+
+// Connect to an HTTP JSON-RPC endpoint. Requests failing with a transient
+// error are retried as configured with the `--rpc-retry*` flags.
+pub fn connect(url: &str) -> Result<RpcProvider> {
+    let config = RETRY_CONFIG.get().cloned().unwrap_or_default();
+    Ok(Provider::new(RpcTransport::Http(RetryHttp {
+        inner: Http::from_str(url)?,
+        config,
+    })))
+}
+
+// Connect to a websocket JSON-RPC endpoint, whose new blocks and logs are
+// subscribed to instead of polled for. Requests are not retried, a dropped
+// connection fails them.
+pub async fn connect_ws(url: &str) -> Result<RpcProvider> {
+    Ok(Provider::new(RpcTransport::Ws(Ws::connect(url).await?)))
+}
+
+// Connect to the websocket endpoint when one is given, otherwise to the HTTP
+// one, which is then polled for new blocks
+pub async fn connect_with_ws(url: &str, ws_url: Option<&str>) -> Result<RpcProvider> {
+    match ws_url {
+        Some(ws_url) => connect_ws(ws_url).await,
+        None => connect(url),
+    }
+}
+
+// Connection to a node, over HTTP or a websocket
+#[derive(Debug)]
+pub enum RpcTransport {
+    Http(RetryHttp),
+    Ws(Ws),
+}
+
+impl RpcTransport {
+    // Whether the node pushes new blocks and logs, otherwise they are polled
+    pub fn is_pubsub(&self) -> bool {
+        matches!(self, RpcTransport::Ws(_))
+    }
+}
+
+#[derive(Debug)]
+pub enum RpcTransportError {
+    Http(HttpClientError),
+    Ws(WsClientError),
+    // A subscription over HTTP
+    NotPubsub,
+}
+
+impl fmt::Display for RpcTransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcTransportError::Http(error) => error.fmt(f),
+            RpcTransportError::Ws(error) => error.fmt(f),
+            RpcTransportError::NotPubsub => {
+                write!(
+                    f,
+                    "Subscriptions need a websocket endpoint, set one with --ws-url"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RpcTransportError {}
+
+impl RpcError for RpcTransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            RpcTransportError::Http(error) => error.as_error_response(),
+            RpcTransportError::Ws(error) => error.as_error_response(),
+            RpcTransportError::NotPubsub => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            RpcTransportError::Http(error) => error.as_serde_error(),
+            RpcTransportError::Ws(error) => error.as_serde_error(),
+            RpcTransportError::NotPubsub => None,
+        }
+    }
+}
+
+impl From<RpcTransportError> for ProviderError {
+    fn from(error: RpcTransportError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(error))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for RpcTransport {
+    type Error = RpcTransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            RpcTransport::Http(http) => http
+                .request(method, params)
+                .await
+                .map_err(RpcTransportError::Http),
+            RpcTransport::Ws(ws) => ws
+                .request(method, params)
+                .await
+                .map_err(RpcTransportError::Ws),
+        }
+    }
+}
+
+impl PubsubClient for RpcTransport {
+    type NotificationStream = <Ws as PubsubClient>::NotificationStream;
+
+    fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, Self::Error> {
+        match self {
+            RpcTransport::Http(_) => Err(RpcTransportError::NotPubsub),
+            RpcTransport::Ws(ws) => ws.subscribe(id).map_err(RpcTransportError::Ws),
+        }
+    }
+
+    fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), Self::Error> {
+        match self {
+            RpcTransport::Http(_) => Err(RpcTransportError::NotPubsub),
+            RpcTransport::Ws(ws) => ws.unsubscribe(id).map_err(RpcTransportError::Ws),
+        }
+    }
+}
+
+// Wait for `confirmations` confirmations of a sent transaction, `None` when
+// it was dropped, and record the outcome in the transaction store. Over a
+// websocket the receipt is looked for on every block the node announces,
+// over HTTP it is polled at the provider's interval.
+pub async fn confirm(
+    provider: &RpcProvider,
+    pending_tx: PendingTransaction<'_, RpcTransport>,
+    confirmations: usize,
+) -> Result<Option<TransactionReceipt>, ProviderError> {
+    let hash = pending_tx.tx_hash();
+    let receipt = wait_for_confirmations(provider, pending_tx, confirmations).await?;
+    match &receipt {
+        Some(receipt) => tx_store::record_receipt(receipt),
+        None => tx_store::record_dropped(hash),
+    }
+    Ok(receipt)
+}
+
+async fn wait_for_confirmations(
+    provider: &RpcProvider,
+    pending_tx: PendingTransaction<'_, RpcTransport>,
+    confirmations: usize,
+) -> Result<Option<TransactionReceipt>, ProviderError> {
+    if !provider.as_ref().is_pubsub() {
+        return pending_tx.confirmations(confirmations).await;
+    }
+    let hash = pending_tx.tx_hash();
+    let mut blocks = provider.subscribe_blocks().await?;
+    let mut latest = provider.get_block_number().await?;
+    loop {
+        match provider.get_transaction_receipt(hash).await? {
+            Some(receipt) => {
+                if let Some(mined) = receipt.block_number {
+                    if latest + 1 >= mined + confirmations {
+                        return Ok(Some(receipt));
+                    }
+                }
+            }
+            None if provider.get_transaction(hash).await?.is_none() => return Ok(None),
+            None => {}
+        }
+        let block = blocks.next().await.ok_or_else(|| {
+            ProviderError::CustomError("Block subscription closed by the node".to_owned())
+        })?;
+        latest = block.number.unwrap_or(latest + 1);
+    }
+}
+
+// HTTP transport retrying timeouts, rate limits and gateway errors with
+// exponential backoff and jitter, until the retry budget of the run is spent
+#[derive(Debug)]
+pub struct RetryHttp {
+    inner: Http,
+    config: RetryConfig,
+}
+
+#[async_trait]
+impl JsonRpcClient for RetryHttp {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // Serialized once so the same parameters can be sent again
+        let params = serde_json::to_value(params).map_err(|err| HttpClientError::SerdeJson {
+            err,
+            text: String::new(),
+        })?;
+        let mut attempt = 0;
+        loop {
+            match self.inner.request(method, &params).await {
+                Err(error)
+                    if attempt < self.config.rpc_retries
+                        && is_transient(&error, SEND_METHODS.contains(&method))
+                        && self.take_retry() =>
+                {
+                    let delay = self.backoff(attempt);
+                    warn!("RPC {method} failed: {error}, retrying in {delay:?}");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl RetryHttp {
+    fn take_retry(&self) -> bool {
+        RETRY_BUDGET
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok()
+    }
+
+    // Exponential backoff with jitter, so clients failing together do not
+    // all retry at the same moment
+    fn backoff(&self, attempt: u32) -> Duration {
+        let base = Duration::from_millis(self.config.rpc_retry_backoff_ms)
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_BACKOFF);
+        base.mul_f64(thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+// Errors worth another attempt: the request did not reach the node, the node
+// is rate limiting or a gateway in front of it failed. A `broadcast` is only
+// retried when it surely did not reach the node.
+fn is_transient(error: &HttpClientError, broadcast: bool) -> bool {
+    match error {
+        HttpClientError::ReqwestError(error) => {
+            error.is_connect()
+                || error.status().is_some_and(|status| status.as_u16() == 429)
+                || (!broadcast
+                    && (error.is_timeout()
+                        || error
+                            .status()
+                            .is_some_and(|status| status.is_server_error())))
+        }
+        HttpClientError::JsonRpcError(error) => {
+            let message = error.message.to_lowercase();
+            error.code == 429
+                || error.code == -32005
+                || message.contains("rate limit")
+                || message.contains("too many requests")
+        }
+        // Gateways answer a 502 or 503 with a page instead of a JSON response
+        HttpClientError::SerdeJson { text, .. } => {
+            !broadcast && !text.trim_start().starts_with('{')
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::cli_config::SafeConfig;
+use ethers::abi::parse_abi;
+use ethers::contract::Contract;
+use ethers::prelude::{LocalWallet, Signer};
+use ethers::types::{Address, Bytes, H256, U256};
+use ethers::utils::to_checksum;
+use ethers_providers::Middleware;
+use eyre::{eyre, ContextCompat, Result};
+use serde::Serialize;
+use std::sync::Arc;
+
+// Proposals show up under this name in the Safe web app
+const ORIGIN: &str = "solidity-deploy";
+
+// Safe Transaction Service of the chains Safe hosts one for
+fn default_service_url(chain_id: u64) -> Option<&'static str> {
+    match chain_id {
+        1 => Some("https://safe-transaction-mainnet.safe.global"),
+        10 => Some("https://safe-transaction-optimism.safe.global"),
+        56 => Some("https://safe-transaction-bsc.safe.global"),
+        100 => Some("https://safe-transaction-gnosis-chain.safe.global"),
+        137 => Some("https://safe-transaction-polygon.safe.global"),
+        8453 => Some("https://safe-transaction-base.safe.global"),
+        42161 => Some("https://safe-transaction-arbitrum.safe.global"),
+        11155111 => Some("https://safe-transaction-sepolia.safe.global"),
+        _ => None,
+    }
+}
+
+// Body of `POST /api/v1/safes/<safe>/multisig-transactions/`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Proposal {
+    to: String,
+    value: String,
+    data: Bytes,
+    operation: u8,
+    safe_tx_gas: String,
+    base_gas: String,
+    gas_price: String,
+    gas_token: String,
+    refund_receiver: String,
+    nonce: u64,
+    contract_transaction_hash: H256,
+    sender: String,
+    signature: Bytes,
+    origin: &'static str,
+}
+
+// Propose a call of `to` to the Safe of `config`, signed by `wallet`, and
+// return its Safe transaction hash. The owners confirm and execute it in the
+// Safe web app; the call gets no gas refund, the executor pays for it.
+pub async fn propose<M: Middleware + 'static>(
+    client: Arc<M>,
+    wallet: &LocalWallet,
+    config: &SafeConfig,
+    to: Address,
+    value: U256,
+    data: Bytes,
+) -> Result<H256>
+where
+    M::Error: 'static,
+{
+    let safe_address = config.safe.context("--safe is required to propose")?;
+    let chain_id = client.get_chainid().await?.as_u64();
+    let service_url = match &config.safe_service_url {
+        Some(url) => url.trim_end_matches('/').to_owned(),
+        None => default_service_url(chain_id)
+            .with_context(|| {
+                format!("No Safe Transaction Service known for chain {chain_id}, use --safe-service-url")
+            })?
+            .to_owned(),
+    };
+    if client.get_code(safe_address, None).await?.is_empty() {
+        return Err(eyre!("No Safe deployed at {safe_address:?}"));
+    }
+
+    let safe = Contract::new(
+        safe_address,
+        parse_abi(&[
+            "function nonce() view returns (uint256)",
+            "function isOwner(address) view returns (bool)",
+            "function getTransactionHash(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,uint256) view returns (bytes32)",
+        ])?,
+        client,
+    );
+    let sender = wallet.address();
+    let owner: bool = safe.method("isOwner", sender)?.call().await?;
+    if !owner {
+        println!(
+            "WARNING: {sender:?} is not an owner of the Safe, the proposal is only accepted from a delegate"
+        );
+    }
+    let nonce = match config.safe_nonce {
+        Some(nonce) => nonce,
+        None => safe.method::<_, U256>("nonce", ())?.call().await?,
+    };
+
+    // The Safe hashes its own transactions, which keeps the EIP-712 domain
+    // right for every Safe version
+    let safe_tx_hash: H256 = safe
+        .method::<_, [u8; 32]>(
+            "getTransactionHash",
+            (
+                to,
+                value,
+                data.clone(),
+                0u8,
+                U256::zero(),
+                U256::zero(),
+                U256::zero(),
+                Address::zero(),
+                Address::zero(),
+                nonce,
+            ),
+        )?
+        .call()
+        .await?
+        .into();
+    let signature = wallet.sign_hash(safe_tx_hash)?;
+
+    let proposal = Proposal {
+        to: to_checksum(&to, None),
+        value: value.to_string(),
+        data,
+        operation: 0,
+        safe_tx_gas: "0".to_owned(),
+        base_gas: "0".to_owned(),
+        gas_price: "0".to_owned(),
+        gas_token: to_checksum(&Address::zero(), None),
+        refund_receiver: to_checksum(&Address::zero(), None),
+        nonce: nonce.as_u64(),
+        contract_transaction_hash: safe_tx_hash,
+        sender: to_checksum(&sender, None),
+        signature: signature.to_vec().into(),
+        origin: ORIGIN,
+    };
+    let url = format!(
+        "{service_url}/api/v1/safes/{}/multisig-transactions/",
+        to_checksum(&safe_address, None)
+    );
+    let response = reqwest::Client::new()
+        .post(&url)
+        .json(&proposal)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(eyre!(
+            "Safe Transaction Service rejected the proposal ({status}): {}",
+            response.text().await.unwrap_or_default()
+        ));
+    }
+
+    println!("{}", "=".repeat(80));
+    println!("PROPOSED to Safe {safe_address:?} with nonce {nonce}");
+    println!("Safe transaction hash: {safe_tx_hash:?}");
+    println!("Nothing was sent, the Safe owners confirm and execute it in the Safe web app");
+    Ok(safe_tx_hash)
+}
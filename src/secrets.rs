@@ -0,0 +1,239 @@
+use crate::cli_config::{SecretsCommand, SecretsConfig};
+use crate::keystore::read_password;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use ethers::core::rand::{thread_rng, RngCore};
+use ethers::utils::hex;
+use eyre::{eyre, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use zeroize::Zeroizing;
+
+// Network profile values of this form are read from the secrets store
+pub const SECRET_PREFIX: &str = "secret:";
+// Cost of deriving the key from the passphrase, scrypt's recommended 2^15
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+// Entry point of the `secrets` subcommand
+pub fn secrets(config: SecretsConfig) -> Result<()> {
+    let mut store = SecretStore::load()?;
+    match config.command {
+        SecretsCommand::Set(config) => {
+            let value = read_password(&format!("Value of secret {}: ", config.name))?;
+            if value.is_empty() {
+                return Err(eyre!("Secret {} cannot be empty", config.name));
+            }
+            let key = store.unlock(true)?;
+            store.insert(&key, &config.name, &value)?;
+            let path = store.save()?;
+            println!("Stored secret {} in {}", config.name, path.display());
+        }
+        SecretsCommand::Get(config) => {
+            let key = store.unlock(false)?;
+            println!("{}", *store.get(&key, &config.name)?);
+        }
+        SecretsCommand::List => {
+            for name in store.secrets.keys() {
+                println!("{name}");
+            }
+        }
+        SecretsCommand::Remove(config) => {
+            store
+                .secrets
+                .remove(&config.name)
+                .with_context(|| format!("No secret named {}", config.name))?;
+            let path = store.save()?;
+            println!("Removed secret {} from {}", config.name, path.display());
+        }
+    }
+    Ok(())
+}
+
+// Store unlocked by the first `secret:` value of the run, so the passphrase is
+// asked for once
+static UNLOCKED: Mutex<Option<UnlockedStore>> = Mutex::new(None);
+
+// Value parser of the flags that may hold secrets, e.g. `--private-key`. A
+// `secret:<name>` value, given on the command line or by a profile, is
+// decrypted into the parsed flag only, the environment holding nothing but
+// the reference, so child processes such as hooks never see the secret.
+pub fn parse_secret(value: &str) -> Result<String, String> {
+    let mut store = UNLOCKED.lock().unwrap_or_else(PoisonError::into_inner);
+    resolve_secret(value.to_owned(), &mut store).map_err(|e| e.to_string())
+}
+
+// Resolve a `secret:<name>` network profile value, other values are returned
+// as is. The store is unlocked once per run.
+pub fn resolve_secret(value: String, store: &mut Option<UnlockedStore>) -> Result<String> {
+    let Some(name) = value.strip_prefix(SECRET_PREFIX) else {
+        return Ok(value);
+    };
+    if store.is_none() {
+        let mut secrets = SecretStore::load()?;
+        let key = secrets.unlock(false)?;
+        *store = Some(UnlockedStore { secrets, key });
+    }
+    let store = store.as_ref().expect("unlocked above");
+    Ok(store.secrets.get(&store.key, name)?.to_string())
+}
+
+pub struct UnlockedStore {
+    secrets: SecretStore,
+    key: Zeroizing<[u8; 32]>,
+}
+
+// Secrets encrypted with AES-256-GCM under a key derived from a passphrase
+// with scrypt, in `secrets.json` of the user's config directory. Every
+// secret has its own nonce and is bound to its name.
+#[derive(Serialize, Deserialize, Default)]
+struct SecretStore {
+    // Salt of the key derivation, set when the first secret is stored
+    #[serde(default)]
+    salt: Option<String>,
+    #[serde(default)]
+    secrets: BTreeMap<String, EncryptedSecret>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedSecret {
+    nonce: String,
+    ciphertext: String,
+}
+
+// Location of the store, `SECRETS_FILE` overriding the user's config directory
+fn store_path() -> Result<PathBuf> {
+    if let Some(path) = std::env::var_os("SECRETS_FILE") {
+        return Ok(PathBuf::from(path));
+    }
+    Ok(dirs::config_dir()
+        .context("No config directory for the secrets store, set SECRETS_FILE")?
+        .join("solidity-deploy")
+        .join("secrets.json"))
+}
+
+impl SecretStore {
+    fn load() -> Result<Self> {
+        let path = store_path()?;
+        if !path.exists() {
+            return Ok(SecretStore::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| eyre!("Failed to read secrets store {}: {e}", path.display()))?;
+        serde_json::from_str(&content)
+            .map_err(|e| eyre!("Invalid secrets store {}: {e}", path.display()))
+    }
+
+    fn save(&self) -> Result<PathBuf> {
+        let path = store_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Written to a file only the owner can read, then moved in place, so
+        // the store is never readable by others, not even for a moment
+        let temporary = path.with_extension("json.tmp");
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&temporary)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&temporary, &path)?;
+        Ok(path)
+    }
+
+    // Derive the key from the passphrase, taken from `SECRETS_PASSPHRASE` or
+    // prompted for. A new store gets a salt and its passphrase is confirmed,
+    // an existing one checks the passphrase against a stored secret.
+    fn unlock(&mut self, create: bool) -> Result<Zeroizing<[u8; 32]>> {
+        let passphrase = match std::env::var("SECRETS_PASSPHRASE") {
+            Ok(passphrase) => Zeroizing::new(passphrase),
+            Err(_) => read_password("Secrets store passphrase: ")?,
+        };
+        let salt = match &self.salt {
+            Some(salt) => hex::decode(salt)?,
+            None if create => {
+                if std::env::var_os("SECRETS_PASSPHRASE").is_none()
+                    && *read_password("Confirm the passphrase: ")? != *passphrase
+                {
+                    return Err(eyre!("Passphrases do not match"));
+                }
+                let mut salt = vec![0u8; 32];
+                thread_rng().fill_bytes(&mut salt);
+                self.salt = Some(hex::encode(&salt));
+                salt
+            }
+            None => {
+                return Err(eyre!(
+                    "The secrets store is empty, add secrets with `secrets set`"
+                ))
+            }
+        };
+
+        let mut key = Zeroizing::new([0u8; 32]);
+        let params = scrypt::Params::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P, key.len())
+            .map_err(|e| eyre!("Invalid scrypt parameters: {e}"))?;
+        scrypt::scrypt(passphrase.as_bytes(), &salt, &params, &mut key[..])
+            .map_err(|e| eyre!("Failed to derive the secrets key: {e}"))?;
+        if let Some(name) = self.secrets.keys().next() {
+            self.get(&key, name)
+                .map_err(|_| eyre!("Wrong passphrase for the secrets store"))?;
+        }
+        Ok(key)
+    }
+
+    fn insert(&mut self, key: &[u8; 32], name: &str, value: &str) -> Result<()> {
+        let mut nonce = [0u8; 12];
+        thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = cipher(key)?
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: value.as_bytes(),
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| eyre!("Failed to encrypt secret {name}"))?;
+        self.secrets.insert(
+            name.to_owned(),
+            EncryptedSecret {
+                nonce: hex::encode(nonce),
+                ciphertext: hex::encode(ciphertext),
+            },
+        );
+        Ok(())
+    }
+
+    fn get(&self, key: &[u8; 32], name: &str) -> Result<Zeroizing<String>> {
+        let secret = self
+            .secrets
+            .get(name)
+            .with_context(|| format!("No secret named {name}, add it with `secrets set {name}`"))?;
+        let nonce = hex::decode(&secret.nonce)?;
+        if nonce.len() != 12 {
+            return Err(eyre!("Secret {name} has a malformed nonce"));
+        }
+        let plaintext = cipher(key)?
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &hex::decode(&secret.ciphertext)?,
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| eyre!("Failed to decrypt secret {name}"))?;
+        Ok(Zeroizing::new(String::from_utf8(plaintext)?))
+    }
+}
+
+fn cipher(key: &[u8; 32]) -> Result<Aes256Gcm> {
+    Aes256Gcm::new_from_slice(key).map_err(|e| eyre!("Invalid secrets key: {e}"))
+}
@@ -0,0 +1,122 @@
+use crate::cli_config::{HashConfig, HashKind, SelectorsConfig};
+use crate::contract_abi::{
+    event_signature, function_signature, parse_event_signature, parse_signature,
+};
+use crate::decode::project_abis;
+use ethers::abi::Abi;
+use ethers::types::{H256, U256};
+use ethers::utils::{hex, keccak256};
+use eyre::{eyre, Result};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Entry point of the `selectors` subcommand: print the function selectors and
+// event topics of a contract, or look for selectors shared by different
+// functions anywhere in the project
+pub async fn selectors(config: SelectorsConfig) -> Result<()> {
+    let abis = project_abis().await?;
+
+    if let Some(contract_name) = &config.contract_name {
+        let (_, abi) = abis
+            .iter()
+            .find(|(name, _)| name == contract_name)
+            .ok_or_else(|| eyre!("Contract {contract_name} not found in the project"))?;
+        println!("{}", "=".repeat(80));
+        println!("{contract_name}");
+        println!("{:<10}  FUNCTION", "SELECTOR");
+        for function in abi.functions() {
+            println!(
+                "0x{}  {}",
+                hex::encode(function.short_signature()),
+                function_signature(function)
+            );
+        }
+        println!("{:<66}  EVENT", "TOPIC0");
+        for event in abi.events() {
+            println!("{:?}  {}", event.signature(), event_signature(event));
+        }
+    }
+
+    if config.check_collisions {
+        check_collisions(&abis)?;
+    }
+    Ok(())
+}
+
+// Entry point of the `hash` subcommand. Signatures are parsed first, so
+// parameter names and spaces do not change the selector or topic.
+pub fn hash(config: HashConfig) -> Result<()> {
+    if config.hex && config.kind != HashKind::Keccak {
+        return Err(eyre!("--hex only applies to --kind keccak"));
+    }
+    match config.kind {
+        HashKind::Keccak => {
+            let data = match config.hex {
+                true => hex::decode(config.input.trim())
+                    .map_err(|e| eyre!("Invalid hex {:?}: {e}", config.input))?,
+                false => config.input.into_bytes(),
+            };
+            println!("{:?}", H256(keccak256(data)));
+        }
+        HashKind::Selector => {
+            let function = parse_signature(&config.input)?;
+            println!(
+                "0x{}  {}",
+                hex::encode(function.short_signature()),
+                function_signature(&function)
+            );
+        }
+        HashKind::Topic => {
+            let event = parse_event_signature(&config.input)?;
+            println!("{:?}  {}", event.signature(), event_signature(&event));
+        }
+        HashKind::Slot => {
+            let slot = U256::from(keccak256(config.input.as_bytes())) - 1;
+            let mut bytes = [0u8; 32];
+            slot.to_big_endian(&mut bytes);
+            println!("{:?}", H256(bytes));
+        }
+    }
+    Ok(())
+}
+
+// A proxy, or a diamond's facets, dispatch on the selector alone, so two
+// different functions sharing one make one of them unreachable. The same
+// function inherited by several contracts is not a collision.
+fn check_collisions(abis: &[(String, Abi)]) -> Result<()> {
+    let mut by_selector: BTreeMap<[u8; 4], BTreeMap<String, BTreeSet<&str>>> = BTreeMap::new();
+    for (contract_name, abi) in abis {
+        for function in abi.functions() {
+            by_selector
+                .entry(function.short_signature())
+                .or_default()
+                .entry(function_signature(function))
+                .or_default()
+                .insert(contract_name.as_str());
+        }
+    }
+
+    println!("{}", "=".repeat(80));
+    let collisions = by_selector
+        .iter()
+        .filter(|(_, signatures)| signatures.len() > 1)
+        .collect::<Vec<_>>();
+    for (selector, signatures) in &collisions {
+        println!("COLLISION 0x{}", hex::encode(selector));
+        for (signature, contracts) in *signatures {
+            let contracts = contracts.iter().copied().collect::<Vec<_>>().join(", ");
+            println!("  {signature} in {contracts}");
+        }
+    }
+    if !collisions.is_empty() {
+        return Err(eyre!(
+            "{} selector collision(s) in the project",
+            collisions.len()
+        ));
+    }
+    println!(
+        "No selector collisions among {} function selector(s) of {} contract(s)",
+        by_selector.len(),
+        abis.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use crate::cli_config::SignerConfig;
+use crate::error::DeployError;
+use crate::keystore::load_keystore;
+use ethers::signers::coins_bip39::English;
+use ethers::signers::{LocalWallet, MnemonicBuilder};
+use ethers::types::{Address, U256};
+use ethers_providers::Middleware;
+use eyre::{eyre, Result};
+
+/// Mnemonic Anvil derives its accounts from unless given another one
+pub const ANVIL_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+/// Derive an account of a BIP-39 mnemonic, either from an explicit derivation
+/// path or from the account index on the default `m/44'/60'/0'/0/{index}` path
+pub fn wallet_from_mnemonic(
+    mnemonic: &str,
+    derivation_path: Option<&str>,
+    account_index: u32,
+) -> Result<LocalWallet> {
+    let builder = MnemonicBuilder::<English>::default().phrase(mnemonic);
+    let builder = match derivation_path {
+        Some(path) => builder
+            .derivation_path(path)
+            .map_err(|e| eyre!("Invalid derivation path {path}: {e}"))?,
+        None => builder.index(account_index)?,
+    };
+    Ok(builder.build()?)
+}
+
+/// Parse a hex encoded private key, with or without the `0x` prefix
+pub fn wallet_from_private_key(private_key: &str) -> Result<LocalWallet> {
+    let wallet = private_key
+        .trim()
+        .parse::<LocalWallet>()
+        .map_err(|e| eyre!("Invalid private key: {e}"))?;
+    Ok(wallet)
+}
+
+/// Build the signer from the supplied credentials. A private key takes
+/// precedence over a keystore, which takes precedence over a mnemonic;
+/// `None` means no credentials were given.
+pub fn wallet_from_config(config: &SignerConfig) -> Result<Option<LocalWallet>> {
+    if let Some(private_key) = &config.private_key {
+        return wallet_from_private_key(private_key).map(Some);
+    }
+    if let Some(keystore) = &config.keystore {
+        return load_keystore(keystore, config.keystore_password.as_deref()).map(Some);
+    }
+    match &config.mnemonic {
+        Some(mnemonic) => wallet_from_mnemonic(
+            mnemonic,
+            config.derivation_path.as_deref(),
+            config.account_index,
+        )
+        .map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Refuse to go on with an account that could not pay for any transaction
+pub async fn ensure_funded<M: Middleware>(provider: &M, address: Address) -> Result<U256>
+where
+    M::Error: 'static,
+{
+    let balance = provider.get_balance(address, None).await?;
+    if balance.is_zero() {
+        return Err(DeployError::InsufficientFunds {
+            account: Some(address),
+        }
+        .into());
+    }
+    Ok(balance)
+}
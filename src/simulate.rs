@@ -0,0 +1,99 @@
+use crate::calls::{execute_calls, PlannedCall};
+use crate::create2::{self, create2_address, create2_transaction, init_code_hash, CREATE2_FACTORY};
+use crate::revert::revert_error;
+use crate::rpc;
+use ethers::abi::Abi;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, Eip1559TransactionRequest, H256};
+use ethers::utils::{get_contract_address, Anvil};
+use ethers_providers::Middleware;
+use eyre::{eyre, ContextCompat, Result};
+use std::time::Duration;
+
+// Rehearse a deployment and its follow-up calls on a throwaway Anvil fork of
+// the target chain. The deployer account is impersonated so the would-be
+// contract address matches the real nonce; nothing reaches the target chain.
+// With a salt, the contract is deployed through the CREATE2 proxy as the real
+// run would.
+pub async fn simulate_deployment(
+    endpoint: &str,
+    deployer: Address,
+    deploy_code: &Bytes,
+    salt: Option<H256>,
+    abi: &Abi,
+    calls: &[PlannedCall],
+) -> Result<()> {
+    println!("DRY RUN: simulating on a fork of {endpoint}");
+    let fork = Anvil::new().fork(endpoint).spawn();
+    let provider = rpc::connect(&fork.endpoint())?.interval(Duration::from_millis(10));
+    provider
+        .request::<_, ()>("anvil_impersonateAccount", [deployer])
+        .await?;
+
+    let (tx, predicted_address): (TypedTransaction, _) = match salt {
+        Some(salt) => {
+            let address = create2_address(CREATE2_FACTORY, salt, init_code_hash(deploy_code));
+            create2::ensure_deployable(&provider, address).await?;
+            println!("Would-be CREATE2 address: {address:?}");
+            (
+                create2_transaction(salt, deploy_code).from(deployer).into(),
+                address,
+            )
+        }
+        None => {
+            let nonce = provider.get_transaction_count(deployer, None).await?;
+            let address = get_contract_address(deployer, nonce);
+            println!("Would-be contract address: {address:?} (nonce {nonce})");
+            let tx = Eip1559TransactionRequest::new()
+                .from(deployer)
+                .data(deploy_code.clone());
+            (tx.into(), address)
+        }
+    };
+    provider
+        .call(&tx, None)
+        .await
+        .map_err(|e| revert_error(e, Some(abi), "Deployment"))?;
+    let gas_estimate = provider.estimate_gas(&tx, None).await?;
+
+    let receipt = provider
+        .send_transaction(tx, None)
+        .await?
+        .await?
+        .context("Simulated deployment dropped from mempool")?;
+    // A deployment through the proxy is a call, creating no contract itself
+    let address = match salt {
+        Some(_) => {
+            create2::ensure_deployed(&provider, predicted_address).await?;
+            predicted_address
+        }
+        None => receipt
+            .contract_address
+            .context("Simulated deployment created no contract")?,
+    };
+    println!(
+        "Deployment gas estimate: {gas_estimate}, gas used: {:?}",
+        receipt.gas_used
+    );
+    if address != predicted_address {
+        return Err(eyre!(
+            "Simulated contract address {address:?} does not match the predicted {predicted_address:?}"
+        ));
+    }
+
+    execute_calls(
+        &provider,
+        abi,
+        address,
+        calls,
+        Some(deployer),
+        None,
+        None,
+        1,
+        None,
+        None,
+    )
+    .await?;
+    println!("DRY RUN finished, nothing was broadcast to the target chain");
+    Ok(())
+}
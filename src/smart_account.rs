@@ -0,0 +1,284 @@
+use crate::cli_config::SmartAccountConfig;
+use crate::error::DeployError;
+use crate::fees::Fees;
+use crate::progress::Progress;
+use crate::revert::revert_error;
+use crate::rpc::{self, RpcProvider};
+use crate::units::format_eth;
+use ethers::abi::{parse_abi, Token};
+use ethers::contract::BaseContract;
+use ethers::prelude::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionReceipt, TransactionRequest, H160, H256, U256};
+use ethers::utils::keccak256;
+use ethers_providers::Middleware;
+use eyre::{eyre, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+// EntryPoint v0.6, at the same address on every chain
+const ENTRY_POINT_V06: Address = H160([
+    0x5f, 0xf1, 0x37, 0xd4, 0xb0, 0xfd, 0xcd, 0x49, 0xdc, 0xa3, 0x0c, 0x7c, 0xf5, 0x7e, 0x57, 0x8a,
+    0x02, 0x6d, 0x27, 0x89,
+]);
+// How often the bundler is asked whether the UserOperation was included
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Longest a UserOperation may wait for a bundle before giving up on it
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(300);
+
+// UserOperation of EntryPoint v0.6, serialized the way bundlers take it
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    pub sender: Address,
+    pub nonce: U256,
+    pub init_code: Bytes,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub paymaster_and_data: Bytes,
+    pub signature: Bytes,
+}
+
+impl UserOperation {
+    // Hash the account owner signs, as computed by `EntryPoint.getUserOpHash`
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> H256 {
+        let packed = ethers::abi::encode(&[
+            Token::Address(self.sender),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.init_code).to_vec()),
+            Token::FixedBytes(keccak256(&self.call_data).to_vec()),
+            Token::Uint(self.call_gas_limit),
+            Token::Uint(self.verification_gas_limit),
+            Token::Uint(self.pre_verification_gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::FixedBytes(keccak256(&self.paymaster_and_data).to_vec()),
+        ]);
+        keccak256(ethers::abi::encode(&[
+            Token::FixedBytes(keccak256(packed).to_vec()),
+            Token::Address(entry_point),
+            Token::Uint(chain_id.into()),
+        ]))
+        .into()
+    }
+
+    fn set_gas(&mut self, gas: &GasLimits) {
+        self.call_gas_limit = gas.call_gas_limit;
+        self.verification_gas_limit = gas.verification_gas_limit;
+        self.pre_verification_gas = gas.pre_verification_gas;
+    }
+}
+
+// Answer of `eth_estimateUserOperationGas`
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GasLimits {
+    call_gas_limit: U256,
+    verification_gas_limit: U256,
+    pre_verification_gas: U256,
+}
+
+// Answer of `pm_sponsorUserOperation`. Some paymasters estimate the gas
+// themselves, the others sign over the limits they were sent.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Sponsorship {
+    paymaster_and_data: Bytes,
+    #[serde(flatten)]
+    gas: Option<GasLimits>,
+}
+
+// Answer of `eth_getUserOperationReceipt` once the operation is included
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct UserOperationReceipt {
+    success: bool,
+    actual_gas_cost: U256,
+    receipt: TransactionReceipt,
+}
+
+// Have the smart account of `config` call `to` with `data`, through a
+// UserOperation signed by `wallet`, the account owner, and return the
+// receipt of the bundle transaction including it. The account must be
+// deployed already and take calls with `execute(address,uint256,bytes)`,
+// like the SimpleAccount of the reference implementation.
+pub async fn execute<M: Middleware>(
+    client: &M,
+    wallet: &LocalWallet,
+    config: &SmartAccountConfig,
+    fees: &Fees,
+    to: Address,
+    value: U256,
+    data: Bytes,
+) -> Result<TransactionReceipt>
+where
+    M::Error: 'static,
+{
+    let sender = config
+        .smart_account
+        .context("--smart-account is required to send a UserOperation")?;
+    let bundler_url = config
+        .bundler_url
+        .as_deref()
+        .context("--bundler-url is required to send a UserOperation")?;
+    let entry_point = config.entry_point.unwrap_or(ENTRY_POINT_V06);
+    let chain_id = client.get_chainid().await?.as_u64();
+    if client.get_code(sender, None).await?.is_empty() {
+        return Err(eyre!("No smart account deployed at {sender:?}"));
+    }
+
+    let bundler = rpc::connect(bundler_url)?;
+    let supported: Vec<Address> = bundler.request("eth_supportedEntryPoints", ()).await?;
+    if !supported.contains(&entry_point) {
+        return Err(eyre!(
+            "The bundler does not support EntryPoint {entry_point:?}, it supports {supported:?}"
+        ));
+    }
+
+    let entry_point_contract = BaseContract::from(parse_abi(&[
+        "function getNonce(address sender, uint192 key) view returns (uint256)",
+    ])?);
+    let call: TypedTransaction = TransactionRequest::new()
+        .to(entry_point)
+        .data(entry_point_contract.encode("getNonce", (sender, U256::zero()))?)
+        .into();
+    let nonce: U256 =
+        entry_point_contract.decode_output("getNonce", client.call(&call, None).await?)?;
+
+    let account = BaseContract::from(parse_abi(&[
+        "function execute(address dest, uint256 value, bytes func)",
+    ])?);
+    let (max_fee_per_gas, max_priority_fee_per_gas) = match *fees {
+        Fees::Legacy { gas_price } => (gas_price, gas_price),
+        Fees::Eip1559 {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        } => (max_fee_per_gas, max_priority_fee_per_gas),
+    };
+    let mut operation = UserOperation {
+        sender,
+        nonce,
+        init_code: Bytes::new(),
+        call_data: account.encode("execute", (to, value, data))?,
+        call_gas_limit: U256::zero(),
+        verification_gas_limit: U256::zero(),
+        pre_verification_gas: U256::zero(),
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        paymaster_and_data: Bytes::new(),
+        // Estimation needs a well-formed signature, not a valid one
+        signature: wallet.sign_hash(H256::zero())?.to_vec().into(),
+    };
+
+    let paymaster = match &config.paymaster_url {
+        Some(url) => Some(rpc::connect(url)?),
+        None => None,
+    };
+    let mut estimated = false;
+    if let Some(paymaster) = &paymaster {
+        let sponsorship = sponsor(paymaster, &operation, entry_point).await?;
+        operation.paymaster_and_data = sponsorship.paymaster_and_data;
+        if let Some(gas) = &sponsorship.gas {
+            operation.set_gas(gas);
+            estimated = true;
+        }
+    }
+    if !estimated {
+        let gas: GasLimits = bundler
+            .request("eth_estimateUserOperationGas", (&operation, entry_point))
+            .await
+            .map_err(|e| revert_error(e, None, "Gas estimation of the UserOperation"))?;
+        operation.set_gas(&gas);
+        // The paymaster signs over the gas limits, sponsor the estimated ones
+        if let Some(paymaster) = &paymaster {
+            operation.paymaster_and_data = sponsor(paymaster, &operation, entry_point)
+                .await?
+                .paymaster_and_data;
+        }
+    }
+
+    let hash = operation.hash(entry_point, chain_id);
+    operation.signature = wallet.sign_message(hash.as_bytes()).await?.to_vec().into();
+
+    println!("{}", "=".repeat(80));
+    println!("USEROPERATION from smart account {sender:?} with nonce {nonce}");
+    println!("EntryPoint: {entry_point:?}");
+    println!(
+        "Gas limits: call {}, verification {}, pre-verification {}",
+        operation.call_gas_limit, operation.verification_gas_limit, operation.pre_verification_gas
+    );
+    if operation.paymaster_and_data.is_empty() {
+        println!("Paid by the smart account");
+    } else {
+        println!(
+            "Sponsored by paymaster {:?}",
+            Address::from_slice(&operation.paymaster_and_data[..20])
+        );
+    }
+
+    let submitted: H256 = bundler
+        .request("eth_sendUserOperation", (&operation, entry_point))
+        .await
+        .map_err(|e| revert_error(e, None, "Submission of the UserOperation"))?;
+    println!("UserOperation hash: {submitted:?}");
+
+    let receipt = wait_for_inclusion(&bundler, submitted).await?;
+    println!(
+        "Included in transaction {:?}, actual cost {}",
+        receipt.receipt.transaction_hash,
+        format_eth(receipt.actual_gas_cost)
+    );
+    if !receipt.success {
+        return Err(eyre!(
+            "UserOperation {submitted:?} reverted in transaction {:?}",
+            receipt.receipt.transaction_hash
+        ));
+    }
+    Ok(receipt.receipt)
+}
+
+async fn sponsor(
+    paymaster: &RpcProvider,
+    operation: &UserOperation,
+    entry_point: Address,
+) -> Result<Sponsorship> {
+    let sponsorship: Sponsorship = paymaster
+        .request("pm_sponsorUserOperation", (operation, entry_point))
+        .await
+        .map_err(|e| revert_error(e, None, "Sponsorship of the UserOperation"))?;
+    if sponsorship.paymaster_and_data.len() < 20 {
+        return Err(eyre!(
+            "The paymaster answered without a paymaster address: {}",
+            sponsorship.paymaster_and_data
+        ));
+    }
+    Ok(sponsorship)
+}
+
+// Poll the bundler until the UserOperation is included in a bundle
+async fn wait_for_inclusion(bundler: &RpcProvider, hash: H256) -> Result<UserOperationReceipt> {
+    let _progress = Progress::spinner(format!("Waiting for UserOperation {hash:?} to be bundled"));
+    let started = Instant::now();
+    loop {
+        let receipt: Option<UserOperationReceipt> = bundler
+            .request("eth_getUserOperationReceipt", [hash])
+            .await?;
+        if let Some(receipt) = receipt {
+            return Ok(receipt);
+        }
+        if started.elapsed() > RECEIPT_TIMEOUT {
+            return Err(DeployError::Timeout {
+                message: format!(
+                    "UserOperation {hash:?} was not bundled within {}s",
+                    RECEIPT_TIMEOUT.as_secs()
+                ),
+            }
+            .into());
+        }
+        tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,66 @@
+use crate::cli_config::{SnapshotCommand, SnapshotConfig};
+use crate::rpc;
+use ethers::types::U256;
+use ethers_providers::Middleware;
+use eyre::{eyre, Result};
+
+// Entry point of the `snapshot` subcommand
+pub async fn snapshot(config: SnapshotConfig) -> Result<()> {
+    match config.command {
+        SnapshotCommand::Take(config) => {
+            let provider = rpc::connect(&config.rpc_url)?;
+            let snapshot = Snapshot::take(&provider).await?;
+            println!("Snapshot id: {}", snapshot.id());
+        }
+        SnapshotCommand::Revert(config) => {
+            let provider = rpc::connect(&config.rpc_url)?;
+            let mut snapshot = Snapshot::from_id(config.id);
+            snapshot.revert(&provider).await?;
+            println!("Reverted to snapshot {}", config.id);
+            println!("Snapshot id: {}", snapshot.id());
+        }
+    }
+    Ok(())
+}
+
+// Saved state of a local chain, taken with `evm_snapshot`. Reverting to it
+// takes a new snapshot right away, so a test can deploy, change state and roll
+// back as often as it needs without respawning the node.
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot {
+    id: U256,
+}
+
+impl Snapshot {
+    pub async fn take<M: Middleware>(client: &M) -> Result<Snapshot>
+    where
+        M::Error: 'static,
+    {
+        let id = client.snapshot().await?;
+        Ok(Snapshot { id })
+    }
+
+    // A snapshot taken earlier, e.g. by `snapshot take`
+    pub fn from_id(id: U256) -> Snapshot {
+        Snapshot { id }
+    }
+
+    pub fn id(&self) -> U256 {
+        self.id
+    }
+
+    // Roll the chain back to the snapshot. The node drops the snapshot (and
+    // any taken after it) on reverting, so a new one of the same state
+    // replaces it.
+    pub async fn revert<M: Middleware>(&mut self, client: &M) -> Result<()>
+    where
+        M::Error: 'static,
+    {
+        client
+            .revert_to_snapshot(self.id)
+            .await
+            .map_err(|e| eyre!("Failed to revert to snapshot {}: {e}", self.id))?;
+        self.id = client.snapshot().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use crate::cli_config::{CompileConfig, CompilerConfig};
+use crate::compiler::{build_project, contracts_path};
+use ethers_solc::{CompilerInput, CompilerOutput, Solc};
+use eyre::{eyre, Result};
+use std::path::Path;
+
+// Compile a standard-JSON input exactly as given, e.g. one emitted with
+// `--emit-standard-json` or handed over by another tool, and write solc's
+// output unchanged next to the artifacts
+pub fn compile_standard_json(input_path: &Path, config: &CompileConfig) -> Result<()> {
+    let content = std::fs::read_to_string(input_path)
+        .map_err(|e| eyre!("Failed to read standard-JSON input {input_path:?}: {e}"))?;
+    let input: CompilerInput = serde_json::from_str(&content)
+        .map_err(|e| eyre!("Failed to parse standard-JSON input {input_path:?}: {e}"))?;
+
+    let solc = match &config.solc_version {
+        Some(version) => Solc::find_or_install_svm_version(version)?,
+        None => Solc::default(),
+    };
+    println!(
+        "Compiling {} with solc {}",
+        input_path.display(),
+        solc.version()?
+    );
+    let raw_output = solc.compile_output(&input)?;
+    let output: CompilerOutput = serde_json::from_slice(&raw_output)?;
+    if output.has_error() {
+        return Err(eyre!(
+            "Compiling {input_path:?} failed: {:?}",
+            output.errors
+        ));
+    }
+
+    let stem = input_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "input".to_owned());
+    let output_path = config.output_dir.join(format!("{stem}.output.json"));
+    std::fs::create_dir_all(&config.output_dir)?;
+    std::fs::write(&output_path, raw_output)
+        .map_err(|e| eyre!("Failed to write {output_path:?}: {e}"))?;
+    println!("Compiler output written to {}", output_path.display());
+    Ok(())
+}
+
+// Write the standard-JSON input of the project: its sources, remappings and
+// the compiler settings the other commands compile with
+pub fn emit_standard_json(path: &Path, compiler: &CompilerConfig) -> Result<()> {
+    let project = build_project(contracts_path(), None, compiler)?;
+    let sources = project.paths.read_input_files()?;
+    // Solidity and Yul sources need an input each
+    let inputs = CompilerInput::with_sources(sources)
+        .into_iter()
+        .map(|input| {
+            input
+                .settings(project.solc_config.settings.clone())
+                .with_remappings(project.paths.remappings.clone())
+        })
+        .collect::<Vec<_>>();
+    let json = match inputs.as_slice() {
+        [input] => serde_json::to_string_pretty(input)?,
+        inputs => serde_json::to_string_pretty(inputs)?,
+    };
+    std::fs::write(path, json).map_err(|e| eyre!("Failed to write {path:?}: {e}"))?;
+    println!("Standard-JSON input written to {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use crate::calls::{send_call, PlannedCall};
+use crate::contract_abi::function_signature;
+use crate::deployer::DeployContext;
+use async_trait::async_trait;
+use ethers::abi::{parse_abi, Abi};
+use ethers::types::Address;
+use eyre::{eyre, ContextCompat, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+// A custom step of a deployment plan, run by the `DeployStep` registered for
+// its `kind`. The other fields are the step's arguments, e.g.
+//
+// [[steps]]
+// kind = "call"
+// target = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+// method = "register(address,string)"
+// args = ["@Vault", "vault"]
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlannedStep {
+    pub kind: String,
+    #[serde(flatten)]
+    pub args: Map<String, Value>,
+}
+
+// A kind of plan step, such as registering the deployed contracts in an
+// on-chain registry. Steps run in plan order once every contract is
+// deployed, with `@<name>` in their arguments replaced by addresses.
+// Transactions are journaled under `journal_step`, suffixed when a step
+// sends several, so a resumed run skips those already confirmed.
+#[async_trait]
+pub trait DeployStep: Send + Sync {
+    // Value of `kind` selecting the step
+    fn kind(&self) -> &'static str;
+
+    // Check the arguments before anything of the plan is sent. `@<name>`
+    // references are not resolved yet.
+    fn validate(&self, _args: &Map<String, Value>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn run(
+        &self,
+        context: &mut DeployContext,
+        journal_step: &str,
+        args: &Map<String, Value>,
+    ) -> Result<()>;
+}
+
+// Step kinds the plan executor knows: the built-in ones, and custom ones
+// added with `register`, which replaces a step of the same kind
+#[derive(Clone)]
+pub struct StepRegistry {
+    steps: BTreeMap<&'static str, Arc<dyn DeployStep>>,
+}
+
+impl Default for StepRegistry {
+    fn default() -> Self {
+        let mut registry = StepRegistry {
+            steps: BTreeMap::new(),
+        };
+        registry.register(CallStep);
+        registry
+    }
+}
+
+impl StepRegistry {
+    pub fn register(&mut self, step: impl DeployStep + 'static) {
+        self.steps.insert(step.kind(), Arc::new(step));
+    }
+
+    pub fn get(&self, kind: &str) -> Result<Arc<dyn DeployStep>> {
+        self.steps.get(kind).cloned().ok_or_else(|| {
+            eyre!(
+                "Unknown plan step kind {kind:?}, known kinds: {}",
+                self.steps.keys().copied().collect::<Vec<_>>().join(", ")
+            )
+        })
+    }
+}
+
+// Call a contract outside the plan, with the method given by its signature
+// since there is no ABI for it
+struct CallStep;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CallStepArgs {
+    target: String,
+    // e.g. `register(address,string)`
+    method: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+impl CallStep {
+    fn parse(args: &Map<String, Value>) -> Result<(CallStepArgs, Abi, String)> {
+        let args: CallStepArgs = serde_json::from_value(Value::Object(args.clone()))
+            .map_err(|e| eyre!("Invalid call step: {e}"))?;
+        let method = args.method.trim();
+        let method = method.strip_prefix("function ").unwrap_or(method);
+        let abi = parse_abi(&[&format!("function {method}")])
+            .map_err(|e| eyre!("Invalid method {:?} of a call step: {e}", args.method))?;
+        let signature = abi
+            .functions()
+            .next()
+            .map(function_signature)
+            .context("Call step without a method")?;
+        Ok((args, abi, signature))
+    }
+}
+
+#[async_trait]
+impl DeployStep for CallStep {
+    fn kind(&self) -> &'static str {
+        "call"
+    }
+
+    fn validate(&self, args: &Map<String, Value>) -> Result<()> {
+        Self::parse(args).map(|_| ())
+    }
+
+    async fn run(
+        &self,
+        context: &mut DeployContext,
+        journal_step: &str,
+        args: &Map<String, Value>,
+    ) -> Result<()> {
+        let (args, abi, signature) = Self::parse(args)?;
+        let target: Address = args
+            .target
+            .parse()
+            .map_err(|e| eyre!("Invalid target {:?} of a call step: {e}", args.target))?;
+        let call = PlannedCall {
+            method: signature,
+            args: args.args,
+            value: args.value,
+            expect: None,
+            timelock: None,
+        };
+        println!("CALL {} on {target:?}", call.method);
+        send_call(
+            context.client.as_ref(),
+            &abi,
+            target,
+            &call,
+            journal_step,
+            context.from,
+            Some(&context.fees),
+            Some(&mut context.journal),
+            context.confirmations,
+            context.nonce.as_mut(),
+            context.fee_bump.as_ref(),
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,357 @@
+use crate::cli_config::{parse_u256, CompilerConfig, StorageConfig};
+use crate::compiler::{compile, contracts_path};
+use crate::manifest::Deployment;
+use crate::registry::{parse_reference, resolve_deployment};
+use crate::rpc::{self, RpcProvider};
+use crate::storage_layout::find_storage_layout;
+use ethers::abi::param_type::Reader;
+use ethers::abi::token::{LenientTokenizer, Tokenizer};
+use ethers::abi::{ParamType, Token};
+use ethers::types::{Address, BlockId, H256, I256, U256};
+use ethers::utils::{hex, keccak256};
+use ethers_providers::Middleware;
+use ethers_solc::artifacts::{Storage, StorageLayout, StorageType};
+use eyre::{eyre, ContextCompat, Result};
+
+// Longest `string` or `bytes` value read from storage, in bytes
+const MAX_DYNAMIC_LENGTH: usize = 64 * 1024;
+
+// Entry point of the `storage` subcommand: decode the state variables of a
+// deployed contract with its storage layout, or print a raw slot
+pub async fn storage(config: StorageConfig) -> Result<()> {
+    let provider = rpc::connect(&config.rpc_url)?;
+    let (address, layout) = resolve_layout(&provider, &config).await?;
+    let reader = StorageReader {
+        provider: &provider,
+        address,
+        block: config.block.map(BlockId::from),
+    };
+
+    if let Some(slot) = config.slot {
+        println!("slot {slot}: {:?}", H256(reader.read(slot).await?));
+        return Ok(());
+    }
+
+    let layout = layout.with_context(|| {
+        format!(
+            "No storage layout recorded for {}, read raw slots with --slot",
+            config.contract_name
+        )
+    })?;
+    match &config.variable {
+        Some(name) => {
+            let variable = layout
+                .storage
+                .iter()
+                .find(|variable| variable.label == *name)
+                .with_context(|| {
+                    format!("{} has no state variable {name}", config.contract_name)
+                })?;
+            let (type_id, slot, offset) = locate(&layout, variable, &config.keys)?;
+            let value = reader.decode(&layout, &type_id, slot, offset).await?;
+            let keys = config
+                .keys
+                .iter()
+                .map(|key| format!("[{key}]"))
+                .collect::<String>();
+            println!("{name}{keys} = {value}");
+            println!(
+                "  type {}, slot {slot}, offset {offset}",
+                storage_type(&layout, &type_id)?.label
+            );
+        }
+        None => {
+            println!("{}", "=".repeat(80));
+            println!(
+                "{:<6} {:>6} {:<24} {:<24} VALUE",
+                "SLOT", "OFFSET", "TYPE", "NAME"
+            );
+            for variable in &layout.storage {
+                let slot = parse_u256(&variable.slot).map_err(|e| eyre!(e))?;
+                let offset = variable.offset as usize;
+                let value = reader
+                    .decode(&layout, &variable.storage_type, slot, offset)
+                    .await?;
+                println!(
+                    "{:<6} {:>6} {:<24} {:<24} {value}",
+                    variable.slot,
+                    variable.offset,
+                    storage_type(&layout, &variable.storage_type)?.label,
+                    variable.label
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+// Address and storage layout of the contract, from the deployment manifest
+// or, with an explicit address, from the compiled project
+async fn resolve_layout(
+    provider: &RpcProvider,
+    config: &StorageConfig,
+) -> Result<(Address, Option<StorageLayout>)> {
+    match config.address {
+        Some(address) => {
+            // Compiler settings do not affect the storage layout
+            let output = compile(contracts_path(), None, &CompilerConfig::default()).await?;
+            let contract_name = parse_reference(&config.contract_name).0;
+            Ok((address, find_storage_layout(&output, contract_name)))
+        }
+        None => {
+            let chain_id = provider.get_chainid().await?.as_u64();
+            let deployment = resolve_deployment(&config.contract_name, Some(chain_id))?;
+            // A proxy's storage follows the layout of its implementation
+            let layout = match &deployment.proxy {
+                Some(proxy) => {
+                    Deployment::load(chain_id, &proxy.implementation_name)?.storage_layout
+                }
+                None => deployment.storage_layout,
+            };
+            Ok((deployment.address, layout))
+        }
+    }
+}
+
+// Follow mapping keys, array indices and struct members from a state variable
+// to the type, slot and offset of the value they select
+fn locate(
+    layout: &StorageLayout,
+    variable: &Storage,
+    keys: &[String],
+) -> Result<(String, U256, usize)> {
+    let mut type_id = variable.storage_type.clone();
+    let mut slot = parse_u256(&variable.slot).map_err(|e| eyre!(e))?;
+    let mut offset = variable.offset as usize;
+    for key in keys {
+        let current = storage_type(layout, &type_id)?;
+        match (
+            current.encoding.as_str(),
+            base_type(current),
+            members(current)?,
+        ) {
+            ("mapping", _, _) => {
+                let key_type = current.key.as_deref().context("Mapping without key type")?;
+                let mut preimage = encode_key(&storage_type(layout, key_type)?.label, key)?;
+                preimage.extend_from_slice(&word(slot));
+                slot = U256::from_big_endian(&keccak256(preimage));
+                offset = 0;
+                type_id = current
+                    .value
+                    .clone()
+                    .context("Mapping without value type")?;
+            }
+            ("dynamic_array", Some(base), _) => {
+                let first = U256::from_big_endian(&keccak256(word(slot)));
+                (slot, offset) = element(layout, first, &base, parse_index(key)?)?;
+                type_id = base;
+            }
+            ("inplace", Some(base), _) => {
+                (slot, offset) = element(layout, slot, &base, parse_index(key)?)?;
+                type_id = base;
+            }
+            ("inplace", None, Some(members)) => {
+                let member = members
+                    .iter()
+                    .find(|member| member.label == *key)
+                    .with_context(|| format!("{} has no member {key}", current.label))?;
+                slot += parse_u256(&member.slot).map_err(|e| eyre!(e))?;
+                offset = member.offset as usize;
+                type_id = member.storage_type.clone();
+            }
+            _ => return Err(eyre!("{} cannot be indexed with {key}", current.label)),
+        }
+    }
+    Ok((type_id, slot, offset))
+}
+
+// Slot and offset of an array element. Elements smaller than a slot are packed.
+fn element(
+    layout: &StorageLayout,
+    first_slot: U256,
+    base: &str,
+    index: U256,
+) -> Result<(U256, usize)> {
+    let size = type_size(storage_type(layout, base)?)?;
+    if size <= 32 {
+        let per_slot = U256::from(32 / size);
+        Ok((
+            first_slot + index / per_slot,
+            (index % per_slot).as_usize() * size,
+        ))
+    } else {
+        Ok((first_slot + index * U256::from(size.div_ceil(32)), 0))
+    }
+}
+
+// Mapping keys are hashed ABI encoded, except `string` and `bytes` keys
+// which are hashed as they are
+fn encode_key(label: &str, key: &str) -> Result<Vec<u8>> {
+    let kind = param_type(label)?;
+    let token = LenientTokenizer::tokenize(&kind, key)
+        .map_err(|e| eyre!("Invalid key {key:?} for a mapping from {label}: {e}"))?;
+    Ok(match token {
+        Token::String(value) => value.into_bytes(),
+        Token::Bytes(value) => value,
+        token => ethers::abi::encode(&[token]),
+    })
+}
+
+// ABI type of a storage type label. Contracts are stored as addresses and
+// enums as the smallest unsigned integer, which holds up to 256 members.
+fn param_type(label: &str) -> Result<ParamType> {
+    if label.starts_with("contract ") {
+        return Ok(ParamType::Address);
+    }
+    if label.starts_with("enum ") {
+        return Ok(ParamType::Uint(8));
+    }
+    Reader::read(label.trim_end_matches(" payable"))
+        .map_err(|e| eyre!("Unsupported key type {label}: {e}"))
+}
+
+fn parse_index(key: &str) -> Result<U256> {
+    parse_u256(key).map_err(|e| eyre!("Invalid array index: {e}"))
+}
+
+struct StorageReader<'a> {
+    provider: &'a RpcProvider,
+    address: Address,
+    block: Option<BlockId>,
+}
+
+impl StorageReader<'_> {
+    async fn read(&self, slot: U256) -> Result<[u8; 32]> {
+        let value = self
+            .provider
+            .get_storage_at(self.address, H256(word(slot)), self.block)
+            .await?;
+        Ok(value.0)
+    }
+
+    // Render the value of the given type stored at a slot and offset
+    async fn decode(
+        &self,
+        layout: &StorageLayout,
+        type_id: &str,
+        slot: U256,
+        offset: usize,
+    ) -> Result<String> {
+        let storage_type = storage_type(layout, type_id)?;
+        let label = &storage_type.label;
+        match storage_type.encoding.as_str() {
+            "mapping" => Ok(format!("{label}, select an entry with --key")),
+            "dynamic_array" => {
+                let length = U256::from_big_endian(&self.read(slot).await?);
+                Ok(format!("length {length}, select an element with --key"))
+            }
+            "bytes" => self.decode_dynamic(label, slot).await,
+            _ if base_type(storage_type).is_some() => {
+                Ok(format!("{label}, select an element with --key"))
+            }
+            _ if members(storage_type)?.is_some() => {
+                Ok(format!("{label}, select a member with --key"))
+            }
+            _ => {
+                let size = type_size(storage_type)?;
+                if offset + size > 32 {
+                    return Err(eyre!("{label} does not fit in a slot at offset {offset}"));
+                }
+                let value = self.read(slot).await?;
+                Ok(decode_value(label, &value[32 - offset - size..32 - offset]))
+            }
+        }
+    }
+
+    // `string` and `bytes` shorter than 32 bytes live in their slot with twice
+    // their length in the lowest byte. Longer ones store twice their length
+    // plus one, their data starts at the slot's hash.
+    async fn decode_dynamic(&self, label: &str, slot: U256) -> Result<String> {
+        let value = self.read(slot).await?;
+        let data = if value[31] & 1 == 0 {
+            value[..(value[31] / 2) as usize].to_vec()
+        } else {
+            let length = (U256::from_big_endian(&value) - 1) / 2;
+            if length > U256::from(MAX_DYNAMIC_LENGTH) {
+                return Ok(format!("{label} of {length} bytes, too long to print"));
+            }
+            let length = length.as_usize();
+            let first = U256::from_big_endian(&keccak256(word(slot)));
+            let mut data = Vec::with_capacity(length);
+            for index in 0..length.div_ceil(32) {
+                data.extend_from_slice(&self.read(first + index).await?);
+            }
+            data.truncate(length);
+            data
+        };
+        Ok(match label {
+            "string" => format!("{:?}", String::from_utf8_lossy(&data)),
+            _ => format!("0x{}", hex::encode(data)),
+        })
+    }
+}
+
+// Render a value type from its big-endian bytes
+fn decode_value(label: &str, bytes: &[u8]) -> String {
+    let number = U256::from_big_endian(bytes);
+    if label == "bool" {
+        return (!number.is_zero()).to_string();
+    }
+    if label.starts_with("address") || label.starts_with("contract ") {
+        let address = Address::from_slice(&bytes[bytes.len().saturating_sub(20)..]);
+        return format!("{address:?}");
+    }
+    if label.starts_with("uint") || label.starts_with("enum ") {
+        return number.to_string();
+    }
+    if label.starts_with("int") {
+        // Sign extend to 256 bits
+        let mut extended = match bytes.first() {
+            Some(byte) if byte & 0x80 != 0 => [0xff; 32],
+            _ => [0; 32],
+        };
+        extended[32 - bytes.len()..].copy_from_slice(bytes);
+        return I256::from_raw(U256::from_big_endian(&extended)).to_string();
+    }
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn storage_type<'a>(layout: &'a StorageLayout, type_id: &str) -> Result<&'a StorageType> {
+    layout
+        .types
+        .get(type_id)
+        .with_context(|| format!("Type {type_id} missing from the storage layout"))
+}
+
+fn type_size(storage_type: &StorageType) -> Result<usize> {
+    storage_type.number_of_bytes.parse().map_err(|e| {
+        eyre!(
+            "Invalid size {:?} of {}: {e}",
+            storage_type.number_of_bytes,
+            storage_type.label
+        )
+    })
+}
+
+// Element type of an array
+fn base_type(storage_type: &StorageType) -> Option<String> {
+    storage_type
+        .other
+        .get("base")
+        .and_then(|base| base.as_str())
+        .map(str::to_owned)
+}
+
+// Members of a struct
+fn members(storage_type: &StorageType) -> Result<Option<Vec<Storage>>> {
+    match storage_type.other.get("members") {
+        Some(members) => Ok(Some(serde_json::from_value(members.clone())?)),
+        None => Ok(None),
+    }
+}
+
+fn word(value: U256) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word
+}
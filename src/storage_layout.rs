@@ -0,0 +1,146 @@
+use crate::cli_config::{CompilerConfig, LayoutConfig, LayoutFormat};
+use crate::compiler::{compile, contracts_path};
+use ethers_solc::artifacts::{Storage, StorageLayout};
+use ethers_solc::{ConfigurableArtifacts, ProjectCompileOutput};
+use eyre::{ContextCompat, Result};
+
+// Entry point of the `layout` subcommand: print the storage slots of a
+// contract's state variables and the contract in its inheritance chain that
+// declares each of them
+pub async fn layout(config: LayoutConfig) -> Result<()> {
+    // Compiler settings do not affect the storage layout
+    let output = compile(contracts_path(), None, &CompilerConfig::default()).await?;
+    let layout = find_storage_layout(&output, &config.contract_name).with_context(|| {
+        format!(
+            "No storage layout for {}, is it a Solidity contract of the project?",
+            config.contract_name
+        )
+    })?;
+
+    match config.format {
+        LayoutFormat::Table => {
+            println!("{}", "=".repeat(80));
+            println!(
+                "{}: {} state variable(s)",
+                config.contract_name,
+                layout.storage.len()
+            );
+            println!(
+                "{:>6} {:>6} {:>6}  {:<32} {:<24} ORIGIN",
+                "SLOT", "OFFSET", "BYTES", "TYPE", "NAME"
+            );
+            for variable in &layout.storage {
+                println!(
+                    "{:>6} {:>6} {:>6}  {:<32} {:<24} {}",
+                    variable.slot,
+                    variable.offset,
+                    type_size(&layout, variable),
+                    type_label(&layout, variable),
+                    variable.label,
+                    origin(variable)
+                );
+            }
+        }
+        LayoutFormat::Json => {
+            let variables = layout
+                .storage
+                .iter()
+                .map(|variable| {
+                    serde_json::json!({
+                        "slot": variable.slot,
+                        "offset": variable.offset,
+                        "bytes": type_size(&layout, variable),
+                        "type": type_label(&layout, variable),
+                        "label": variable.label,
+                        "contract": variable.contract,
+                    })
+                })
+                .collect::<Vec<_>>();
+            println!("{}", serde_json::to_string_pretty(&variables)?);
+        }
+    }
+    Ok(())
+}
+
+// Storage layout of a compiled contract, if the compiler reported one
+pub fn find_storage_layout(
+    output: &ProjectCompileOutput<ConfigurableArtifacts>,
+    contract_name: &str,
+) -> Option<StorageLayout> {
+    output
+        .clone()
+        .into_artifacts()
+        .find(|(id, _)| id.name == contract_name)
+        .and_then(|(_, artifact)| artifact.storage_layout)
+}
+
+// Changes that make `new` unsafe to put behind a proxy whose storage was
+// written by `old`. Every existing variable has to keep its slot, offset,
+// type and name; new variables may only be appended.
+pub fn upgrade_problems(old: &StorageLayout, new: &StorageLayout) -> Vec<String> {
+    let mut problems = vec![];
+    for (index, old_variable) in old.storage.iter().enumerate() {
+        let Some(new_variable) = new.storage.get(index) else {
+            problems.push(format!("{} was removed", describe(old, old_variable)));
+            continue;
+        };
+        if (&new_variable.slot, new_variable.offset) != (&old_variable.slot, old_variable.offset) {
+            problems.push(format!(
+                "{} moved to slot {} offset {} ({})",
+                describe(old, old_variable),
+                new_variable.slot,
+                new_variable.offset,
+                new_variable.label
+            ));
+        } else if type_label(new, new_variable) != type_label(old, old_variable) {
+            problems.push(format!(
+                "{} changed type to {}",
+                describe(old, old_variable),
+                type_label(new, new_variable)
+            ));
+        } else if new_variable.label != old_variable.label {
+            problems.push(format!(
+                "{} was renamed to {}",
+                describe(old, old_variable),
+                new_variable.label
+            ));
+        }
+    }
+    problems
+}
+
+// e.g. `uint256 count (slot 0 offset 0)`
+pub fn describe(layout: &StorageLayout, variable: &Storage) -> String {
+    format!(
+        "{} {} (slot {} offset {})",
+        type_label(layout, variable),
+        variable.label,
+        variable.slot,
+        variable.offset
+    )
+}
+
+fn type_label(layout: &StorageLayout, variable: &Storage) -> String {
+    layout
+        .types
+        .get(&variable.storage_type)
+        .map(|storage_type| storage_type.label.clone())
+        .unwrap_or_else(|| variable.storage_type.clone())
+}
+
+// Bytes the variable takes, a full slot or more for dynamic and composite types
+fn type_size(layout: &StorageLayout, variable: &Storage) -> String {
+    layout
+        .types
+        .get(&variable.storage_type)
+        .map(|storage_type| storage_type.number_of_bytes.clone())
+        .unwrap_or_else(|| "?".to_owned())
+}
+
+// Name of the declaring contract, e.g. `Ownable` for `lib/Ownable.sol:Ownable`
+fn origin(variable: &Storage) -> &str {
+    variable
+        .contract
+        .rsplit_once(':')
+        .map_or(variable.contract.as_str(), |(_, name)| name)
+}
@@ -0,0 +1,231 @@
+use crate::calls::PlannedCall;
+use ethers::abi::{parse_abi, Abi, Token};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockNumber, Bytes, TransactionRequest, H256, U256};
+use ethers::utils::keccak256;
+use ethers_providers::Middleware;
+use eyre::{eyre, ContextCompat, Result};
+use std::time::Duration;
+
+// Longest pause between two checks of an operation waiting for its delay
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+// Methods of an OpenZeppelin TimelockController
+pub fn timelock_abi() -> Result<Abi> {
+    Ok(parse_abi(&[
+        "function schedule(address,uint256,bytes,bytes32,bytes32,uint256)",
+        "function execute(address,uint256,bytes,bytes32,bytes32) payable",
+        "function hashOperation(address,uint256,bytes,bytes32,bytes32) pure returns (bytes32)",
+        "function getMinDelay() view returns (uint256)",
+        "function getTimestamp(bytes32) view returns (uint256)",
+        "function isOperationReady(bytes32) view returns (bool)",
+        "function isOperationDone(bytes32) view returns (bool)",
+    ])?)
+}
+
+// Where an operation stands in the timelock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationState {
+    // Never scheduled, or cancelled
+    Unset,
+    // Scheduled, executable once the timestamp is reached
+    Waiting(U256),
+    Ready,
+    Done,
+}
+
+// A call the timelock makes on `target` once it has been scheduled for the
+// timelock's minimum delay. The salt tells identical calls apart, the
+// operation has no predecessor.
+pub struct TimelockOperation {
+    timelock: Address,
+    target: Address,
+    value: U256,
+    data: Bytes,
+    salt: H256,
+}
+
+impl TimelockOperation {
+    // The operation making the call `tx` through `timelock`, salted with the
+    // journal step of the call so a rerun finds the operation it scheduled
+    pub fn new(timelock: Address, tx: &TransactionRequest, step: &str) -> Result<Self> {
+        let target = tx
+            .to
+            .as_ref()
+            .and_then(|to| to.as_address())
+            .copied()
+            .context("Timelocked call has no target address")?;
+        Ok(TimelockOperation {
+            timelock,
+            target,
+            value: tx.value.unwrap_or_default(),
+            data: tx.data.clone().unwrap_or_default(),
+            salt: keccak256(step.as_bytes()).into(),
+        })
+    }
+
+    fn args(&self) -> Vec<String> {
+        vec![
+            format!("{:?}", self.target),
+            self.value.to_string(),
+            self.data.to_string(),
+            format!("{:?}", H256::zero()),
+            format!("{:?}", self.salt),
+        ]
+    }
+
+    fn tokens(&self) -> Vec<Token> {
+        vec![
+            Token::Address(self.target),
+            Token::Uint(self.value),
+            Token::Bytes(self.data.to_vec()),
+            Token::FixedBytes(H256::zero().as_bytes().to_vec()),
+            Token::FixedBytes(self.salt.as_bytes().to_vec()),
+        ]
+    }
+
+    // Call of the timelock scheduling the operation after `delay` seconds
+    pub fn schedule(&self, delay: U256) -> PlannedCall {
+        let mut args = self.args();
+        args.push(delay.to_string());
+        PlannedCall {
+            method: "schedule".to_owned(),
+            args,
+            value: None,
+            expect: None,
+            timelock: None,
+        }
+    }
+
+    // Call of the timelock executing the operation, paying its value
+    pub fn execute(&self) -> PlannedCall {
+        PlannedCall {
+            method: "execute".to_owned(),
+            args: self.args(),
+            value: (!self.value.is_zero()).then(|| self.value.to_string()),
+            expect: None,
+            timelock: None,
+        }
+    }
+
+    pub async fn id<M: Middleware>(&self, client: &M) -> Result<H256>
+    where
+        M::Error: 'static,
+    {
+        let id = view(client, self.timelock, "hashOperation", &self.tokens()).await?;
+        match id.as_slice() {
+            [Token::FixedBytes(id)] => Ok(H256::from_slice(id)),
+            _ => Err(eyre!("Timelock returned a malformed operation id")),
+        }
+    }
+
+    pub async fn state<M: Middleware>(&self, client: &M, id: H256) -> Result<OperationState>
+    where
+        M::Error: 'static,
+    {
+        let id = [Token::FixedBytes(id.as_bytes().to_vec())];
+        if view_bool(client, self.timelock, "isOperationDone", &id).await? {
+            return Ok(OperationState::Done);
+        }
+        if view_bool(client, self.timelock, "isOperationReady", &id).await? {
+            return Ok(OperationState::Ready);
+        }
+        match view(client, self.timelock, "getTimestamp", &id)
+            .await?
+            .first()
+            .cloned()
+            .and_then(Token::into_uint)
+        {
+            Some(timestamp) if !timestamp.is_zero() => Ok(OperationState::Waiting(timestamp)),
+            _ => Ok(OperationState::Unset),
+        }
+    }
+
+    // Minimum delay of the timelock, in seconds
+    pub async fn min_delay<M: Middleware>(&self, client: &M) -> Result<U256>
+    where
+        M::Error: 'static,
+    {
+        view(client, self.timelock, "getMinDelay", &[])
+            .await?
+            .first()
+            .cloned()
+            .and_then(Token::into_uint)
+            .context("Timelock returned a malformed minimum delay")
+    }
+
+    // Poll until the operation's delay has passed. An interrupted wait is
+    // picked up by resuming the run, which finds the operation scheduled.
+    pub async fn wait_until_ready<M: Middleware>(&self, client: &M, id: H256) -> Result<()>
+    where
+        M::Error: 'static,
+    {
+        let mut announced = false;
+        loop {
+            let eta = match self.state(client, id).await? {
+                OperationState::Ready => return Ok(()),
+                OperationState::Done => {
+                    return Err(eyre!(
+                        "Timelock operation {id:?} was executed by someone else"
+                    ))
+                }
+                OperationState::Unset => {
+                    return Err(eyre!("Timelock operation {id:?} was cancelled"))
+                }
+                OperationState::Waiting(eta) => eta,
+            };
+            let now = client
+                .get_block(BlockNumber::Latest)
+                .await?
+                .context("Node returned no latest block")?
+                .timestamp;
+            let remaining = eta.saturating_sub(now).as_u64();
+            if !announced {
+                println!(
+                    "Timelock operation {id:?} is executable at {eta} (in {remaining}s), waiting. Interrupt and rerun with --resume to execute it later"
+                );
+                announced = true;
+            }
+            tokio::time::sleep(Duration::from_secs(remaining.max(1)).min(MAX_POLL_INTERVAL)).await;
+        }
+    }
+}
+
+async fn view<M: Middleware>(
+    client: &M,
+    timelock: Address,
+    method: &str,
+    tokens: &[Token],
+) -> Result<Vec<Token>>
+where
+    M::Error: 'static,
+{
+    let abi = timelock_abi()?;
+    let function = abi.function(method)?;
+    let tx: TypedTransaction = TransactionRequest::new()
+        .to(timelock)
+        .data(Bytes::from(function.encode_input(tokens)?))
+        .into();
+    let output = client
+        .call(&tx, None)
+        .await
+        .map_err(|e| eyre!("Timelock {timelock:?} {method} failed: {e}"))?;
+    Ok(function.decode_output(&output)?)
+}
+
+async fn view_bool<M: Middleware>(
+    client: &M,
+    timelock: Address,
+    method: &str,
+    tokens: &[Token],
+) -> Result<bool>
+where
+    M::Error: 'static,
+{
+    view(client, timelock, method, tokens)
+        .await?
+        .first()
+        .cloned()
+        .and_then(Token::into_bool)
+        .with_context(|| format!("Timelock returned a malformed {method} result"))
+}
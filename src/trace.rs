@@ -0,0 +1,256 @@
+use crate::abi_args::format_token;
+use crate::cli_config::TraceConfig;
+use crate::decode::project_abis;
+use crate::manifest::Deployment;
+use crate::revert::decode_revert;
+use crate::rpc::{self, RpcProvider};
+use crate::units::format_eth;
+use ethers::abi::{Abi, Function, Token};
+use ethers::types::{
+    Action, Address, Bytes, CallFrame, GethDebugBuiltInTracerType, GethDebugTracerType,
+    GethDebugTracingOptions, GethTrace, GethTraceFrame, Res, Trace, U256,
+};
+use ethers_providers::Middleware;
+use eyre::{eyre, ContextCompat, Result};
+use std::collections::HashMap;
+
+// One call of a transaction and the calls it made in turn
+struct Frame {
+    kind: String,
+    to: Option<Address>,
+    value: U256,
+    gas_used: U256,
+    input: Bytes,
+    output: Bytes,
+    error: Option<String>,
+    calls: Vec<Frame>,
+}
+
+// Entry point of the `trace` subcommand: fetch the call tree of a transaction
+// with `debug_traceTransaction`, falling back to the parity style
+// `trace_transaction`, and print it decoded with the project's ABIs
+pub async fn trace(config: TraceConfig) -> Result<()> {
+    let provider = rpc::connect(&config.rpc_url)?;
+    let options = GethDebugTracingOptions {
+        tracer: Some(GethDebugTracerType::BuiltInTracer(
+            GethDebugBuiltInTracerType::CallTracer,
+        )),
+        ..Default::default()
+    };
+    let root = match provider
+        .debug_trace_transaction(config.tx_hash, options)
+        .await
+    {
+        Ok(GethTrace::Known(GethTraceFrame::CallTracer(frame))) => Frame::from(frame),
+        Ok(_) => return Err(eyre!("Node returned an unexpected debug trace")),
+        Err(debug_error) => {
+            let traces = provider
+                .trace_transaction(config.tx_hash)
+                .await
+                .map_err(|e| eyre!("Node cannot trace transactions: {debug_error}, {e}"))?;
+            frame_tree(traces)?
+        }
+    };
+
+    let contracts = Contracts::load(&provider).await?;
+    println!("{}", "=".repeat(80));
+    println!("TRACE {:?}", config.tx_hash);
+    contracts.print(&root, "", "");
+    Ok(())
+}
+
+impl From<CallFrame> for Frame {
+    fn from(frame: CallFrame) -> Self {
+        Frame {
+            kind: frame.typ,
+            to: frame.to.and_then(|to| to.as_address().copied()),
+            value: frame.value.unwrap_or_default(),
+            gas_used: frame.gas_used,
+            input: frame.input,
+            output: frame.output.unwrap_or_default(),
+            error: frame.error,
+            calls: frame
+                .calls
+                .unwrap_or_default()
+                .into_iter()
+                .map(Frame::from)
+                .collect(),
+        }
+    }
+}
+
+// Rebuild the call tree from parity traces, which come depth first with the
+// path from the root in `trace_address`
+fn frame_tree(traces: Vec<Trace>) -> Result<Frame> {
+    let mut stack: Vec<Frame> = vec![];
+    for trace in traces {
+        let depth = trace.trace_address.len();
+        attach_until(&mut stack, depth);
+        stack.push(parity_frame(trace));
+    }
+    attach_until(&mut stack, 1);
+    stack.pop().context("Node returned an empty trace")
+}
+
+// Pop frames into their parent's calls until `depth` frames are left
+fn attach_until(stack: &mut Vec<Frame>, depth: usize) {
+    while stack.len() > depth.max(1) {
+        if let Some(frame) = stack.pop() {
+            if let Some(parent) = stack.last_mut() {
+                parent.calls.push(frame);
+            }
+        }
+    }
+}
+
+fn parity_frame(trace: Trace) -> Frame {
+    let (gas_used, output, created) = match trace.result {
+        Some(Res::Call(result)) => (result.gas_used, result.output, None),
+        Some(Res::Create(result)) => (result.gas_used, result.code, Some(result.address)),
+        _ => (U256::zero(), Bytes::default(), None),
+    };
+    let (kind, to, value, input) = match trace.action {
+        Action::Call(call) => (
+            format!("{:?}", call.call_type).to_uppercase(),
+            Some(call.to),
+            call.value,
+            call.input,
+        ),
+        Action::Create(create) => ("CREATE".to_owned(), created, create.value, create.init),
+        _ => (
+            format!("{:?}", trace.action_type).to_uppercase(),
+            None,
+            U256::zero(),
+            Bytes::default(),
+        ),
+    };
+    Frame {
+        kind,
+        to,
+        value,
+        gas_used,
+        input,
+        output,
+        error: trace.error,
+        calls: vec![],
+    }
+}
+
+// Names and ABIs to decode calls with: deployments recorded for the chain by
+// address, and the project's contracts by function selector
+struct Contracts {
+    deployed: HashMap<Address, (String, Abi)>,
+    project: Vec<(String, Abi)>,
+}
+
+impl Contracts {
+    async fn load(provider: &RpcProvider) -> Result<Contracts> {
+        let chain_id = provider.get_chainid().await?.as_u64();
+        let deployed = Deployment::list_all()?
+            .into_iter()
+            .filter(|deployment| deployment.chain_id == chain_id)
+            .map(|deployment| (deployment.address, (deployment.name, deployment.abi)))
+            .collect();
+        Ok(Contracts {
+            deployed,
+            project: project_abis().await?,
+        })
+    }
+
+    // Print a frame as `[gas] Contract::method(args)` followed by what it
+    // returned or reverted with, then its calls as a tree below it
+    fn print(&self, frame: &Frame, prefix: &str, child_prefix: &str) {
+        let target = match frame.to {
+            Some(address) => match self.deployed.get(&address) {
+                Some((name, _)) => name.clone(),
+                None => format!("{address:?}"),
+            },
+            None => "<unknown>".to_owned(),
+        };
+        let (abi, function) = self.function(frame);
+        let call = match (frame.kind.starts_with("CREATE"), function) {
+            (true, _) => format!("{} new {target}", frame.kind),
+            (false, Some(function)) => {
+                let args = frame
+                    .input
+                    .get(4..)
+                    .and_then(|args| function.decode_input(args).ok())
+                    .map(|tokens| join(&tokens))
+                    .unwrap_or_else(|| "<undecodable>".to_owned());
+                format!("{} {target}::{}({args})", frame.kind, function.name)
+            }
+            (false, None) if frame.input.is_empty() => format!("{} {target}", frame.kind),
+            (false, None) => format!("{} {target} calldata {}", frame.kind, frame.input),
+        };
+        let value = match frame.value.is_zero() {
+            true => String::new(),
+            false => format!(" value: {}", format_eth(frame.value)),
+        };
+        println!("{prefix}[{}] {call}{value}", frame.gas_used);
+
+        let result = match (&frame.error, function) {
+            (Some(error), _) if frame.output.is_empty() => format!("✗ {error}"),
+            (Some(_), _) => format!("✗ {}", decode_revert(&frame.output, abi)),
+            (None, Some(function)) if !frame.kind.starts_with("CREATE") => {
+                match function.decode_output(&frame.output) {
+                    Ok(tokens) if tokens.is_empty() => "← ()".to_owned(),
+                    Ok(tokens) => format!("← {}", join(&tokens)),
+                    Err(_) => format!("← {}", frame.output),
+                }
+            }
+            (None, _) => String::new(),
+        };
+
+        for (index, call) in frame.calls.iter().enumerate() {
+            let last = index + 1 == frame.calls.len() && result.is_empty();
+            let (branch, indent) = match last {
+                true => ("└─ ", "   "),
+                false => ("├─ ", "│  "),
+            };
+            self.print(
+                call,
+                &format!("{child_prefix}{branch}"),
+                &format!("{child_prefix}{indent}"),
+            );
+        }
+        if !result.is_empty() {
+            println!("{child_prefix}└─ {result}");
+        }
+    }
+
+    // ABI and function a frame calls, preferring the deployment at the called
+    // address over any project contract with the same selector
+    fn function(&self, frame: &Frame) -> (Option<&Abi>, Option<&Function>) {
+        let deployed = frame
+            .to
+            .and_then(|address| self.deployed.get(&address))
+            .map(|(_, abi)| abi);
+        let Some(selector) = frame.input.get(..4) else {
+            return (deployed, None);
+        };
+        if let Some(abi) = deployed {
+            if let Some(function) = by_selector(abi, selector) {
+                return (Some(abi), Some(function));
+            }
+        }
+        for (_, abi) in &self.project {
+            if let Some(function) = by_selector(abi, selector) {
+                return (Some(abi), Some(function));
+            }
+        }
+        (deployed, None)
+    }
+}
+
+fn by_selector<'a>(abi: &'a Abi, selector: &[u8]) -> Option<&'a Function> {
+    abi.functions()
+        .find(|function| function.short_signature() == selector)
+}
+
+fn join(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .map(format_token)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,322 @@
+use crate::audit;
+use crate::cli_config::{
+    FeeBumpConfig, TxCommand, TxConfig, TxListConfig, TxReplaceConfig, TxShowConfig,
+};
+use crate::fees::{resolve_fees, Fees, MIN_BUMP_PERCENT};
+use crate::interact::print_receipt;
+use crate::lock::DeployLock;
+use crate::progress::{track_confirmations, Progress};
+use crate::rpc::{self, RpcTransport};
+use crate::signer::wallet_from_config;
+use crate::tx_store::{self, TxFilter};
+use crate::units::format_gwei;
+use ethers::middleware::SignerMiddleware;
+use ethers::signers::Signer;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{TransactionReceipt, TransactionRequest, H256, U256};
+use ethers_providers::{Middleware, PendingTransaction};
+use eyre::{eyre, ContextCompat, Result};
+use std::time::{Duration, Instant};
+use tracing::{instrument, warn};
+
+// How often the receipts of transactions that may be bumped are polled
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Entry point of the `tx` subcommand
+pub async fn tx(config: TxConfig) -> Result<()> {
+    match config.command {
+        TxCommand::Bump(config) => replace(config, false).await,
+        TxCommand::Cancel(config) => replace(config, true).await,
+        TxCommand::List(config) => list(config),
+        TxCommand::Show(config) => show(config),
+    }
+}
+
+fn list(config: TxListConfig) -> Result<()> {
+    let transactions = tx_store::open_configured()?.list(&TxFilter {
+        chain_id: config.chain_id,
+        status: config.status,
+        limit: Some(config.limit),
+    })?;
+    println!("{}", "=".repeat(80));
+    println!(
+        "{:<68} {:>10} {:>8} {:<10} PURPOSE",
+        "HASH", "CHAIN", "NONCE", "STATUS"
+    );
+    for tx in &transactions {
+        println!(
+            "{:<68} {:>10} {:>8} {:<10} {}",
+            format!("{:?}", tx.hash),
+            tx.chain_id,
+            tx.nonce
+                .map_or_else(|| "-".to_owned(), |nonce| nonce.to_string()),
+            tx.status.as_str(),
+            tx.purpose
+        );
+    }
+    println!("{} transaction(s)", transactions.len());
+    Ok(())
+}
+
+fn show(config: TxShowConfig) -> Result<()> {
+    let tx = tx_store::open_configured()?
+        .get(config.tx_hash)?
+        .with_context(|| {
+            format!(
+                "Transaction {:?} is not in the transaction database",
+                config.tx_hash
+            )
+        })?;
+    println!("{}", serde_json::to_string_pretty(&tx)?);
+    Ok(())
+}
+
+// Resend a pending transaction at the same nonce with raised fees, either as
+// it is or, to cancel it, as an empty transfer to the sender itself
+async fn replace(config: TxReplaceConfig, cancel: bool) -> Result<()> {
+    let provider = rpc::connect(&config.rpc_url)?;
+    let pending = provider
+        .get_transaction(config.tx_hash)
+        .await?
+        .with_context(|| format!("Transaction {:?} not found", config.tx_hash))?;
+    if let Some(block_number) = pending.block_number {
+        return Err(eyre!(
+            "Transaction {:?} is already mined in block {block_number}",
+            config.tx_hash
+        ));
+    }
+
+    let chain_id = provider.get_chainid().await?;
+    let wallet = wallet_from_config(&config.signer)?
+        .context("--private-key, --keystore or --mnemonic of the sender is required")?
+        .with_chain_id(chain_id.as_u64());
+    if wallet.address() != pending.from {
+        return Err(eyre!(
+            "Transaction {:?} was sent by {:?}, not {:?}",
+            config.tx_hash,
+            pending.from,
+            wallet.address()
+        ));
+    }
+    let command = if cancel { "cancel" } else { "bump" };
+    let _lock = DeployLock::acquire(chain_id.as_u64(), command, config.force_unlock)?;
+
+    // Nodes only accept a replacement paying clearly more than the original,
+    // and it should be competitive with the current fees as well
+    let fees = Fees::of_transaction(&pending)
+        .bumped(config.bump_percent)
+        .at_least(resolve_fees(&provider, &config.fees).await?);
+    let mut request = TransactionRequest::new()
+        .from(pending.from)
+        .nonce(pending.nonce);
+    request = match (cancel, pending.to) {
+        (true, _) => request.to(pending.from).value(U256::zero()).gas(21_000),
+        (false, Some(to)) => request
+            .to(to)
+            .value(pending.value)
+            .data(pending.input.clone())
+            .gas(pending.gas),
+        (false, None) => request
+            .value(pending.value)
+            .data(pending.input.clone())
+            .gas(pending.gas),
+    };
+    let mut tx = TypedTransaction::Legacy(request);
+    fees.apply(&mut tx);
+
+    println!(
+        "{} {:?} at nonce {} with {fees}",
+        if cancel { "CANCELLING" } else { "BUMPING" },
+        config.tx_hash,
+        pending.nonce
+    );
+    let client = SignerMiddleware::new(provider, wallet);
+    let pending_tx = client.send_transaction(tx.clone(), None).await?;
+    let action = match cancel {
+        true => format!("cancel {:?}", config.tx_hash),
+        false => format!("replace {:?}", config.tx_hash),
+    };
+    audit::record(&client, &action, &tx, pending_tx.tx_hash()).await;
+    println!("Replacement transaction hash: {:?}", pending_tx.tx_hash());
+    let receipt = rpc::confirm(client.provider(), pending_tx, config.confirmations)
+        .await?
+        .context("Replacement transaction dropped from mempool")?;
+    print_receipt(&receipt);
+    Ok(())
+}
+
+// Wait for a sent transaction to be mined. With fee bumping, a transaction
+// still pending after `--bump-after` is resent at the same nonce with fees
+// raised by `--bump-percent`, and whichever of them is mined first counts.
+// `on_sent` is called with the hash of every replacement and of the
+// transaction that was mined in the end. Returns `None` when the transaction
+// was dropped.
+#[instrument(name = "confirmation", skip_all, fields(tx = ?pending_tx.tx_hash()))]
+pub async fn wait_for_receipt<M: Middleware<Provider = RpcTransport>>(
+    client: &M,
+    pending_tx: PendingTransaction<'_, M::Provider>,
+    tx: TypedTransaction,
+    fees: Option<&Fees>,
+    fee_bump: Option<&FeeBumpConfig>,
+    confirmations: usize,
+    on_sent: impl FnMut(H256) -> Result<()>,
+) -> Result<Option<TransactionReceipt>>
+where
+    M::Error: 'static,
+{
+    let (Some(fees), Some(fee_bump)) = (fees, fee_bump.filter(|bump| bump.bump_after.is_some()))
+    else {
+        let hash = pending_tx.tx_hash();
+        let receipt = track_confirmations(
+            client,
+            hash,
+            confirmations,
+            rpc::confirm(client.provider(), pending_tx, confirmations),
+        );
+        return Ok(receipt.await?);
+    };
+
+    let first_hash = pending_tx.tx_hash();
+    let Some(receipt) =
+        bump_until_mined(client, first_hash, tx, Some(fees), fee_bump, on_sent).await?
+    else {
+        return Ok(None);
+    };
+    if confirmations > 1 {
+        let hash = receipt.transaction_hash;
+        let pending_tx = rpc::confirm(
+            client.provider(),
+            PendingTransaction::new(hash, client.provider()),
+            confirmations,
+        );
+        return Ok(track_confirmations(client, hash, confirmations, pending_tx).await?);
+    }
+    tx_store::record_receipt(&receipt);
+    Ok(Some(receipt))
+}
+
+// Resend `tx`, sent as `first_hash`, with fees raised by `--bump-percent`
+// every `--bump-after` seconds it stays pending, and return the receipt of
+// whichever of them was mined, `None` when all were dropped. The fees rise
+// geometrically until `--max-bumps` runs out, or until a resend at
+// `--bump-fee-cap` when the next bump would pass it. Without `fees`, those
+// the transaction was sent with are bumped.
+pub async fn bump_until_mined<M: Middleware>(
+    client: &M,
+    first_hash: H256,
+    mut tx: TypedTransaction,
+    fees: Option<&Fees>,
+    fee_bump: &FeeBumpConfig,
+    mut on_sent: impl FnMut(H256) -> Result<()>,
+) -> Result<Option<TransactionReceipt>>
+where
+    M::Error: 'static,
+{
+    let bump_after = fee_bump
+        .bump_after
+        .context("--bump-after is required to bump fees")?;
+    // The replacements must reuse the nonce and gas the signer filled in
+    let sent = client
+        .get_transaction(first_hash)
+        .await?
+        .with_context(|| format!("Transaction {first_hash:?} not found after sending it"))?;
+    tx.set_nonce(sent.nonce);
+    tx.set_gas(sent.gas);
+
+    let mut hashes = vec![first_hash];
+    let mut fees = fees.copied().unwrap_or_else(|| Fees::of_transaction(&sent));
+    let mut bumps = 0;
+    // Resends the node rejected, limited like the bumps so a node rejecting
+    // every one of them is not tried forever
+    let mut rejected = 0;
+    // Set once a resend paid `--bump-fee-cap`
+    let mut capped = false;
+    let mut sent_at = Instant::now();
+    let progress = Progress::spinner(format!("Waiting for {first_hash:?} to be mined"));
+    loop {
+        for hash in &hashes {
+            if let Some(receipt) = client.get_transaction_receipt(*hash).await? {
+                if Some(hash) != hashes.last() {
+                    on_sent(*hash)?;
+                }
+                // The others can no longer be mined at the same nonce
+                for replaced in hashes.iter().filter(|replaced| *replaced != hash) {
+                    tx_store::record_dropped(*replaced);
+                }
+                return Ok(Some(receipt));
+            }
+        }
+        let escalating = bumps < fee_bump.max_bumps && rejected < fee_bump.max_bumps && !capped;
+        if !escalating && !any_known(client, &hashes).await? {
+            for hash in &hashes {
+                tx_store::record_dropped(*hash);
+            }
+            return Ok(None);
+        }
+        if sent_at.elapsed() < Duration::from_secs(bump_after) || !escalating {
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+            continue;
+        }
+
+        // A bump past the cap is resent at the cap, the last one to be sent,
+        // unless the cap leaves less of a raise than nodes accept
+        let bumped = fees.bumped(fee_bump.bump_percent);
+        let cap = fee_bump
+            .bump_fee_cap
+            .filter(|cap| bumped.max_gas_price() >= *cap);
+        let bumped = cap.map_or(bumped, |cap| bumped.capped(cap));
+        let minimum = fees.bumped(MIN_BUMP_PERCENT);
+        if let Some(cap) = cap.filter(|_| bumped.at_least(minimum) != bumped) {
+            println!(
+                "Still pending after {bump_after}s, not resending as the cap of {} leaves less than a {MIN_BUMP_PERCENT}% raise",
+                format_gwei(cap)
+            );
+            capped = true;
+            continue;
+        }
+        let mut replacement_tx = tx.clone();
+        bumped.apply(&mut replacement_tx);
+        // Tried again after another `--bump-after`, rejected or not
+        sent_at = Instant::now();
+        match client.send_transaction(replacement_tx.clone(), None).await {
+            Ok(replacement) => {
+                let hash = replacement.tx_hash();
+                audit::record(
+                    client,
+                    &format!("fee bump of {first_hash:?}"),
+                    &replacement_tx,
+                    hash,
+                )
+                .await;
+                // Later bumps build on the fees that reached the mempool
+                fees = bumped;
+                tx = replacement_tx;
+                bumps += 1;
+                capped = cap.is_some();
+                println!("Still pending after {bump_after}s, resent as {hash:?} with {fees}");
+                progress.set_message(format!("Waiting for {hash:?} to be mined"));
+                on_sent(hash)?;
+                hashes.push(hash);
+            }
+            // Fails with e.g. `nonce too low` when one of the sent ones was
+            // just mined, which the next poll picks up
+            Err(e) => {
+                rejected += 1;
+                warn!("Resending with {bumped} failed: {e}");
+            }
+        }
+    }
+}
+
+// Whether the node still knows any of the transactions
+async fn any_known<M: Middleware>(client: &M, hashes: &[H256]) -> Result<bool>
+where
+    M::Error: 'static,
+{
+    for hash in hashes {
+        if client.get_transaction(*hash).await?.is_some() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
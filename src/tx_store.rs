@@ -0,0 +1,336 @@
+use crate::cli_config::TxStoreConfig;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionReceipt, H256, U256};
+use ethers_providers::Middleware;
+use eyre::{eyre, Result};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+// Where the CLI and the server keep the database unless told otherwise
+pub const DEFAULT_TX_DB: &str = "deployments/transactions.db";
+
+// Store settings of the run, set once the command line is parsed
+static TX_STORE_CONFIG: OnceLock<TxStoreConfig> = OnceLock::new();
+
+// Longest wait for another process, e.g. the server, to release the database
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn init(config: TxStoreConfig) {
+    let _ = TX_STORE_CONFIG.set(config);
+}
+
+#[derive(clap::ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TxStatus {
+    /// Sent, not mined yet
+    Pending,
+    /// Mined and successful
+    Confirmed,
+    /// Mined but reverted
+    Reverted,
+    /// Left the mempool without being mined
+    Dropped,
+}
+
+impl TxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxStatus::Pending => "pending",
+            TxStatus::Confirmed => "confirmed",
+            TxStatus::Reverted => "reverted",
+            TxStatus::Dropped => "dropped",
+        }
+    }
+}
+
+impl FromStr for TxStatus {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "pending" => Ok(TxStatus::Pending),
+            "confirmed" => Ok(TxStatus::Confirmed),
+            "reverted" => Ok(TxStatus::Reverted),
+            "dropped" => Ok(TxStatus::Dropped),
+            status => Err(eyre!("Unknown transaction status {status:?}")),
+        }
+    }
+}
+
+// A transaction sent by the CLI or the server, as recorded in the store
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredTransaction {
+    pub hash: H256,
+    pub chain_id: u64,
+    pub from: Option<Address>,
+    // None for a contract creation
+    pub to: Option<Address>,
+    pub nonce: Option<U256>,
+    pub value: U256,
+    pub calldata: Bytes,
+    // What the transaction was sent for, e.g. the journal step
+    pub purpose: String,
+    pub status: TxStatus,
+    pub block_number: Option<u64>,
+    pub gas_used: Option<U256>,
+    // Unix timestamps in seconds
+    pub sent_at: u64,
+    pub updated_at: u64,
+}
+
+// Which transactions `TxStore::list` returns
+#[derive(Debug, Clone, Default)]
+pub struct TxFilter {
+    pub chain_id: Option<u64>,
+    pub status: Option<TxStatus>,
+    pub limit: Option<usize>,
+}
+
+// SQLite database of the sent transactions, shared by the CLI and the
+// server. Each of them opens it for as long as it needs it, so both can
+// write to it at the same time.
+pub struct TxStore {
+    connection: Connection,
+}
+
+impl TxStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let connection =
+            Connection::open(path).map_err(|e| eyre!("Failed to open {}: {e}", path.display()))?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS transactions (
+                hash TEXT PRIMARY KEY,
+                chain_id INTEGER NOT NULL,
+                sender TEXT,
+                recipient TEXT,
+                nonce TEXT,
+                value TEXT NOT NULL,
+                calldata TEXT NOT NULL,
+                purpose TEXT NOT NULL,
+                status TEXT NOT NULL,
+                block_number INTEGER,
+                gas_used TEXT,
+                sent_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS transactions_sent_at ON transactions (sent_at);",
+        )?;
+        Ok(TxStore { connection })
+    }
+
+    pub fn insert(&self, tx: &StoredTransaction) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO transactions VALUES
+                (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                format!("{:?}", tx.hash),
+                tx.chain_id,
+                tx.from.map(|from| format!("{from:?}")),
+                tx.to.map(|to| format!("{to:?}")),
+                tx.nonce.map(|nonce| nonce.to_string()),
+                tx.value.to_string(),
+                tx.calldata.to_string(),
+                tx.purpose,
+                tx.status.as_str(),
+                tx.block_number,
+                tx.gas_used.map(|gas| gas.to_string()),
+                tx.sent_at,
+                tx.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    // Record how a transaction ended. Transactions the store does not know
+    // are left out.
+    pub fn update_status(
+        &self,
+        hash: H256,
+        status: TxStatus,
+        receipt: Option<&TransactionReceipt>,
+    ) -> Result<()> {
+        self.connection.execute(
+            "UPDATE transactions
+            SET status = ?2, block_number = ?3, gas_used = ?4, updated_at = ?5
+            WHERE hash = ?1",
+            params![
+                format!("{hash:?}"),
+                status.as_str(),
+                receipt
+                    .and_then(|receipt| receipt.block_number)
+                    .map(|number| number.as_u64()),
+                receipt
+                    .and_then(|receipt| receipt.gas_used)
+                    .map(|gas| gas.to_string()),
+                now()?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get(&self, hash: H256) -> Result<Option<StoredTransaction>> {
+        let row = self
+            .connection
+            .query_row(
+                "SELECT * FROM transactions WHERE hash = ?1",
+                [format!("{hash:?}")],
+                |row| Ok(read_row(row)),
+            )
+            .optional()?;
+        row.transpose()
+    }
+
+    // The transactions matching `filter`, most recent first
+    pub fn list(&self, filter: &TxFilter) -> Result<Vec<StoredTransaction>> {
+        let mut statement = self.connection.prepare(
+            "SELECT * FROM transactions
+            WHERE (?1 IS NULL OR chain_id = ?1) AND (?2 IS NULL OR status = ?2)
+            ORDER BY sent_at DESC, rowid DESC
+            LIMIT ?3",
+        )?;
+        let limit = filter.limit.map_or(-1, |limit| limit as i64);
+        let rows = statement.query_map(
+            params![
+                filter.chain_id,
+                filter.status.map(|status| status.as_str()),
+                limit
+            ],
+            |row| Ok(read_row(row)),
+        )?;
+        let mut transactions = vec![];
+        for row in rows {
+            transactions.push(row??);
+        }
+        Ok(transactions)
+    }
+}
+
+fn read_row(row: &Row) -> Result<StoredTransaction> {
+    let parse_u256 = |value: String| U256::from_dec_str(&value);
+    Ok(StoredTransaction {
+        hash: row.get::<_, String>("hash")?.parse()?,
+        chain_id: row.get("chain_id")?,
+        from: row
+            .get::<_, Option<String>>("sender")?
+            .map(|from| from.parse())
+            .transpose()?,
+        to: row
+            .get::<_, Option<String>>("recipient")?
+            .map(|to| to.parse())
+            .transpose()?,
+        nonce: row
+            .get::<_, Option<String>>("nonce")?
+            .map(parse_u256)
+            .transpose()?,
+        value: parse_u256(row.get("value")?)?,
+        calldata: row.get::<_, String>("calldata")?.parse()?,
+        purpose: row.get("purpose")?,
+        status: row.get::<_, String>("status")?.parse()?,
+        block_number: row.get("block_number")?,
+        gas_used: row
+            .get::<_, Option<String>>("gas_used")?
+            .map(parse_u256)
+            .transpose()?,
+        sent_at: row.get("sent_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+// Open the store of the run to read it back
+pub fn open_configured() -> Result<TxStore> {
+    let config = TX_STORE_CONFIG
+        .get()
+        .ok_or_else(|| eyre!("No transaction database configured"))?;
+    TxStore::open(&config.tx_db)
+}
+
+// Store of the run, `None` with `--no-tx-db` or when not initialized, as in
+// library use
+fn configured() -> Option<&'static TxStoreConfig> {
+    TX_STORE_CONFIG.get().filter(|config| !config.no_tx_db)
+}
+
+// Record the just sent transaction `tx` as pending, described by `purpose`.
+// The transaction is already sent, so a failure to record it is reported
+// without failing the run.
+pub async fn record_sent<M: Middleware>(
+    client: &M,
+    purpose: &str,
+    tx: &TypedTransaction,
+    hash: H256,
+) where
+    M::Error: 'static,
+{
+    let Some(config) = configured() else {
+        return;
+    };
+    let chain_id = match tx.chain_id() {
+        Some(chain_id) => chain_id.as_u64(),
+        None => match client.get_chainid().await {
+            Ok(chain_id) => chain_id.as_u64(),
+            Err(e) => {
+                warn!("Failed to record transaction {hash:?}: {e}");
+                return;
+            }
+        },
+    };
+    let result = now().and_then(|now| {
+        TxStore::open(&config.tx_db)?.insert(&StoredTransaction {
+            hash,
+            chain_id,
+            from: tx.from().copied().or_else(|| client.default_sender()),
+            to: tx.to().and_then(|to| to.as_address()).copied(),
+            nonce: tx.nonce().copied(),
+            value: tx.value().copied().unwrap_or_default(),
+            calldata: tx.data().cloned().unwrap_or_default(),
+            purpose: purpose.to_owned(),
+            status: TxStatus::Pending,
+            block_number: None,
+            gas_used: None,
+            sent_at: now,
+            updated_at: now,
+        })
+    });
+    if let Err(e) = result {
+        warn!("Failed to record transaction {hash:?}: {e}");
+    }
+}
+
+// Record the receipt of a mined transaction
+pub fn record_receipt(receipt: &TransactionReceipt) {
+    let status = match receipt.status.is_some_and(|status| status.is_zero()) {
+        true => TxStatus::Reverted,
+        false => TxStatus::Confirmed,
+    };
+    record_status(receipt.transaction_hash, status, Some(receipt));
+}
+
+// Record that a transaction left the mempool without being mined
+pub fn record_dropped(hash: H256) {
+    record_status(hash, TxStatus::Dropped, None);
+}
+
+fn record_status(hash: H256, status: TxStatus, receipt: Option<&TransactionReceipt>) {
+    let Some(config) = configured() else {
+        return;
+    };
+    let result =
+        TxStore::open(&config.tx_db).and_then(|store| store.update_status(hash, status, receipt));
+    if let Err(e) = result {
+        warn!("Failed to record the status of transaction {hash:?}: {e}");
+    }
+}
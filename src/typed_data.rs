@@ -0,0 +1,48 @@
+use crate::cli_config::SignTypedDataConfig;
+use crate::signer::wallet_from_config;
+use ethers::signers::Signer;
+use ethers::types::transaction::eip712::{Eip712, TypedData};
+use ethers::types::H256;
+use eyre::{eyre, ContextCompat, Result};
+use std::io::Read;
+
+// Entry point of the `sign-typed-data` subcommand: sign an EIP-712 payload,
+// e.g. an ERC-2612 permit, as `eth_signTypedData_v4` would
+pub async fn sign_typed_data(config: SignTypedDataConfig) -> Result<()> {
+    let wallet = wallet_from_config(&config.signer)?
+        .context("--private-key, --keystore or --mnemonic is required to sign")?;
+    let payload = match config.file.to_str() {
+        Some("-") => {
+            let mut payload = String::new();
+            std::io::stdin().read_to_string(&mut payload)?;
+            payload
+        }
+        _ => std::fs::read_to_string(&config.file)
+            .map_err(|e| eyre!("Failed to read {}: {e}", config.file.display()))?,
+    };
+    let typed_data: TypedData =
+        serde_json::from_str(&payload).map_err(|e| eyre!("Invalid EIP-712 payload: {e}"))?;
+    let digest = typed_data
+        .encode_eip712()
+        .map_err(|e| eyre!("Failed to hash the EIP-712 payload: {e}"))?;
+    let signature = wallet.sign_typed_data(&typed_data).await?;
+
+    println!("{}", "=".repeat(80));
+    println!("TYPED DATA {}", typed_data.primary_type);
+    if let Some(name) = &typed_data.domain.name {
+        println!("domain: {name}");
+    }
+    if let Some(chain_id) = typed_data.domain.chain_id {
+        println!("chain id: {chain_id}");
+    }
+    if let Some(contract) = typed_data.domain.verifying_contract {
+        println!("verifying contract: {contract:?}");
+    }
+    println!("signer: {:?}", wallet.address());
+    println!("digest: {:?}", H256(digest));
+    println!("v: {}", signature.v);
+    println!("r: {:?}", H256::from_uint(&signature.r));
+    println!("s: {:?}", H256::from_uint(&signature.s));
+    println!("signature: 0x{signature}");
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use crate::cli_config::ConvertConfig;
+use ethers::types::U256;
+use ethers::utils::{format_units, parse_units, Units};
+use eyre::{eyre, Result};
+
+// Units `convert` prints every amount in when no `--to` is given
+const COMMON_UNITS: [(&str, u32); 3] = [("wei", 0), ("gwei", 9), ("ether", 18)];
+
+// Amount of base units in a unit of `decimals`, without the trailing zeros of
+// the fraction, e.g. `1.5` rather than `1.500000000000000000`
+pub fn format_amount(value: U256, decimals: u32) -> String {
+    let formatted = format_units(value, decimals).unwrap_or_else(|_| value.to_string());
+    match formatted.contains('.') {
+        true => formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_owned(),
+        false => formatted,
+    }
+}
+
+// Wei amount such as a balance or a cost, e.g. `0.0125 ETH`
+pub fn format_eth(wei: U256) -> String {
+    format!("{} ETH", format_amount(wei, 18))
+}
+
+// Wei amount such as a gas price, e.g. `1.5 gwei`
+pub fn format_gwei(wei: U256) -> String {
+    format!("{} gwei", format_amount(wei, 9))
+}
+
+// Decimals of a unit given by name, e.g. `gwei`, or as a number of decimals,
+// e.g. `6` for a token such as USDC
+pub fn unit_decimals(unit: &str) -> Result<u32> {
+    let unit = unit.trim();
+    if let Ok(decimals) = unit.parse::<u32>() {
+        return Ok(Units::try_from(decimals)
+            .map_err(|e| eyre!("Invalid decimals {unit}: {e}"))?
+            .as_num());
+    }
+    Ok(Units::try_from(unit)
+        .map_err(|e| eyre!("Unknown unit {unit:?}: {e}"))?
+        .as_num())
+}
+
+// Entry point of the `convert` subcommand: print an amount in another unit,
+// or in wei, gwei and ether
+pub fn convert(config: ConvertConfig) -> Result<()> {
+    let value = config.value.trim();
+    // The unit may follow the number, e.g. `1.5ether`
+    let (amount, suffix) = match value.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(index) => (value[..index].trim(), Some(&value[index..])),
+        None => (value, None),
+    };
+    let from = match (suffix, &config.from) {
+        (Some(_), Some(_)) => {
+            return Err(eyre!("{value:?} has a unit already, leave out --from"));
+        }
+        (Some(unit), None) => unit_decimals(unit)?,
+        (None, Some(unit)) => unit_decimals(unit)?,
+        (None, None) => 0,
+    };
+    let base: U256 = parse_units(amount, from)
+        .map_err(|e| eyre!("Invalid amount {value:?}: {e}"))?
+        .into();
+
+    match &config.to {
+        Some(unit) => println!("{}", format_amount(base, unit_decimals(unit)?)),
+        None => {
+            for (unit, decimals) in COMMON_UNITS {
+                println!("{:>40} {unit}", format_amount(base, decimals));
+            }
+        }
+    }
+    Ok(())
+}
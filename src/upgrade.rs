@@ -0,0 +1,182 @@
+use crate::artifacts::ContractSource;
+use crate::calls::execute_calls;
+use crate::chains::chain_info;
+use crate::cli_config::UpgradeConfig;
+use crate::compiler::{build_project, check_contract_sizes, compile, contracts_path};
+use crate::deployer::DeployContext;
+use crate::fees::resolve_fees;
+use crate::gas_report::GasReport;
+use crate::journal::{journal_path, Journal};
+use crate::lock::DeployLock;
+use crate::manifest::Deployment;
+use crate::proxy::{encode_initializer, upgrade_call};
+use crate::signer::{ensure_funded, wallet_from_config};
+use crate::steps::StepRegistry;
+use crate::storage_layout::upgrade_problems;
+use crate::{rpc, safe};
+use ethers::middleware::SignerMiddleware;
+use ethers::signers::Signer;
+use ethers::types::Bytes;
+use ethers_providers::Middleware;
+use eyre::{eyre, ContextCompat, Result};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Entry point of the `upgrade` subcommand: check the new implementation's
+// storage layout against the current one, deploy it and point the proxy at it
+pub async fn upgrade(config: UpgradeConfig) -> Result<()> {
+    let provider = rpc::connect(&config.rpc_url)?.interval(Duration::from_millis(10));
+    let chain_id = provider.get_chainid().await?.as_u64();
+    // Deployments and upgrades of a network run one at a time
+    let _lock = DeployLock::acquire(chain_id, "upgrade", config.force_unlock)?;
+
+    let mut deployment = Deployment::load(chain_id, &config.name)?;
+    let mut proxy = deployment
+        .proxy
+        .clone()
+        .with_context(|| format!("{} is not deployed behind a proxy", config.name))?;
+    let current = Deployment::load(chain_id, &proxy.implementation_name)?;
+    let contract_name = config
+        .contract_name
+        .clone()
+        .unwrap_or_else(|| current.contract_name.clone());
+    println!(
+        "Upgrading {} ({} proxy at {:?}) from {} to {contract_name}",
+        config.name, proxy.kind, deployment.address, current.contract_name
+    );
+
+    let wallet = wallet_from_config(&config.signer)?
+        .context("--private-key, --keystore or --mnemonic is required to upgrade")?
+        .with_chain_id(chain_id);
+    // With --safe the Safe upgrades the proxy, the signer only deploys the
+    // new implementation and proposes the upgrade
+    let upgrader = config.safe.safe.unwrap_or(wallet.address());
+    if let Some(admin) = proxy.admin {
+        if admin != upgrader {
+            return Err(eyre!(
+                "Only the proxy admin {admin:?} can upgrade {}",
+                config.name
+            ));
+        }
+    }
+    ensure_funded(&provider, wallet.address()).await?;
+
+    let project = compile(contracts_path(), None, &config.compiler).await?;
+    check_contract_sizes(&project, config.compiler.size_limit)?;
+    let contracts = ContractSource::Compiled(project);
+    match (
+        &current.storage_layout,
+        contracts.storage_layout(&contract_name),
+    ) {
+        (Some(old), Some(new)) => {
+            let problems = upgrade_problems(old, &new);
+            if problems.is_empty() {
+                println!(
+                    "Storage layout is compatible with {}",
+                    current.contract_name
+                );
+            } else {
+                for problem in &problems {
+                    println!("UNSAFE: {problem}");
+                }
+                if !config.force {
+                    return Err(eyre!(
+                        "{} storage layout change(s) make the upgrade unsafe, use --force to upgrade anyway",
+                        problems.len()
+                    ));
+                }
+                println!("Upgrading anyway because of --force");
+            }
+        }
+        _ if config.force => println!("No storage layout to compare, upgrading because of --force"),
+        _ => {
+            return Err(eyre!(
+                "No storage layout to compare {} against, use --force to upgrade without checking",
+                proxy.implementation_name
+            ))
+        }
+    }
+
+    let client = Arc::new(SignerMiddleware::new(provider.clone(), wallet));
+    let fees = resolve_fees(&provider, &chain_info(chain_id)?.fee_config(&config.fees)).await?;
+    println!("fees: {fees}");
+    let journal = Journal::open(
+        journal_path(chain_id, &format!("{}.upgrade", config.name)),
+        false,
+    )?;
+    let mut context = DeployContext {
+        client,
+        chain_id,
+        contracts,
+        fees,
+        journal,
+        salt: None,
+        libraries: BTreeMap::new(),
+        compiler_settings: build_project(contracts_path(), None, &config.compiler)?
+            .solc_config
+            .settings,
+        gas_report: GasReport::default(),
+        confirmations: config.confirmations,
+        nonce: None,
+        fee_bump: None,
+        from: None,
+        steps: StepRegistry::default(),
+    };
+    let implementation = context
+        .deploy_contract(
+            &proxy.implementation_name,
+            &contract_name,
+            &config.constructor_args,
+            &[],
+        )
+        .await?;
+
+    let data = match &config.call {
+        Some(method) => encode_initializer(&implementation.abi, method, &config.call_args)?,
+        None => Bytes::default(),
+    };
+    let (upgrade_abi, call) =
+        upgrade_call(proxy.kind, &current.abi, implementation.address, &data)?;
+    if config.safe.safe.is_some() {
+        let tx = call.to_transaction(&upgrade_abi, deployment.address)?;
+        safe::propose(
+            Arc::new(provider),
+            context.client.signer(),
+            &config.safe,
+            deployment.address,
+            tx.value.unwrap_or_default(),
+            tx.data.unwrap_or_default(),
+        )
+        .await?;
+        context.journal.finish()?;
+        println!(
+            "The manifest of {} is left as is, the proxy still points at {:?}",
+            config.name, proxy.implementation
+        );
+        return Ok(());
+    }
+    execute_calls(
+        context.client.as_ref(),
+        &upgrade_abi,
+        deployment.address,
+        &[call],
+        None,
+        Some(&context.fees),
+        Some(&mut context.journal),
+        context.confirmations,
+        context.nonce.as_mut(),
+        context.fee_bump.as_ref(),
+    )
+    .await?;
+    context.journal.finish()?;
+
+    // The proxy keeps its address but now speaks the new implementation's ABI
+    proxy.implementation = implementation.address;
+    deployment.proxy = Some(proxy);
+    deployment.contract_name = contract_name;
+    deployment.abi = implementation.abi;
+    deployment.compiler = implementation.compiler;
+    println!("Deployment manifest: {}", deployment.save()?.display());
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use crate::abi_args::parse_tokens;
+use crate::chains::ChainInfo;
+use crate::cli_config::{CompilerConfig, VerifyConfig};
+use crate::compiler::{build_project, compile, compiler_version, contracts_dir, contracts_path};
+use crate::contract_abi::load_abi;
+use crate::manifest::{CompilerInfo, Deployment};
+use ethers::etherscan::verify::{CodeFormat, VerifyContract};
+use ethers::types::Address;
+use eyre::{eyre, ContextCompat, Result};
+use std::time::Duration;
+
+// How often and how many times the verification status is polled
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const STATUS_POLL_ATTEMPTS: usize = 24;
+
+// Submit the standard-json input of a contract to Etherscan and wait until
+// the verification either passes or fails. The compiler version and settings
+// are detected from the project unless recorded ones are given.
+pub async fn verify_contract(
+    chain: &ChainInfo,
+    api_key: &str,
+    address: Address,
+    contract_name: &str,
+    constructor_args: &[u8],
+    compiler: Option<&CompilerInfo>,
+) -> Result<()> {
+    let project = build_project(contracts_path(), None, &CompilerConfig::default())?;
+    let contract_path =
+        std::fs::canonicalize(contracts_dir().join(format!("{contract_name}.sol")))?;
+    let mut input = project.standard_json_input(&contract_path)?;
+
+    // Sources in the standard-json input are relative to the project root
+    let root = std::fs::canonicalize(project.root())?;
+    let relative_path = contract_path.strip_prefix(&root).unwrap_or(&contract_path);
+    let qualified_name = format!("{}:{contract_name}", relative_path.display());
+
+    let compiler_version = match compiler {
+        Some(compiler) => {
+            input.settings = compiler.settings.clone();
+            etherscan_compiler_version(&compiler.version)?
+        }
+        None => {
+            let output = compile(contracts_path(), None, &CompilerConfig::default()).await?;
+            etherscan_compiler_version(&compiler_version(&output, contract_name)?)?
+        }
+    };
+
+    let optimizer = &input.settings.optimizer;
+    let mut request = VerifyContract::new(
+        address,
+        qualified_name.clone(),
+        serde_json::to_string(&input)?,
+        compiler_version.clone(),
+    )
+    .code_format(CodeFormat::StandardJsonInput)
+    .optimization(optimizer.enabled.unwrap_or_default());
+    if let Some(runs) = optimizer.runs {
+        request = request.runs(runs as u32);
+    }
+    if !constructor_args.is_empty() {
+        request = request.constructor_arguments(Some(ethers::utils::hex::encode(constructor_args)));
+    }
+
+    println!(
+        "Submitting {qualified_name} at {address:?} for verification on {} with {compiler_version}",
+        chain.name
+    );
+    let client = chain.explorer_client(api_key)?;
+    let response = client.submit_contract_verification(&request).await?;
+    if response.status != "1" {
+        return Err(eyre!(
+            "Verification submission failed: {} ({})",
+            response.message,
+            response.result
+        ));
+    }
+    let guid = response.result;
+    println!("Verification submitted, guid: {guid}");
+
+    for _ in 0..STATUS_POLL_ATTEMPTS {
+        tokio::time::sleep(STATUS_POLL_INTERVAL).await;
+        let status = client.check_contract_verification_status(&guid).await?;
+        match status.result.as_str() {
+            "Pending in queue" => println!("Verification pending..."),
+            "Pass - Verified" | "Already Verified" => {
+                println!("Contract verified: {}", status.result);
+                return Ok(());
+            }
+            result => return Err(eyre!("Verification failed: {result}")),
+        }
+    }
+
+    Err(eyre!(
+        "Verification still pending after {} attempts, guid: {guid}",
+        STATUS_POLL_ATTEMPTS
+    ))
+}
+
+// Etherscan expects the full solc build string, e.g. `v0.8.29+commit.ab55807c`,
+// while solc reports versions like `0.8.29+commit.ab55807c.Linux.g++`
+fn etherscan_compiler_version(version: &str) -> Result<String> {
+    let version = version.trim_start_matches('v');
+    let (release, build) = version
+        .split_once('+')
+        .with_context(|| format!("Compiler version {version} has no commit hash"))?;
+    let commit = build
+        .strip_prefix("commit.")
+        .and_then(|build| build.split('.').next())
+        .with_context(|| format!("Compiler version {version} has no commit hash"))?;
+    Ok(format!("v{release}+commit.{commit}"))
+}
+
+// Entry point of the `verify` subcommand. Without an explicit address the
+// contract is resolved from the deployment manifest of the chain.
+pub async fn verify(config: VerifyConfig) -> Result<()> {
+    let (address, contract_name, constructor_args, compiler) = match config.address {
+        Some(address) => {
+            let abi = load_abi(None, Some(&config.contract_name)).await?;
+            let constructor_args = match abi.constructor() {
+                Some(constructor) => parse_tokens(&constructor.inputs, &config.constructor_args)?,
+                None => vec![],
+            };
+            (
+                address,
+                config.contract_name.clone(),
+                ethers::abi::encode(&constructor_args),
+                None,
+            )
+        }
+        None => {
+            let mut deployment = Deployment::load(config.chain.chain_id, &config.contract_name)?;
+            // A proxy is verified through its implementation
+            if let Some(proxy) = &deployment.proxy {
+                deployment = Deployment::load(deployment.chain_id, &proxy.implementation_name)?;
+            }
+            // The manifest may be recorded under a plan name
+            (
+                deployment.address,
+                deployment.contract_name,
+                deployment.encoded_constructor_args.to_vec(),
+                Some(deployment.compiler),
+            )
+        }
+    };
+    let compiler = match (config.compiler_version, compiler) {
+        (Some(version), Some(compiler)) => CompilerInfo {
+            version,
+            settings: compiler.settings,
+        },
+        (None, Some(compiler)) => compiler,
+        // Without a manifest the contract is rebuilt with the given settings
+        (version, None) => CompilerInfo {
+            version: match version {
+                Some(version) => version,
+                None => compiler_version(
+                    &compile(contracts_path(), None, &config.compiler).await?,
+                    &contract_name,
+                )?,
+            },
+            settings: build_project(contracts_path(), None, &config.compiler)?
+                .solc_config
+                .settings,
+        },
+    };
+
+    verify_contract(
+        &config.chain,
+        &config.etherscan_api_key,
+        address,
+        &contract_name,
+        &constructor_args,
+        Some(&compiler),
+    )
+    .await
+}
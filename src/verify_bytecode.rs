@@ -0,0 +1,164 @@
+use crate::cli_config::VerifyBytecodeConfig;
+use crate::compiler::{compile, contracts_path};
+use crate::deployer::find_artifact;
+use crate::rpc;
+use ethers::types::Bytes;
+use ethers::utils::hex;
+use ethers_providers::Middleware;
+use ethers_solc::artifacts::{BytecodeObject, Offsets};
+use eyre::{eyre, ContextCompat, Result};
+use std::collections::BTreeMap;
+
+// Entry point of the `verify-bytecode` subcommand: compare the code live at an
+// address with the project's compiled runtime bytecode. The metadata appended
+// by solc, immutables and linked library addresses are left out of the
+// comparison and reported on their own.
+pub async fn verify_bytecode(config: VerifyBytecodeConfig) -> Result<()> {
+    let provider = rpc::connect(&config.rpc_url)?;
+    let project = compile(contracts_path(), None, &config.compiler).await?;
+    let (artifact, _) = find_artifact(&project, &config.contract_name)?;
+    let deployed = artifact
+        .deployed_bytecode
+        .context("Missing deployed bytecode from contract")?;
+    let bytecode = deployed
+        .bytecode
+        .context("Missing deployed bytecode from contract")?;
+    let link_offsets = bytecode
+        .link_references
+        .values()
+        .flat_map(|libraries| libraries.iter())
+        .map(|(library, offsets)| (library.clone(), offsets.clone()))
+        .collect::<BTreeMap<_, _>>();
+    let local = runtime_bytes(&bytecode.object, &link_offsets)?;
+
+    let onchain = provider.get_code(config.address, None).await?;
+    if onchain.is_empty() {
+        return Err(eyre!("No code deployed at {:?}", config.address));
+    }
+
+    println!("{}", "=".repeat(80));
+    println!(
+        "VERIFY BYTECODE {} at {:?}",
+        config.contract_name, config.address
+    );
+    let (local_code, local_metadata) = split_metadata(&local);
+    let (onchain_code, onchain_metadata) = split_metadata(&onchain);
+    println!(
+        "runtime size: {} bytes on-chain, {} bytes compiled",
+        onchain.len(),
+        local.len()
+    );
+
+    // Immutables are only written by the constructor, libraries on linking
+    let masked = deployed
+        .immutable_references
+        .values()
+        .chain(link_offsets.values())
+        .flatten()
+        .map(|offsets| offsets.start as usize..(offsets.start + offsets.length) as usize)
+        .collect::<Vec<_>>();
+    let differing = match onchain_code.len() == local_code.len() {
+        true => (0..local_code.len())
+            .filter(|index| !masked.iter().any(|range| range.contains(index)))
+            .filter(|index| onchain_code[*index] != local_code[*index])
+            .collect::<Vec<_>>(),
+        false => vec![],
+    };
+
+    for (id, offsets) in &deployed.immutable_references {
+        for offsets in offsets {
+            println!(
+                "immutable (AST id {id}) at {}: {}",
+                offsets.start,
+                word(&onchain, offsets)
+            );
+        }
+    }
+    for (library, offsets) in &link_offsets {
+        if let Some(offsets) = offsets.first() {
+            println!("library {library}: {}", word(&onchain, offsets));
+        }
+    }
+    match (onchain_metadata, local_metadata) {
+        (Some(onchain), Some(local)) if onchain != local => println!(
+            "metadata differs: the sources or settings differ in ways that do not change the code, e.g. comments"
+        ),
+        (Some(_), Some(_)) => println!("metadata matches"),
+        _ => println!("metadata not compared, one of the codes has none"),
+    }
+
+    if onchain_code.len() != local_code.len() {
+        println!("MISMATCH: code sizes differ without metadata");
+        return Err(eyre!(
+            "Code at {:?} is not {} as compiled, check the compiler version and settings",
+            config.address,
+            config.contract_name
+        ));
+    }
+    if let Some(first) = differing.first() {
+        println!(
+            "MISMATCH: {} byte(s) differ, the first at offset {first}",
+            differing.len()
+        );
+        return Err(eyre!(
+            "Code at {:?} is not {} as compiled, check the compiler version and settings",
+            config.address,
+            config.contract_name
+        ));
+    }
+    println!(
+        "MATCH: the code at {:?} is {}",
+        config.address, config.contract_name
+    );
+    Ok(())
+}
+
+// Runtime bytecode with zeros in place of library placeholders
+fn runtime_bytes(
+    object: &BytecodeObject,
+    link_offsets: &BTreeMap<String, Vec<Offsets>>,
+) -> Result<Bytes> {
+    match object {
+        BytecodeObject::Bytecode(bytes) => Ok(bytes.clone()),
+        BytecodeObject::Unlinked(code) => {
+            let mut code = code.trim_start_matches("0x").to_owned();
+            for offsets in link_offsets.values().flatten() {
+                let start = offsets.start as usize * 2;
+                let end = start + offsets.length as usize * 2;
+                if end > code.len() {
+                    return Err(eyre!("Library placeholder beyond the end of the bytecode"));
+                }
+                code.replace_range(start..end, &"0".repeat(end - start));
+            }
+            Ok(hex::decode(code)?.into())
+        }
+    }
+}
+
+// Split off the CBOR metadata solc appends, whose length is in the last two
+// bytes
+fn split_metadata(code: &[u8]) -> (&[u8], Option<&[u8]>) {
+    let Some(length) = code
+        .len()
+        .checked_sub(2)
+        .map(|end| u16::from_be_bytes([code[end], code[end + 1]]) as usize + 2)
+    else {
+        return (code, None);
+    };
+    // CBOR metadata is a map, starting with 0xa1 to 0xb7
+    match code.len().checked_sub(length) {
+        Some(start) if length > 2 && (0xa1..=0xb7).contains(&code[start]) => {
+            (&code[..start], Some(&code[start..]))
+        }
+        _ => (code, None),
+    }
+}
+
+// Value at the offsets of an immutable or library in the on-chain code
+fn word(code: &[u8], offsets: &Offsets) -> String {
+    let start = offsets.start as usize;
+    match code.get(start..start + offsets.length as usize) {
+        Some(value) => format!("0x{}", hex::encode(value)),
+        None => "-".to_owned(),
+    }
+}
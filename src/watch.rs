@@ -0,0 +1,108 @@
+use crate::artifacts::ContractSource;
+use crate::calls::PlannedCall;
+use crate::cli_config::DeployConfig;
+use crate::compiler::{check_contract_sizes, compile, contracts_path, print_compile_summary};
+use crate::deployer::{deploy_all, DeployContext};
+use crate::gas_report::GasReport;
+use crate::manifest::Deployment;
+use crate::plan::Plan;
+use crate::price_feed::usd_price;
+use eyre::{eyre, Result};
+use notify::{Event, RecursiveMode, Watcher};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// Editors often write a file in several steps, changes closer together than
+// this are handled as one
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+// Watch the contract sources and, on every change, recompile and redeploy to
+// the local Anvil chain, printing the new addresses. Runs until interrupted.
+// A failing build or deployment is reported and the next change awaited.
+pub async fn watch_and_redeploy(
+    context: &mut DeployContext,
+    config: &DeployConfig,
+    plan: Option<&Plan>,
+    run_name: &str,
+    calls: &[PlannedCall],
+) -> Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        if let Ok(event) = event {
+            let _ = sender.send(event);
+        }
+    })?;
+    watcher.watch(contracts_path(), RecursiveMode::Recursive)?;
+
+    loop {
+        println!("{}", "=".repeat(80));
+        println!(
+            "WATCHING {} for changes, press Ctrl+C to stop",
+            contracts_path().display()
+        );
+        let changed = next_change(&mut receiver).await?;
+        println!("Changed: {}", changed.join(", "));
+
+        match redeploy(context, config, plan, run_name, calls).await {
+            Ok(deployments) => {
+                for deployment in deployments {
+                    println!("REDEPLOYED {} at {:?}", deployment.name, deployment.address);
+                }
+            }
+            Err(e) => println!("Redeploy failed: {e}"),
+        }
+    }
+}
+
+async fn redeploy(
+    context: &mut DeployContext,
+    config: &DeployConfig,
+    plan: Option<&Plan>,
+    run_name: &str,
+    calls: &[PlannedCall],
+) -> Result<Vec<Deployment>> {
+    let output = compile(contracts_path(), None, &config.compiler).await?;
+    print_compile_summary(&output);
+    check_contract_sizes(&output, config.compiler.size_limit)?;
+    context.contracts = ContractSource::Compiled(output);
+    // Libraries deployed on the way may have changed too
+    context.libraries = config.libraries.iter().cloned().collect();
+    context.gas_report = GasReport::default();
+
+    let deployments = deploy_all(context, config, plan, run_name, calls).await?;
+    context.journal.finish()?;
+    if let Some(format) = config.gas_report {
+        let price = usd_price(context.client.as_ref(), &config.price_feed).await;
+        context.gas_report.print(format, price)?;
+    }
+    Ok(deployments)
+}
+
+// Wait for a change to a Solidity source and return the changed files
+async fn next_change(receiver: &mut mpsc::UnboundedReceiver<Event>) -> Result<Vec<String>> {
+    let mut changed = vec![];
+    while changed.is_empty() {
+        let event = receiver
+            .recv()
+            .await
+            .ok_or_else(|| eyre!("File watcher stopped"))?;
+        collect_sources(&event, &mut changed);
+    }
+    while let Ok(Some(event)) = tokio::time::timeout(DEBOUNCE, receiver.recv()).await {
+        collect_sources(&event, &mut changed);
+    }
+    Ok(changed)
+}
+
+fn collect_sources(event: &Event, changed: &mut Vec<String>) {
+    if event.kind.is_access() {
+        return;
+    }
+    for path in &event.paths {
+        let is_source = path.extension().is_some_and(|extension| extension == "sol");
+        let path = path.display().to_string();
+        if is_source && !changed.contains(&path) {
+            changed.push(path);
+        }
+    }
+}
@@ -0,0 +1,522 @@
+use crate::chains;
+use crate::cli_config::{CompilerConfig, DeployConfig, Language};
+use crate::compiler::{build_project, contracts_path};
+use crate::deployer::encode_constructor_args;
+use crate::error::DeployError;
+use crate::interact::print_receipt;
+use crate::manifest::{CompilerInfo, Deployment};
+use crate::progress::Progress;
+use crate::revert::revert_error;
+use crate::units::format_eth;
+use crate::{audit, rpc, signer};
+use ethers::abi::{parse_abi, Abi, Token};
+use ethers::contract::BaseContract;
+use ethers::prelude::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{
+    Address, BlockNumber, Bytes, Eip1559TransactionRequest, Signature, H160, H256, U256,
+};
+use ethers::utils::{keccak256, rlp::RlpStream};
+use ethers_providers::Middleware;
+use ethers_solc::{CompilerInput, Solc};
+use eyre::{eyre, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+// ContractDeployer system contract, which every contract of zkSync Era is
+// deployed through
+const CONTRACT_DEPLOYER: Address = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x80, 0x06,
+]);
+// Type of the EIP-712 transactions of zkSync Era
+const EIP712_TX_TYPE: u8 = 0x71;
+// Gas per byte of published data the transaction accepts, the default of the
+// zkSync SDKs
+const DEFAULT_GAS_PER_PUBDATA: u64 = 50_000;
+const DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId)";
+const TRANSACTION_TYPE: &str = "Transaction(uint256 txType,uint256 from,uint256 to,uint256 gasLimit,uint256 gasPerPubdataByteLimit,uint256 maxFeePerGas,uint256 maxPriorityFeePerGas,uint256 paymaster,uint256 nonce,uint256 value,bytes data,bytes32[] factoryDeps,bytes paymasterInput)";
+
+// Contract compiled by zksolc for EraVM
+struct ZkContract {
+    abi: Abi,
+    bytecode: Bytes,
+    // Bytecode of the contracts it creates itself, which the chain has to
+    // know before it runs
+    factory_deps: Vec<Bytes>,
+}
+
+// Standard-JSON output of zksolc, the solc one with the factory dependencies
+// of every contract by bytecode hash
+#[derive(Deserialize)]
+struct ZksolcOutput {
+    #[serde(default)]
+    errors: Vec<ZksolcError>,
+    #[serde(default)]
+    contracts: BTreeMap<String, BTreeMap<String, ZksolcContract>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ZksolcError {
+    severity: String,
+    message: String,
+    formatted_message: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ZksolcContract {
+    #[serde(default)]
+    abi: Abi,
+    evm: Option<ZksolcEvm>,
+    // `<source>:<contract>` of every dependency, by bytecode hash
+    #[serde(default)]
+    factory_dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct ZksolcEvm {
+    bytecode: Option<ZksolcBytecode>,
+}
+
+#[derive(Deserialize)]
+struct ZksolcBytecode {
+    object: String,
+}
+
+// Body of `zks_estimateFee`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FeeRequest<'a> {
+    from: Address,
+    to: Address,
+    data: &'a Bytes,
+    value: U256,
+    eip712_meta: Eip712Meta,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Eip712Meta {
+    gas_per_pubdata: U256,
+    // The node takes the bytecode as arrays of bytes rather than hex
+    factory_deps: Vec<Vec<u8>>,
+}
+
+// Answer of `zks_estimateFee`
+#[derive(Deserialize, Debug)]
+struct Fee {
+    gas_limit: U256,
+    gas_per_pubdata_limit: U256,
+    max_fee_per_gas: U256,
+    max_priority_fee_per_gas: U256,
+}
+
+// EIP-712 transaction of zkSync Era. Deployments call the ContractDeployer
+// with the hash of the bytecode, the bytecode itself travels in
+// `factory_deps`.
+struct Eip712Transaction {
+    chain_id: u64,
+    from: Address,
+    to: Address,
+    nonce: U256,
+    gas_limit: U256,
+    gas_per_pubdata: U256,
+    max_fee_per_gas: U256,
+    max_priority_fee_per_gas: U256,
+    value: U256,
+    data: Bytes,
+    factory_deps: Vec<Bytes>,
+}
+
+impl Eip712Transaction {
+    // Digest the sender signs, in the `zkSync` version 2 domain. The
+    // transaction has no paymaster.
+    fn signing_hash(&self) -> Result<H256> {
+        let mut factory_deps = vec![];
+        for dep in &self.factory_deps {
+            factory_deps.extend_from_slice(hash_bytecode(dep)?.as_bytes());
+        }
+        let address = |address: Address| Token::Uint(U256::from_big_endian(address.as_bytes()));
+        let hash = |data: &[u8]| Token::FixedBytes(keccak256(data).to_vec());
+        let struct_hash = keccak256(ethers::abi::encode(&[
+            hash(TRANSACTION_TYPE.as_bytes()),
+            Token::Uint(EIP712_TX_TYPE.into()),
+            address(self.from),
+            address(self.to),
+            Token::Uint(self.gas_limit),
+            Token::Uint(self.gas_per_pubdata),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::Uint(U256::zero()),
+            Token::Uint(self.nonce),
+            Token::Uint(self.value),
+            hash(&self.data),
+            hash(&factory_deps),
+            hash(&[]),
+        ]));
+        let domain_separator = keccak256(ethers::abi::encode(&[
+            hash(DOMAIN_TYPE.as_bytes()),
+            hash(b"zkSync"),
+            hash(b"2"),
+            Token::Uint(self.chain_id.into()),
+        ]));
+        let mut digest = vec![0x19, 0x01];
+        digest.extend_from_slice(&domain_separator);
+        digest.extend_from_slice(&struct_hash);
+        Ok(keccak256(digest).into())
+    }
+
+    // `0x71 || rlp(fields)`, the encoding zkSync Era nodes take in
+    // `eth_sendRawTransaction`
+    fn rlp_signed(&self, signature: &Signature) -> Bytes {
+        let mut rlp = RlpStream::new_list(16);
+        rlp.append(&self.nonce);
+        rlp.append(&self.max_priority_fee_per_gas);
+        rlp.append(&self.max_fee_per_gas);
+        rlp.append(&self.gas_limit);
+        rlp.append(&self.to);
+        rlp.append(&self.value);
+        rlp.append(&self.data);
+        // y parity instead of v
+        rlp.append(&(signature.v - 27));
+        rlp.append(&signature.r);
+        rlp.append(&signature.s);
+        rlp.append(&self.chain_id);
+        rlp.append(&self.from);
+        rlp.append(&self.gas_per_pubdata);
+        rlp.begin_list(self.factory_deps.len());
+        for dep in &self.factory_deps {
+            rlp.append(dep);
+        }
+        // Custom signature, only for smart accounts, and paymaster parameters
+        rlp.append_empty_data();
+        rlp.begin_list(0);
+        let mut raw = vec![EIP712_TX_TYPE];
+        raw.extend_from_slice(&rlp.out());
+        raw.into()
+    }
+}
+
+// Entry point of `deploy --target zksync`: compile with zksolc, deploy the
+// contract of `config` through the ContractDeployer and return its deployment
+pub async fn deploy(config: DeployConfig) -> Result<Deployment> {
+    let unsupported = [
+        ("--plan", config.plan.is_some()),
+        ("--all", config.all),
+        ("--proxy", config.proxy.is_some()),
+        ("--create2", config.create2),
+        ("--libraries", !config.libraries.is_empty()),
+        ("--artifacts", config.artifacts.is_some()),
+        ("--post-deploy", config.calls.is_some()),
+        ("--dry-run", config.dry_run),
+        ("--watch", config.watch),
+        ("--safe", config.safe.safe.is_some()),
+        (
+            "--smart-account",
+            config.smart_account.smart_account.is_some(),
+        ),
+        ("--ens-name", config.ens_name.is_some()),
+        ("--gas-report", config.gas_report.is_some()),
+        ("--verify", config.verify),
+        ("--legacy", config.fees.legacy),
+        ("--language yul", config.compiler.language == Language::Yul),
+    ];
+    if let Some((flag, _)) = unsupported.iter().find(|(_, given)| *given) {
+        return Err(eyre!("{flag} is not supported with --target zksync"));
+    }
+    let rpc_url = config
+        .rpc_url
+        .as_deref()
+        .context("--rpc-url of a zkSync Era node is required with --target zksync")?;
+    let contract_name = config
+        .contract_name
+        .as_deref()
+        .context("--contract-name is required with --target zksync")?;
+    let wallet: LocalWallet = signer::wallet_from_config(&config.signer)?
+        .context("--private-key, --keystore or --mnemonic is required with --target zksync")?;
+
+    let provider = rpc::connect_with_ws(rpc_url, config.ws_url.as_deref()).await?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let chain = match &config.chain {
+        Some(chain) => {
+            chain.check(chain_id)?;
+            chain.clone()
+        }
+        None => chains::chain_info(chain_id)?,
+    };
+    println!("Deploying to zkSync Era chain {} ({chain_id})", chain.name);
+    let from = wallet.address();
+    let balance = signer::ensure_funded(&provider, from).await?;
+    println!("Sender {from:?} balance: {}", format_eth(balance));
+
+    let (contracts, compiler) = compile(&config.compiler, &config.zksolc).await?;
+    let contract = contracts
+        .get(contract_name)
+        .ok_or_else(|| DeployError::ArtifactMissing {
+            contract: contract_name.to_owned(),
+        })?;
+    if contract.bytecode.is_empty() {
+        return Err(eyre!(
+            "Contract {contract_name} has no bytecode, it is abstract or an interface"
+        ));
+    }
+    let constructor_args =
+        encode_constructor_args(&contract.abi, contract_name, &config.constructor_args)?;
+    let encoded_constructor_args: Bytes = ethers::abi::encode(&constructor_args).into();
+
+    let deployer = BaseContract::from(parse_abi(&[
+        "function create(bytes32 salt, bytes32 bytecodeHash, bytes input) payable returns (address)",
+    ])?);
+    let data = deployer.encode(
+        "create",
+        (
+            H256::zero(),
+            hash_bytecode(&contract.bytecode)?,
+            encoded_constructor_args.clone(),
+        ),
+    )?;
+    let mut factory_deps = vec![contract.bytecode.clone()];
+    factory_deps.extend(contract.factory_deps.iter().cloned());
+
+    let what = format!("Deployment of {contract_name}");
+    let fee: Fee = provider
+        .request(
+            "zks_estimateFee",
+            [FeeRequest {
+                from,
+                to: CONTRACT_DEPLOYER,
+                data: &data,
+                value: U256::zero(),
+                eip712_meta: Eip712Meta {
+                    gas_per_pubdata: DEFAULT_GAS_PER_PUBDATA.into(),
+                    factory_deps: factory_deps.iter().map(|dep| dep.to_vec()).collect(),
+                },
+            }],
+        )
+        .await
+        .map_err(|e| revert_error(e, Some(&contract.abi), &what))?;
+    let nonce = provider
+        .get_transaction_count(from, Some(BlockNumber::Pending.into()))
+        .await?;
+    let tx = Eip712Transaction {
+        chain_id,
+        from,
+        to: CONTRACT_DEPLOYER,
+        nonce,
+        gas_limit: fee.gas_limit,
+        gas_per_pubdata: fee.gas_per_pubdata_limit,
+        max_fee_per_gas: config.fees.max_fee_per_gas.unwrap_or(fee.max_fee_per_gas),
+        max_priority_fee_per_gas: config
+            .fees
+            .max_priority_fee_per_gas
+            .unwrap_or(fee.max_priority_fee_per_gas),
+        value: U256::zero(),
+        data,
+        factory_deps,
+    };
+    println!(
+        "gas limit: {}, max fee per gas: {}, max priority fee per gas: {}",
+        tx.gas_limit, tx.max_fee_per_gas, tx.max_priority_fee_per_gas
+    );
+    let signature = wallet.sign_hash(tx.signing_hash()?)?;
+
+    println!("{}", "=".repeat(80));
+    println!("DEPLOYING {contract_name}");
+    let pending_tx = provider
+        .send_raw_transaction(tx.rlp_signed(&signature))
+        .await
+        .map_err(|e| revert_error(e, Some(&contract.abi), &what))?;
+    let transaction = pending_tx.tx_hash();
+    // The audit log records the call of the ContractDeployer, without the
+    // bytecode it came with
+    let audited: TypedTransaction = Eip1559TransactionRequest::new()
+        .from(from)
+        .to(CONTRACT_DEPLOYER)
+        .nonce(nonce)
+        .chain_id(chain_id)
+        .data(tx.data.clone())
+        .into();
+    audit::record(&provider, "deploy", &audited, transaction).await;
+    println!("Transaction hash: {transaction:?}");
+    let receipt = rpc::confirm(&provider, pending_tx, config.confirmations)
+        .await?
+        .ok_or(DeployError::Dropped { transaction })?;
+    print_receipt(&receipt);
+    if receipt.status.is_some_and(|status| status.is_zero()) {
+        return Err(eyre!("{what} reverted in transaction {transaction:?}"));
+    }
+
+    // The ContractDeployer emits `ContractDeployed(deployer, bytecodeHash,
+    // contract)`, after those of the contracts the constructor created
+    let deployed_topic = H256(keccak256("ContractDeployed(address,bytes32,address)"));
+    let address = receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == CONTRACT_DEPLOYER)
+        .filter(|log| log.topics.first() == Some(&deployed_topic))
+        .filter(|log| log.topics.get(1) == Some(&H256::from(from)))
+        .filter_map(|log| log.topics.get(3))
+        .last()
+        .map(|topic| Address::from(*topic))
+        .context("The deployment emitted no ContractDeployed event")?;
+
+    let deployment = Deployment {
+        name: contract_name.to_owned(),
+        contract_name: contract_name.to_owned(),
+        chain_id,
+        address,
+        transaction_hash: receipt.transaction_hash,
+        block_number: receipt.block_number.map(|number| number.as_u64()),
+        deployer: from,
+        abi: contract.abi.clone(),
+        constructor_args: config.constructor_args.clone(),
+        encoded_constructor_args,
+        compiler,
+        salt: None,
+        proxy: None,
+        storage_layout: None,
+        libraries: BTreeMap::new(),
+    };
+    println!("Contract deployed at {address:?}");
+    println!("Deployment manifest: {}", deployment.save()?.display());
+    Ok(deployment)
+}
+
+// Compile the project with zksolc, which drives solc through its
+// standard-JSON interface, and return the contracts by name with the solc
+// version and settings they were built with
+async fn compile(
+    compiler: &CompilerConfig,
+    zksolc: &Path,
+) -> Result<(BTreeMap<String, ZkContract>, CompilerInfo)> {
+    let project = build_project(contracts_path(), None, compiler)?;
+    let settings = project.solc_config.settings.clone();
+    let input = CompilerInput::with_sources(project.paths.read_input_files()?)
+        .into_iter()
+        .next()
+        .context("No Solidity sources to compile")?
+        .settings(settings.clone())
+        .with_remappings(project.paths.remappings.clone());
+    // zksolc wraps the solc on the PATH, or the one of `SOLC_PATH`
+    let solc = Solc::default();
+    let solc_version = solc.version()?;
+
+    let version = tokio::process::Command::new(zksolc)
+        .arg("--version")
+        .output()
+        .await
+        .map_err(|e| {
+            eyre!(
+                "Failed to run {}: {e}, install zksolc or point --zksolc at it",
+                zksolc.display()
+            )
+        })?;
+    let zksolc_version = String::from_utf8_lossy(&version.stdout).trim().to_owned();
+    println!("Compiling with {zksolc_version} and solc {solc_version}");
+
+    let _progress = Progress::spinner("Compiling for zkSync Era");
+    let mut child = tokio::process::Command::new(zksolc)
+        .arg("--standard-json")
+        .arg("--solc")
+        .arg(&solc.solc)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().context("zksolc has no stdin")?;
+    stdin.write_all(&serde_json::to_vec(&input)?).await?;
+    drop(stdin);
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "zksolc failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    let output: ZksolcOutput = serde_json::from_slice(&output.stdout)
+        .map_err(|e| eyre!("Failed to parse the output of zksolc: {e}"))?;
+    let errors = output
+        .errors
+        .iter()
+        .filter(|error| error.severity == "error")
+        .map(|error| {
+            error
+                .formatted_message
+                .clone()
+                .unwrap_or_else(|| error.message.clone())
+        })
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return Err(DeployError::CompileFailed { errors }.into());
+    }
+
+    // Dependencies are referred to as `<source>:<contract>`
+    let mut bytecodes = BTreeMap::new();
+    for (source, contracts) in &output.contracts {
+        for (name, contract) in contracts {
+            let object = contract
+                .evm
+                .as_ref()
+                .and_then(|evm| evm.bytecode.as_ref())
+                .map_or("", |bytecode| bytecode.object.as_str());
+            let bytecode: Bytes = object
+                .parse()
+                .map_err(|e| eyre!("Invalid bytecode of {name} in the output of zksolc: {e}"))?;
+            bytecodes.insert(format!("{source}:{name}"), bytecode);
+        }
+    }
+    let mut zk_contracts = BTreeMap::new();
+    for (source, contracts) in output.contracts {
+        for (name, contract) in contracts {
+            let factory_deps = contract
+                .factory_dependencies
+                .values()
+                .map(|dependency| {
+                    bytecodes.get(dependency).cloned().with_context(|| {
+                        format!("Factory dependency {dependency} of {name} not compiled")
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let zk_contract = ZkContract {
+                abi: contract.abi,
+                bytecode: bytecodes[&format!("{source}:{name}")].clone(),
+                factory_deps,
+            };
+            zk_contracts.insert(name, zk_contract);
+        }
+    }
+    println!("Compiled {} contract(s) for EraVM", zk_contracts.len());
+    let compiler = CompilerInfo {
+        version: solc_version.to_string(),
+        settings,
+    };
+    Ok((zk_contracts, compiler))
+}
+
+// Hash zkSync Era knows bytecode by: version 1, a zero byte, the length in
+// 32-byte words, then the end of the bytecode's SHA-256
+fn hash_bytecode(bytecode: &[u8]) -> Result<H256> {
+    if bytecode.len() % 32 != 0 {
+        return Err(eyre!(
+            "EraVM bytecode must be made of 32-byte words, got {} bytes",
+            bytecode.len()
+        ));
+    }
+    let words = bytecode.len() / 32;
+    if words % 2 == 0 || words >= 1 << 16 {
+        return Err(eyre!(
+            "EraVM bytecode must have an odd number of words below 65536, got {words}"
+        ));
+    }
+    let mut hash = H256::from_slice(&Sha256::digest(bytecode));
+    hash.0[0] = 1;
+    hash.0[1] = 0;
+    hash.0[2..4].copy_from_slice(&(words as u16).to_be_bytes());
+    Ok(hash)
+}